@@ -26,6 +26,27 @@ pub enum CLMMError {
 
     #[error("Invalid oracle")]
     InvalidOracle,
+
+    #[error("Slippage tolerance exceeded")]
+    SlippageExceeded,
+
+    #[error("Invalid fee")]
+    InvalidFee,
+
+    #[error("Pool is locked")]
+    PoolLocked,
+
+    #[error("Flash loan not repaid")]
+    FlashLoanNotRepaid,
+
+    #[error("Range order already filled")]
+    RangeOrderFilled,
+
+    #[error("Range order not filled")]
+    RangeOrderNotFilled,
+
+    #[error("Vault received less than the required amount")]
+    InsufficientAmountReceived,
 }
 
 impl From<CLMMError> for ProgramError {
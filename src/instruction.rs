@@ -1 +1,938 @@
-// Enum representing instructions
+//! Client-side builders for every `CLMMInstruction`
+//!
+//! Each builder derives the program addresses the processor expects and returns a
+//! ready-to-sign `Instruction` with its accounts in processor order.
+
+use solana_program::{
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+    sysvar,
+};
+use borsh::BorshSerialize;
+use crate::math::mev_protection::MevConfig;
+use crate::processor::CLMMInstruction;
+use crate::processor::swap_route::RouteHop;
+use crate::state::{Pool, TickBitmap};
+use crate::utils::{
+    derive_pool_address, derive_pool_vault_a_address, derive_pool_vault_b_address,
+    derive_pool_authority_address, derive_position_address, derive_position_token_address,
+    derive_tick_address, derive_tick_bitmap_address, derive_oracle_address,
+    derive_protocol_config_address, derive_wsol_address, is_native_mint,
+};
+
+/// Serialize the instruction data and assemble the instruction
+fn build(program_id: &Pubkey, accounts: Vec<AccountMeta>, instruction: CLMMInstruction) -> Instruction {
+    let mut data = Vec::new();
+    instruction
+        .serialize(&mut data)
+        .expect("serializing into a Vec cannot fail");
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Pool vault A, vault B and authority addresses
+fn pool_accounts(program_id: &Pubkey, pool: &Pubkey) -> (Pubkey, Pubkey, Pubkey) {
+    let (vault_a, _) = derive_pool_vault_a_address(program_id, pool);
+    let (vault_b, _) = derive_pool_vault_b_address(program_id, pool);
+    let (pool_authority, _) = derive_pool_authority_address(program_id, pool);
+    (vault_a, vault_b, pool_authority)
+}
+
+/// Mints of a pool and the token programs that own them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolTokens {
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub token_program_a: Pubkey,
+    pub token_program_b: Pubkey,
+}
+
+impl PoolTokens {
+    /// Two mints owned by the classic SPL Token program
+    pub fn new(mint_a: Pubkey, mint_b: Pubkey) -> Self {
+        Self {
+            mint_a,
+            mint_b,
+            token_program_a: spl_token::id(),
+            token_program_b: spl_token::id(),
+        }
+    }
+
+    /// Mints and token programs recorded in a pool account
+    pub fn from_pool(pool: &Pool) -> Self {
+        Self {
+            mint_a: pool.token_a,
+            mint_b: pool.token_b,
+            token_program_a: pool.token_program_a,
+            token_program_b: pool.token_program_b,
+        }
+    }
+
+    /// Token program B and both mints, appended after an instruction's fixed accounts
+    fn trailing_accounts(&self) -> [AccountMeta; 3] {
+        [
+            AccountMeta::new_readonly(self.token_program_b, false),
+            AccountMeta::new_readonly(self.mint_a, false),
+            AccountMeta::new_readonly(self.mint_b, false),
+        ]
+    }
+
+    /// The user's token accounts, with the SOL side replaced by the user's wrapped SOL
+    /// PDA when `native_sol` is set
+    fn user_token_accounts(
+        &self,
+        program_id: &Pubkey,
+        user: &Pubkey,
+        user_token_a: &Pubkey,
+        user_token_b: &Pubkey,
+        native_sol: bool,
+    ) -> (Pubkey, Pubkey) {
+        if native_sol && is_native_mint(&self.mint_a) {
+            (derive_wsol_address(program_id, user).0, *user_token_b)
+        } else if native_sol && is_native_mint(&self.mint_b) {
+            (*user_token_a, derive_wsol_address(program_id, user).0)
+        } else {
+            (*user_token_a, *user_token_b)
+        }
+    }
+}
+
+/// System program and rent sysvar, needed to create the temporary wrapped SOL account
+fn native_sol_accounts() -> [AccountMeta; 2] {
+    [
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ]
+}
+
+/// The signing user, writable when it pays or receives lamports
+fn user_meta(user: &Pubkey, native_sol: bool) -> AccountMeta {
+    if native_sol {
+        AccountMeta::new(*user, true)
+    } else {
+        AccountMeta::new_readonly(*user, true)
+    }
+}
+
+/// Address of the bitmap word holding `tick`
+fn tick_bitmap_address(program_id: &Pubkey, pool: &Pubkey, tick: i32, tick_spacing: u32) -> Pubkey {
+    let (word_position, _) = TickBitmap::position(tick, tick_spacing);
+    derive_tick_bitmap_address(program_id, pool, word_position).0
+}
+
+/// Parameters of a new pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InitializePoolArgs {
+    pub fee: u32,
+    pub tick_spacing: u32,
+    pub initial_sqrt_price_x96: u128,
+}
+
+/// Create an `InitializePool` instruction; the mints in `tokens` may be given in either order
+pub fn initialize_pool(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    tokens: &PoolTokens,
+    args: InitializePoolArgs,
+) -> Instruction {
+    let (token_0, token_program_0, token_1, token_program_1) = if tokens.mint_a < tokens.mint_b {
+        (tokens.mint_a, tokens.token_program_a, tokens.mint_b, tokens.token_program_b)
+    } else {
+        (tokens.mint_b, tokens.token_program_b, tokens.mint_a, tokens.token_program_a)
+    };
+    let (pool, _) = derive_pool_address(program_id, &token_0, &token_1, args.fee);
+    let (vault_a, vault_b, pool_authority) = pool_accounts(program_id, &pool);
+    let (oracle, _) = derive_oracle_address(program_id, &pool);
+
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(pool, false),
+        AccountMeta::new_readonly(token_0, false),
+        AccountMeta::new_readonly(token_1, false),
+        AccountMeta::new(vault_a, false),
+        AccountMeta::new(vault_b, false),
+        AccountMeta::new_readonly(pool_authority, false),
+        AccountMeta::new_readonly(token_program_0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(oracle, false),
+        AccountMeta::new_readonly(token_program_1, false),
+    ];
+
+    build(program_id, accounts, CLMMInstruction::InitializePool {
+        fee: args.fee,
+        tick_spacing: args.tick_spacing,
+        initial_sqrt_price_x96: args.initial_sqrt_price_x96,
+    })
+}
+
+/// Create an `OpenPosition` instruction; `position_mint` is a fresh keypair that must also sign
+pub fn open_position(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    pool: &Pubkey,
+    position_mint: &Pubkey,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Instruction {
+    let (position, _) = derive_position_address(program_id, position_mint);
+    let (position_token_account, _) = derive_position_token_address(program_id, position_mint);
+    let (pool_authority, _) = derive_pool_authority_address(program_id, pool);
+
+    let accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*pool, false),
+        AccountMeta::new(position, false),
+        AccountMeta::new(*position_mint, true),
+        AccountMeta::new(position_token_account, false),
+        AccountMeta::new_readonly(pool_authority, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    build(program_id, accounts, CLMMInstruction::OpenPosition {
+        tick_lower,
+        tick_upper,
+    })
+}
+
+/// Accounts of the position identified by `position_mint`, shared by the liquidity
+/// and fee instructions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionAccounts {
+    pub pool: Pubkey,
+    pub tokens: PoolTokens,
+    /// Locates the bitmap words of the boundary ticks; unused by `collect_fees`
+    pub tick_spacing: u32,
+    pub position_mint: Pubkey,
+    /// The holder's token account for the position token
+    pub holder_token_account: Pubkey,
+    pub user_token_a: Pubkey,
+    pub user_token_b: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+}
+
+/// Amounts for an `AddLiquidity` instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddLiquidityArgs {
+    pub liquidity_delta: u128,
+    pub amount_0_max: u64,
+    pub amount_1_max: u64,
+    pub native_sol: bool,
+}
+
+/// Amounts for an `AddLiquidityByAmounts` instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddLiquidityByAmountsArgs {
+    pub amount_0_desired: u64,
+    pub amount_1_desired: u64,
+    pub amount_0_min: u64,
+    pub amount_1_min: u64,
+    pub native_sol: bool,
+}
+
+/// Amounts for a `RemoveLiquidity` instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoveLiquidityArgs {
+    pub liquidity_delta: u128,
+    pub amount_0_min: u64,
+    pub amount_1_min: u64,
+    pub native_sol: bool,
+}
+
+/// Amounts for a `CollectFees` instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollectFeesArgs {
+    pub amount_0_requested: u64,
+    pub amount_1_requested: u64,
+    pub native_sol: bool,
+}
+
+/// Create an `AddLiquidity` instruction for `position`
+pub fn add_liquidity(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    position: &PositionAccounts,
+    args: AddLiquidityArgs,
+) -> Instruction {
+    let accounts = add_liquidity_accounts(program_id, holder, position, args.native_sol);
+
+    build(program_id, accounts, CLMMInstruction::AddLiquidity {
+        liquidity_delta: args.liquidity_delta,
+        amount_0_max: args.amount_0_max,
+        amount_1_max: args.amount_1_max,
+        native_sol: args.native_sol,
+    })
+}
+
+/// Create an `AddLiquidityByAmounts` instruction for `position`
+pub fn add_liquidity_by_amounts(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    position: &PositionAccounts,
+    args: AddLiquidityByAmountsArgs,
+) -> Instruction {
+    let accounts = add_liquidity_accounts(program_id, holder, position, args.native_sol);
+
+    build(program_id, accounts, CLMMInstruction::AddLiquidityByAmounts {
+        amount_0_desired: args.amount_0_desired,
+        amount_1_desired: args.amount_1_desired,
+        amount_0_min: args.amount_0_min,
+        amount_1_min: args.amount_1_min,
+        native_sol: args.native_sol,
+    })
+}
+
+/// Accounts shared by `AddLiquidity`, `AddLiquidityByAmounts` and `PlaceRangeOrder`
+fn add_liquidity_accounts(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    position: &PositionAccounts,
+    native_sol: bool,
+) -> Vec<AccountMeta> {
+    let PositionAccounts { pool, tokens, tick_spacing, tick_lower, tick_upper, .. } = *position;
+    let (position_account, _) = derive_position_address(program_id, &position.position_mint);
+    let (tick_lower_account, _) = derive_tick_address(program_id, &pool, tick_lower);
+    let (tick_upper_account, _) = derive_tick_address(program_id, &pool, tick_upper);
+    let (vault_a, vault_b, pool_authority) = pool_accounts(program_id, &pool);
+    let (user_token_a, user_token_b) = tokens.user_token_accounts(
+        program_id,
+        holder,
+        &position.user_token_a,
+        &position.user_token_b,
+        native_sol,
+    );
+
+    let mut accounts = vec![
+        AccountMeta::new(*holder, true),
+        AccountMeta::new(pool, false),
+        AccountMeta::new(position_account, false),
+        AccountMeta::new_readonly(position.holder_token_account, false),
+        AccountMeta::new(tick_lower_account, false),
+        AccountMeta::new(tick_upper_account, false),
+        AccountMeta::new(user_token_a, false),
+        AccountMeta::new(user_token_b, false),
+        AccountMeta::new(vault_a, false),
+        AccountMeta::new(vault_b, false),
+        AccountMeta::new_readonly(pool_authority, false),
+        AccountMeta::new_readonly(tokens.token_program_a, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(tick_bitmap_address(program_id, &pool, tick_lower, tick_spacing), false),
+        AccountMeta::new(tick_bitmap_address(program_id, &pool, tick_upper, tick_spacing), false),
+    ];
+    accounts.extend(tokens.trailing_accounts());
+    accounts
+}
+
+/// Create a `RemoveLiquidity` instruction for `position`
+pub fn remove_liquidity(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    position: &PositionAccounts,
+    args: RemoveLiquidityArgs,
+) -> Instruction {
+    let PositionAccounts { pool, tokens, tick_spacing, tick_lower, tick_upper, .. } = *position;
+    let (position_account, _) = derive_position_address(program_id, &position.position_mint);
+    let (tick_lower_account, _) = derive_tick_address(program_id, &pool, tick_lower);
+    let (tick_upper_account, _) = derive_tick_address(program_id, &pool, tick_upper);
+    let (vault_a, vault_b, pool_authority) = pool_accounts(program_id, &pool);
+    let (user_token_a, user_token_b) = tokens.user_token_accounts(
+        program_id,
+        holder,
+        &position.user_token_a,
+        &position.user_token_b,
+        args.native_sol,
+    );
+
+    let mut accounts = vec![
+        user_meta(holder, args.native_sol),
+        AccountMeta::new(pool, false),
+        AccountMeta::new(position_account, false),
+        AccountMeta::new_readonly(position.holder_token_account, false),
+        AccountMeta::new(tick_lower_account, false),
+        AccountMeta::new(tick_upper_account, false),
+        AccountMeta::new(user_token_a, false),
+        AccountMeta::new(user_token_b, false),
+        AccountMeta::new(vault_a, false),
+        AccountMeta::new(vault_b, false),
+        AccountMeta::new_readonly(pool_authority, false),
+        AccountMeta::new_readonly(tokens.token_program_a, false),
+        AccountMeta::new(tick_bitmap_address(program_id, &pool, tick_lower, tick_spacing), false),
+        AccountMeta::new(tick_bitmap_address(program_id, &pool, tick_upper, tick_spacing), false),
+    ];
+    accounts.extend(tokens.trailing_accounts());
+    if args.native_sol {
+        accounts.extend(native_sol_accounts());
+    }
+
+    build(program_id, accounts, CLMMInstruction::RemoveLiquidity {
+        liquidity_delta: args.liquidity_delta,
+        amount_0_min: args.amount_0_min,
+        amount_1_min: args.amount_1_min,
+        native_sol: args.native_sol,
+    })
+}
+
+/// Create a `CollectFees` instruction for `position`
+pub fn collect_fees(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    position: &PositionAccounts,
+    args: CollectFeesArgs,
+) -> Instruction {
+    let PositionAccounts { pool, tokens, tick_lower, tick_upper, .. } = *position;
+    let (position_account, _) = derive_position_address(program_id, &position.position_mint);
+    let (tick_lower_account, _) = derive_tick_address(program_id, &pool, tick_lower);
+    let (tick_upper_account, _) = derive_tick_address(program_id, &pool, tick_upper);
+    let (vault_a, vault_b, pool_authority) = pool_accounts(program_id, &pool);
+    let (user_token_a, user_token_b) = tokens.user_token_accounts(
+        program_id,
+        holder,
+        &position.user_token_a,
+        &position.user_token_b,
+        args.native_sol,
+    );
+
+    let mut accounts = vec![
+        user_meta(holder, args.native_sol),
+        AccountMeta::new(pool, false),
+        AccountMeta::new(position_account, false),
+        AccountMeta::new_readonly(position.holder_token_account, false),
+        AccountMeta::new_readonly(tick_lower_account, false),
+        AccountMeta::new_readonly(tick_upper_account, false),
+        AccountMeta::new(user_token_a, false),
+        AccountMeta::new(user_token_b, false),
+        AccountMeta::new(vault_a, false),
+        AccountMeta::new(vault_b, false),
+        AccountMeta::new_readonly(pool_authority, false),
+        AccountMeta::new_readonly(tokens.token_program_a, false),
+    ];
+    accounts.extend(tokens.trailing_accounts());
+    if args.native_sol {
+        accounts.extend(native_sol_accounts());
+    }
+
+    build(program_id, accounts, CLMMInstruction::CollectFees {
+        amount_0_requested: args.amount_0_requested,
+        amount_1_requested: args.amount_1_requested,
+        native_sol: args.native_sol,
+    })
+}
+
+/// Create a `ClosePosition` instruction for the position identified by `position_mint`
+pub fn close_position(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    position_mint: &Pubkey,
+    holder_token_account: &Pubkey,
+) -> Instruction {
+    let (position, _) = derive_position_address(program_id, position_mint);
+
+    let accounts = vec![
+        AccountMeta::new(*holder, true),
+        AccountMeta::new(position, false),
+        AccountMeta::new(*holder_token_account, false),
+        AccountMeta::new(*position_mint, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    build(program_id, accounts, CLMMInstruction::ClosePosition)
+}
+
+/// Accounts of the range order identified by `position_mint`, which spans the single
+/// tick range starting at `tick_lower`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeOrderAccounts {
+    pub pool: Pubkey,
+    pub tokens: PoolTokens,
+    pub tick_spacing: u32,
+    pub position_mint: Pubkey,
+    /// The holder's token account for the position token
+    pub holder_token_account: Pubkey,
+    /// The holder's token accounts, which fund a placement and receive a settlement
+    pub token_account_a: Pubkey,
+    pub token_account_b: Pubkey,
+    pub tick_lower: i32,
+}
+
+impl RangeOrderAccounts {
+    fn position(&self) -> PositionAccounts {
+        PositionAccounts {
+            pool: self.pool,
+            tokens: self.tokens,
+            tick_spacing: self.tick_spacing,
+            position_mint: self.position_mint,
+            holder_token_account: self.holder_token_account,
+            user_token_a: self.token_account_a,
+            user_token_b: self.token_account_b,
+            tick_lower: self.tick_lower,
+            tick_upper: self.tick_lower + self.tick_spacing as i32,
+        }
+    }
+}
+
+/// Amounts for a `PlaceRangeOrder` instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaceRangeOrderArgs {
+    pub liquidity_delta: u128,
+    pub amount_0_max: u64,
+    pub amount_1_max: u64,
+}
+
+/// Create a `PlaceRangeOrder` instruction for `order`, signed by its holder
+pub fn place_range_order(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    order: &RangeOrderAccounts,
+    args: PlaceRangeOrderArgs,
+) -> Instruction {
+    let accounts = add_liquidity_accounts(program_id, holder, &order.position(), false);
+
+    build(program_id, accounts, CLMMInstruction::PlaceRangeOrder {
+        liquidity_delta: args.liquidity_delta,
+        amount_0_max: args.amount_0_max,
+        amount_1_max: args.amount_1_max,
+    })
+}
+
+/// Create a `SettleRangeOrder` instruction paying the holder of `order`
+///
+/// Anyone may sign as `caller`; the order's token accounts must be owned by the holder.
+pub fn settle_range_order(
+    program_id: &Pubkey,
+    caller: &Pubkey,
+    order: &RangeOrderAccounts,
+) -> Instruction {
+    let PositionAccounts { pool, tokens, tick_spacing, tick_lower, tick_upper, .. } = order.position();
+    let (position, _) = derive_position_address(program_id, &order.position_mint);
+    let (tick_lower_account, _) = derive_tick_address(program_id, &pool, tick_lower);
+    let (tick_upper_account, _) = derive_tick_address(program_id, &pool, tick_upper);
+    let (vault_a, vault_b, pool_authority) = pool_accounts(program_id, &pool);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*caller, true),
+        AccountMeta::new_readonly(pool, false),
+        AccountMeta::new(position, false),
+        AccountMeta::new_readonly(order.holder_token_account, false),
+        AccountMeta::new(tick_lower_account, false),
+        AccountMeta::new(tick_upper_account, false),
+        AccountMeta::new(order.token_account_a, false),
+        AccountMeta::new(order.token_account_b, false),
+        AccountMeta::new(vault_a, false),
+        AccountMeta::new(vault_b, false),
+        AccountMeta::new_readonly(pool_authority, false),
+        AccountMeta::new_readonly(tokens.token_program_a, false),
+        AccountMeta::new(tick_bitmap_address(program_id, &pool, tick_lower, tick_spacing), false),
+        AccountMeta::new(tick_bitmap_address(program_id, &pool, tick_upper, tick_spacing), false),
+    ];
+    accounts.extend(tokens.trailing_accounts());
+
+    build(program_id, accounts, CLMMInstruction::SettleRangeOrder)
+}
+
+/// Accounts shared by `Swap` and `SwapExactOut`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapAccounts {
+    pub pool: Pubkey,
+    pub tokens: PoolTokens,
+    pub user_token_a: Pubkey,
+    pub user_token_b: Pubkey,
+    /// Every bitmap word the price moves through
+    pub bitmap_words: Vec<i16>,
+    /// Every initialized tick the swap may cross, plus the other boundary tick of any
+    /// range order it fills
+    pub ticks: Vec<i32>,
+}
+
+/// Amounts and limits for a `Swap` instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapArgs {
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+    pub sqrt_price_limit: u128,
+    pub zero_for_one: bool,
+    pub max_price_impact_bps: u32,
+    pub native_sol: bool,
+}
+
+/// Amounts and limits for a `SwapExactOut` instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapExactOutArgs {
+    pub amount_out: u64,
+    pub maximum_amount_in: u64,
+    pub sqrt_price_limit: u128,
+    pub zero_for_one: bool,
+    pub max_price_impact_bps: u32,
+    pub native_sol: bool,
+}
+
+/// Account metas shared by `Swap` and `SwapExactOut`
+fn swap_account_metas(
+    program_id: &Pubkey,
+    user: &Pubkey,
+    swap: &SwapAccounts,
+    native_sol: bool,
+) -> Vec<AccountMeta> {
+    let pool = &swap.pool;
+    let (vault_a, vault_b, pool_authority) = pool_accounts(program_id, pool);
+    let (oracle, _) = derive_oracle_address(program_id, pool);
+    let (user_token_a, user_token_b) = swap.tokens.user_token_accounts(
+        program_id,
+        user,
+        &swap.user_token_a,
+        &swap.user_token_b,
+        native_sol,
+    );
+
+    let mut accounts = vec![
+        user_meta(user, native_sol),
+        AccountMeta::new(*pool, false),
+        AccountMeta::new(user_token_a, false),
+        AccountMeta::new(user_token_b, false),
+        AccountMeta::new(vault_a, false),
+        AccountMeta::new(vault_b, false),
+        AccountMeta::new_readonly(pool_authority, false),
+        AccountMeta::new_readonly(swap.tokens.token_program_a, false),
+        AccountMeta::new(oracle, false),
+    ];
+    accounts.extend(swap.tokens.trailing_accounts());
+    if native_sol {
+        accounts.extend(native_sol_accounts());
+    }
+    accounts.extend(swap.bitmap_words.iter().map(|&word_position| {
+        AccountMeta::new_readonly(derive_tick_bitmap_address(program_id, pool, word_position).0, false)
+    }));
+    accounts.extend(swap.ticks.iter().map(|&tick| {
+        AccountMeta::new(derive_tick_address(program_id, pool, tick).0, false)
+    }));
+    accounts
+}
+
+/// Create a `Swap` instruction (exact input)
+pub fn swap(
+    program_id: &Pubkey,
+    user: &Pubkey,
+    accounts: &SwapAccounts,
+    args: SwapArgs,
+) -> Instruction {
+    let accounts = swap_account_metas(program_id, user, accounts, args.native_sol);
+
+    build(program_id, accounts, CLMMInstruction::Swap {
+        amount_in: args.amount_in,
+        minimum_amount_out: args.minimum_amount_out,
+        sqrt_price_limit: args.sqrt_price_limit,
+        zero_for_one: args.zero_for_one,
+        max_price_impact_bps: args.max_price_impact_bps,
+        native_sol: args.native_sol,
+    })
+}
+
+/// Create a `SwapExactOut` instruction
+pub fn swap_exact_out(
+    program_id: &Pubkey,
+    user: &Pubkey,
+    accounts: &SwapAccounts,
+    args: SwapExactOutArgs,
+) -> Instruction {
+    let accounts = swap_account_metas(program_id, user, accounts, args.native_sol);
+
+    build(program_id, accounts, CLMMInstruction::SwapExactOut {
+        amount_out: args.amount_out,
+        maximum_amount_in: args.maximum_amount_in,
+        sqrt_price_limit: args.sqrt_price_limit,
+        zero_for_one: args.zero_for_one,
+        max_price_impact_bps: args.max_price_impact_bps,
+        native_sol: args.native_sol,
+    })
+}
+
+/// One pool of a `SwapRoute`, with the user's token accounts for both of its sides
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapRouteHop {
+    pub pool: Pubkey,
+    pub tokens: PoolTokens,
+    pub user_token_a: Pubkey,
+    pub user_token_b: Pubkey,
+    pub zero_for_one: bool,
+    pub max_price_impact_bps: u32,
+    /// Bitmap words and ticks the hop may touch, as for `swap`
+    pub bitmap_words: Vec<i16>,
+    pub ticks: Vec<i32>,
+}
+
+/// Create a `SwapRoute` instruction
+///
+/// The output account of each hop must be the input account of the next.
+pub fn swap_route(
+    program_id: &Pubkey,
+    user: &Pubkey,
+    hops: &[SwapRouteHop],
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(*user, true)];
+
+    for hop in hops {
+        let (vault_a, vault_b, pool_authority) = pool_accounts(program_id, &hop.pool);
+        let (oracle, _) = derive_oracle_address(program_id, &hop.pool);
+
+        accounts.extend([
+            AccountMeta::new(hop.pool, false),
+            AccountMeta::new(hop.user_token_a, false),
+            AccountMeta::new(hop.user_token_b, false),
+            AccountMeta::new(vault_a, false),
+            AccountMeta::new(vault_b, false),
+            AccountMeta::new_readonly(pool_authority, false),
+            AccountMeta::new(oracle, false),
+            AccountMeta::new_readonly(hop.tokens.token_program_a, false),
+            AccountMeta::new_readonly(hop.tokens.token_program_b, false),
+            AccountMeta::new_readonly(hop.tokens.mint_a, false),
+            AccountMeta::new_readonly(hop.tokens.mint_b, false),
+        ]);
+        accounts.extend(hop.bitmap_words.iter().map(|&word_position| {
+            AccountMeta::new_readonly(derive_tick_bitmap_address(program_id, &hop.pool, word_position).0, false)
+        }));
+        accounts.extend(hop.ticks.iter().map(|&tick| {
+            AccountMeta::new(derive_tick_address(program_id, &hop.pool, tick).0, false)
+        }));
+    }
+
+    let hops = hops
+        .iter()
+        .map(|hop| RouteHop {
+            zero_for_one: hop.zero_for_one,
+            max_price_impact_bps: hop.max_price_impact_bps,
+            bitmap_count: hop.bitmap_words.len() as u8,
+            tick_count: hop.ticks.len() as u8,
+        })
+        .collect();
+
+    build(program_id, accounts, CLMMInstruction::SwapRoute {
+        amount_in,
+        minimum_amount_out,
+        hops,
+    })
+}
+
+/// Create an `IncreaseObservationCardinality` instruction
+pub fn increase_observation_cardinality(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    pool: &Pubkey,
+    cardinality_next: u16,
+) -> Instruction {
+    let (oracle, _) = derive_oracle_address(program_id, pool);
+
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(*pool, false),
+        AccountMeta::new(oracle, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    build(program_id, accounts, CLMMInstruction::IncreaseObservationCardinality { cardinality_next })
+}
+
+/// Create an `InitializeProtocolConfig` instruction, signed by the program's upgrade authority
+pub fn initialize_protocol_config(
+    program_id: &Pubkey,
+    upgrade_authority: &Pubkey,
+    authority: &Pubkey,
+) -> Instruction {
+    let (config, _) = derive_protocol_config_address(program_id);
+    let (program_data, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+
+    let accounts = vec![
+        AccountMeta::new(*upgrade_authority, true),
+        AccountMeta::new(config, false),
+        AccountMeta::new_readonly(program_data, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    build(program_id, accounts, CLMMInstruction::InitializeProtocolConfig { authority: *authority })
+}
+
+/// Create a `SetProtocolFee` instruction
+pub fn set_protocol_fee(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    pool: &Pubkey,
+    protocol_fee_percent: u32,
+) -> Instruction {
+    let (config, _) = derive_protocol_config_address(program_id);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(config, false),
+        AccountMeta::new(*pool, false),
+    ];
+
+    build(program_id, accounts, CLMMInstruction::SetProtocolFee { protocol_fee_percent })
+}
+
+/// Pool and recipient accounts for a `CollectProtocolFees` instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollectProtocolFeesAccounts {
+    pub pool: Pubkey,
+    pub tokens: PoolTokens,
+    pub recipient_token_a: Pubkey,
+    pub recipient_token_b: Pubkey,
+}
+
+/// Create a `CollectProtocolFees` instruction
+pub fn collect_protocol_fees(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    fees: &CollectProtocolFeesAccounts,
+    amount_0_requested: u64,
+    amount_1_requested: u64,
+) -> Instruction {
+    let (config, _) = derive_protocol_config_address(program_id);
+    let (vault_a, vault_b, pool_authority) = pool_accounts(program_id, &fees.pool);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(config, false),
+        AccountMeta::new(fees.pool, false),
+        AccountMeta::new(fees.recipient_token_a, false),
+        AccountMeta::new(fees.recipient_token_b, false),
+        AccountMeta::new(vault_a, false),
+        AccountMeta::new(vault_b, false),
+        AccountMeta::new_readonly(pool_authority, false),
+        AccountMeta::new_readonly(fees.tokens.token_program_a, false),
+    ];
+    accounts.extend(fees.tokens.trailing_accounts());
+
+    build(program_id, accounts, CLMMInstruction::CollectProtocolFees {
+        amount_0_requested,
+        amount_1_requested,
+    })
+}
+
+/// Accounts shared by the pool admin instructions
+fn admin_accounts(authority: &Pubkey, pool: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*pool, false),
+    ]
+}
+
+/// Create a `SetPoolPaused` instruction
+pub fn set_pool_paused(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    pool: &Pubkey,
+    paused: bool,
+) -> Instruction {
+    build(program_id, admin_accounts(config_authority, pool), CLMMInstruction::SetPoolPaused { paused })
+}
+
+/// Create a `SetDynamicFee` instruction
+pub fn set_dynamic_fee(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    pool: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    build(program_id, admin_accounts(config_authority, pool), CLMMInstruction::SetDynamicFee { enabled })
+}
+
+/// Create a `SetFeeBounds` instruction
+pub fn set_fee_bounds(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    pool: &Pubkey,
+    min_fee: u32,
+    max_fee: u32,
+    fee_adjustment_interval: u32,
+) -> Instruction {
+    build(program_id, admin_accounts(config_authority, pool), CLMMInstruction::SetFeeBounds {
+        min_fee,
+        max_fee,
+        fee_adjustment_interval,
+    })
+}
+
+/// Create a `SetMevConfig` instruction
+pub fn set_mev_config(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    pool: &Pubkey,
+    mev_config: MevConfig,
+) -> Instruction {
+    build(program_id, admin_accounts(config_authority, pool), CLMMInstruction::SetMevConfig { mev_config })
+}
+
+/// Create a `ProposeConfigAuthority` instruction
+pub fn propose_config_authority(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    pool: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    build(program_id, admin_accounts(config_authority, pool), CLMMInstruction::ProposeConfigAuthority {
+        new_authority: *new_authority,
+    })
+}
+
+/// Create an `AcceptConfigAuthority` instruction, signed by the pending authority
+pub fn accept_config_authority(
+    program_id: &Pubkey,
+    pending_authority: &Pubkey,
+    pool: &Pubkey,
+) -> Instruction {
+    build(program_id, admin_accounts(pending_authority, pool), CLMMInstruction::AcceptConfigAuthority)
+}
+
+/// Pool, recipient and borrower accounts for a `Flash` instruction
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlashAccounts {
+    pub pool: Pubkey,
+    pub tokens: PoolTokens,
+    pub recipient_token_a: Pubkey,
+    pub recipient_token_b: Pubkey,
+    pub borrower_program: Pubkey,
+    /// Forwarded, in order, to `borrower_program`'s callback
+    pub borrower_accounts: Vec<AccountMeta>,
+}
+
+/// Create a `Flash` instruction
+pub fn flash(
+    program_id: &Pubkey,
+    caller: &Pubkey,
+    flash_accounts: FlashAccounts,
+    amount_0: u64,
+    amount_1: u64,
+    data: Vec<u8>,
+) -> Instruction {
+    let (vault_a, vault_b, pool_authority) = pool_accounts(program_id, &flash_accounts.pool);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*caller, true),
+        AccountMeta::new(flash_accounts.pool, false),
+        AccountMeta::new(flash_accounts.recipient_token_a, false),
+        AccountMeta::new(flash_accounts.recipient_token_b, false),
+        AccountMeta::new(vault_a, false),
+        AccountMeta::new(vault_b, false),
+        AccountMeta::new_readonly(pool_authority, false),
+        AccountMeta::new_readonly(flash_accounts.tokens.token_program_a, false),
+        AccountMeta::new_readonly(flash_accounts.borrower_program, false),
+    ];
+    accounts.extend(flash_accounts.tokens.trailing_accounts());
+    accounts.extend(flash_accounts.borrower_accounts);
+
+    build(program_id, accounts, CLMMInstruction::Flash {
+        amount_0,
+        amount_1,
+        data,
+    })
+}
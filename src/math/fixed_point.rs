@@ -1,5 +1,5 @@
+use crate::math::tick_math::{TickMath, U256, Q96, U256_ZERO, U256_ONE};
 use crate::error::CLMMError;
-use crate::math::tick_math::{U256, Q96, U256_ZERO, U256_ONE};
 use solana_program::program_error::ProgramError;
 
 pub struct FixedPointMath;
@@ -7,40 +7,12 @@ pub struct FixedPointMath;
 impl FixedPointMath {
     /// Multiply two U256 numbers and divide by a denominator with rounding up
     pub fn mul_div_rounding_up(x: U256, y: U256, denominator: U256) -> Result<U256, ProgramError> {
-        let result = Self::mul_div(x, y, denominator)?;
-        if x * y % denominator != U256_ZERO {
-            Ok(result + U256_ONE)
-        } else {
-            Ok(result)
-        }
+        TickMath::mul_div_rounding_up(x, y, denominator)
     }
 
     /// Multiply two U256 numbers and divide by a denominator
     pub fn mul_div(x: U256, y: U256, denominator: U256) -> Result<U256, ProgramError> {
-        if denominator == U256_ZERO {
-            return Err(CLMMError::MathOverflow.into());
-        }
-
-        let x_u128 = x.low_u128();
-        let y_u128 = y.low_u128();
-        let denominator_u128 = denominator.low_u128();
-
-        let result_low = x_u128 * y_u128 / denominator_u128;
-
-        let x_high = (x >> 128).low_u128();
-        let y_high = (y >> 128).low_u128();
-        let denominator_high = (denominator >> 128).low_u128();
-
-        let cross_term1 = x_u128 * y_high / denominator_u128;
-        let cross_term2 = x_high * y_u128 / denominator_u128;
-        let cross_term3 = x_high * y_high / denominator_high;
-
-        let result_high = cross_term1 + cross_term2 + cross_term3;
-
-        let mut result = U256::from(result_low);
-        result |= U256::from(result_high) << 128;
-
-        Ok(result)
+        TickMath::mul_div(x, y, denominator)
     }
 
     pub fn sqrt(x: U256) -> Result<U256, ProgramError> {
@@ -82,24 +54,29 @@ impl FixedPointMath {
         sqrt_price_b: U256,
         liquidity: U256,
         round_up: bool,
-    ) -> U256 {
+    ) -> Result<U256, ProgramError> {
         let (sqrt_price_start, sqrt_price_end) = if sqrt_price_a < sqrt_price_b {
             (sqrt_price_a, sqrt_price_b)
         } else {
             (sqrt_price_b, sqrt_price_a)
         };
 
+        if sqrt_price_start == U256_ZERO {
+            return Ok(U256_ZERO);
+        }
+
+        if liquidity.bits() > 160 {
+            return Err(CLMMError::MathOverflow.into());
+        }
         let numerator1 = liquidity << 96;
         let numerator2 = sqrt_price_end - sqrt_price_start;
 
-        let amount0 =
-            Self::div_rounding_up(numerator1 * numerator2, sqrt_price_end * sqrt_price_start);
-
-        if round_up && (numerator1 * numerator2 % (sqrt_price_end * sqrt_price_start) != U256_ZERO)
-        {
-            amount0 + U256_ONE
+        if round_up {
+            Self::mul_div_rounding_up(numerator1, numerator2, sqrt_price_end)
+                .map(|value| Self::div_rounding_up(value, sqrt_price_start))
         } else {
-            amount0
+            Self::mul_div(numerator1, numerator2, sqrt_price_end)
+                .map(|value| value / sqrt_price_start)
         }
     }
 
@@ -109,19 +86,19 @@ impl FixedPointMath {
         sqrt_price_b: U256,
         liquidity: U256,
         round_up: bool,
-    ) -> U256 {
+    ) -> Result<U256, ProgramError> {
         let (sqrt_price_start, sqrt_price_end) = if sqrt_price_a < sqrt_price_b {
             (sqrt_price_a, sqrt_price_b)
         } else {
             (sqrt_price_b, sqrt_price_a)
         };
 
-        let numerator = liquidity * (sqrt_price_end - sqrt_price_start);
+        let delta = sqrt_price_end - sqrt_price_start;
 
         if round_up {
-            Self::div_rounding_up(numerator, Q96)
+            Self::mul_div_rounding_up(liquidity, delta, Q96)
         } else {
-            numerator / Q96
+            Self::mul_div(liquidity, delta, Q96)
         }
     }
 
@@ -149,8 +126,44 @@ impl FixedPointMath {
         sqrt_price * sqrt_price
     }
 
-    /// Calculate liquidity for amounts
+    /// Liquidity funded by `amount0` over `[sqrt_price_a, sqrt_price_b]`
+    pub fn get_liquidity_for_amount0(sqrt_price_a: U256, sqrt_price_b: U256, amount0: U256) -> U256 {
+        let (sqrt_price_lower, sqrt_price_upper) = if sqrt_price_a < sqrt_price_b {
+            (sqrt_price_a, sqrt_price_b)
+        } else {
+            (sqrt_price_b, sqrt_price_a)
+        };
+
+        if sqrt_price_upper == sqrt_price_lower {
+            return U256_ZERO;
+        }
+
+        Self::mul_div(sqrt_price_lower, sqrt_price_upper, Q96)
+            .and_then(|intermediate| {
+                Self::mul_div(amount0, intermediate, sqrt_price_upper - sqrt_price_lower)
+            })
+            .unwrap_or(U256_ZERO)
+    }
+
+    /// Liquidity funded by `amount1` over `[sqrt_price_a, sqrt_price_b]`
+    pub fn get_liquidity_for_amount1(sqrt_price_a: U256, sqrt_price_b: U256, amount1: U256) -> U256 {
+        let (sqrt_price_lower, sqrt_price_upper) = if sqrt_price_a < sqrt_price_b {
+            (sqrt_price_a, sqrt_price_b)
+        } else {
+            (sqrt_price_b, sqrt_price_a)
+        };
+
+        if sqrt_price_upper == sqrt_price_lower {
+            return U256_ZERO;
+        }
+
+        Self::mul_div(amount1, Q96, sqrt_price_upper - sqrt_price_lower).unwrap_or(U256_ZERO)
+    }
+
+    /// Largest liquidity `amount0` and `amount1` can fund over `[sqrt_price_a, sqrt_price_b]`
+    /// at the current price `sqrt_price_x96`
     pub fn get_liquidity_for_amounts(
+        sqrt_price_x96: U256,
         sqrt_price_a: U256,
         sqrt_price_b: U256,
         amount0: U256,
@@ -162,17 +175,17 @@ impl FixedPointMath {
             (sqrt_price_b, sqrt_price_a)
         };
 
-        if sqrt_price_upper == sqrt_price_lower {
-            return U256_ZERO;
-        }
-
-        let amount0_liquidity = amount0 * sqrt_price_lower * sqrt_price_upper / Q96;
-        let amount1_liquidity = amount1 * Q96 / (sqrt_price_upper - sqrt_price_lower);
-
-        if amount0_liquidity <= amount1_liquidity {
-            amount0_liquidity
+        if sqrt_price_x96 <= sqrt_price_lower {
+            // Price below range - only token0 counts
+            Self::get_liquidity_for_amount0(sqrt_price_lower, sqrt_price_upper, amount0)
+        } else if sqrt_price_x96 < sqrt_price_upper {
+            // Price in range - the scarcer token limits liquidity
+            let liquidity0 = Self::get_liquidity_for_amount0(sqrt_price_x96, sqrt_price_upper, amount0);
+            let liquidity1 = Self::get_liquidity_for_amount1(sqrt_price_lower, sqrt_price_x96, amount1);
+            liquidity0.min(liquidity1)
         } else {
-            amount1_liquidity
+            // Price above range - only token1 counts
+            Self::get_liquidity_for_amount1(sqrt_price_lower, sqrt_price_upper, amount1)
         }
     }
 
@@ -195,5 +208,39 @@ impl FixedPointMath {
 
         (amount0, amount1)
     }
+
+    /// Sqrt price of `price * ratio_bps / 10000`, i.e. `sqrt_price * sqrt(ratio_bps / 10000)`
+    pub fn scale_sqrt_price_x96(sqrt_price_x96: U256, ratio_bps: u64) -> Result<U256, ProgramError> {
+        let factor_x64 = Self::sqrt((U256::from(ratio_bps) << 128) / U256::from(10_000u64))?;
+        Self::mul_div(sqrt_price_x96, factor_x64, U256_ONE << 64)
+    }
+
+    /// Amounts held by `liquidity` over `[sqrt_price_a, sqrt_price_b]` at the current price
+    /// `sqrt_price_x96`, rounded down
+    pub fn get_amounts_for_liquidity_at_price(
+        sqrt_price_x96: U256,
+        sqrt_price_a: U256,
+        sqrt_price_b: U256,
+        liquidity: U256,
+    ) -> Result<(U256, U256), ProgramError> {
+        let (sqrt_price_lower, sqrt_price_upper) = if sqrt_price_a < sqrt_price_b {
+            (sqrt_price_a, sqrt_price_b)
+        } else {
+            (sqrt_price_b, sqrt_price_a)
+        };
+
+        if sqrt_price_x96 <= sqrt_price_lower {
+            // Price below range - all token0
+            Ok((Self::get_amount0_delta(sqrt_price_lower, sqrt_price_upper, liquidity, false)?, U256_ZERO))
+        } else if sqrt_price_x96 < sqrt_price_upper {
+            Ok((
+                Self::get_amount0_delta(sqrt_price_x96, sqrt_price_upper, liquidity, false)?,
+                Self::get_amount1_delta(sqrt_price_lower, sqrt_price_x96, liquidity, false)?,
+            ))
+        } else {
+            // Price above range - all token1
+            Ok((U256_ZERO, Self::get_amount1_delta(sqrt_price_lower, sqrt_price_upper, liquidity, false)?))
+        }
+    }
 }
 
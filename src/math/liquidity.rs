@@ -1 +1,170 @@
 // Liquidity math
+
+use crate::math::fixed_point::FixedPointMath;
+use crate::math::swap::SwapEngine;
+use crate::math::tick_math::{TickMath, U256, U256_ZERO, MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO, MIN_TICK};
+use crate::state::{Pool, Tick};
+use solana_program::program_error::ProgramError;
+use std::collections::BTreeMap;
+
+/// Active liquidity across a pool's price range, built from a snapshot of its ticks
+///
+/// Bands run between consecutive initialized ticks and carry the liquidity active
+/// anywhere inside them, found by crossing `liquidity_net` outward from the pool's
+/// current tick and liquidity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquidityDistribution {
+    /// Pool tick the distribution was built at
+    pub tick_current: i32,
+    /// Pool sqrt price the distribution was built at
+    pub sqrt_price_x96: U256,
+    /// Bands in ascending price order
+    pub bands: Vec<LiquidityBand>,
+}
+
+/// A tick range of constant active liquidity
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquidityBand {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub sqrt_price_lower_x96: U256,
+    pub sqrt_price_upper_x96: U256,
+    /// Active liquidity inside the band
+    pub liquidity: U256,
+    /// Token0 locked in the band at the current price
+    pub amount0: U256,
+    /// Token1 locked in the band at the current price
+    pub amount1: U256,
+}
+
+/// Cumulative depth within a relative distance of the current price
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthLevel {
+    /// Distance from the current price, in basis points of price
+    pub offset_bps: u32,
+    /// Sqrt price `offset_bps` above the current price
+    pub sqrt_price_above_x96: U256,
+    /// Sqrt price `offset_bps` below the current price
+    pub sqrt_price_below_x96: U256,
+    /// Token0 sold by the pool as the price rises to `sqrt_price_above_x96`
+    pub amount0_above: U256,
+    /// Token1 sold by the pool as the price falls to `sqrt_price_below_x96`
+    pub amount1_below: U256,
+}
+
+impl LiquidityDistribution {
+    /// Build the distribution of `pool` from its initialized ticks
+    ///
+    /// The open-ended bands below the lowest and above the highest initialized tick
+    /// are only included if they hold liquidity.
+    pub fn build(pool: &Pool, ticks: &BTreeMap<i32, Tick>) -> Result<Self, ProgramError> {
+        let boundaries: Vec<(i32, &Tick)> = ticks
+            .iter()
+            .filter(|(_, t)| t.initialized)
+            .map(|(&index, t)| (index, t))
+            .collect();
+        let split = boundaries.partition_point(|(index, _)| *index <= pool.tick);
+
+        let mut below = Vec::new();
+        let mut liquidity = pool.liquidity;
+        let mut upper = boundaries.get(split).map_or(MAX_TICK, |(index, _)| *index);
+        for i in (0..split).rev() {
+            let (lower, tick) = boundaries[i];
+            if upper < MAX_TICK || !liquidity.is_zero() {
+                below.push(Self::band(pool, lower, upper, liquidity)?);
+            }
+            liquidity = SwapEngine::apply_liquidity_net(liquidity, tick.liquidity_net, true)?;
+            upper = lower;
+        }
+        if !liquidity.is_zero() {
+            below.push(Self::band(pool, MIN_TICK, upper, liquidity)?);
+        }
+
+        let mut bands: Vec<LiquidityBand> = below.into_iter().rev().collect();
+        let mut liquidity = pool.liquidity;
+        for i in split..boundaries.len() {
+            let (lower, tick) = boundaries[i];
+            liquidity = SwapEngine::apply_liquidity_net(liquidity, tick.liquidity_net, false)?;
+            let upper = boundaries.get(i + 1).map_or(MAX_TICK, |(index, _)| *index);
+            if upper < MAX_TICK || !liquidity.is_zero() {
+                bands.push(Self::band(pool, lower, upper, liquidity)?);
+            }
+        }
+
+        Ok(LiquidityDistribution {
+            tick_current: pool.tick,
+            sqrt_price_x96: pool.sqrt_price_x96,
+            bands,
+        })
+    }
+
+    /// Total token0 and token1 locked across all bands
+    pub fn total_amounts(&self) -> (U256, U256) {
+        self.bands.iter().fold((U256_ZERO, U256_ZERO), |(amount0, amount1), band| {
+            (amount0 + band.amount0, amount1 + band.amount1)
+        })
+    }
+
+    /// Cumulative depth to `offset_bps` either side of the current price
+    ///
+    /// Offsets of 100% or more reach down to the minimum price.
+    pub fn depth_at(&self, offset_bps: u32) -> Result<DepthLevel, ProgramError> {
+        let sqrt_price_above_x96 = FixedPointMath::scale_sqrt_price_x96(self.sqrt_price_x96, 10_000 + offset_bps as u64)?
+            .min(MAX_SQRT_RATIO);
+        let sqrt_price_below_x96 = if offset_bps >= 10_000 {
+            MIN_SQRT_RATIO
+        } else {
+            FixedPointMath::scale_sqrt_price_x96(self.sqrt_price_x96, 10_000 - offset_bps as u64)?.max(MIN_SQRT_RATIO)
+        };
+
+        let mut amount0_above = U256_ZERO;
+        let mut amount1_below = U256_ZERO;
+        for band in &self.bands {
+            let low = band.sqrt_price_lower_x96.max(self.sqrt_price_x96);
+            let high = band.sqrt_price_upper_x96.min(sqrt_price_above_x96);
+            if low < high {
+                amount0_above += FixedPointMath::get_amount0_delta(low, high, band.liquidity, false)?;
+            }
+
+            let low = band.sqrt_price_lower_x96.max(sqrt_price_below_x96);
+            let high = band.sqrt_price_upper_x96.min(self.sqrt_price_x96);
+            if low < high {
+                amount1_below += FixedPointMath::get_amount1_delta(low, high, band.liquidity, false)?;
+            }
+        }
+
+        Ok(DepthLevel {
+            offset_bps,
+            sqrt_price_above_x96,
+            sqrt_price_below_x96,
+            amount0_above,
+            amount1_below,
+        })
+    }
+
+    /// Depth curve at each of `offsets_bps`
+    pub fn depth_curve(&self, offsets_bps: &[u32]) -> Result<Vec<DepthLevel>, ProgramError> {
+        offsets_bps.iter().map(|&offset_bps| self.depth_at(offset_bps)).collect()
+    }
+
+    fn band(pool: &Pool, tick_lower: i32, tick_upper: i32, liquidity: U256) -> Result<LiquidityBand, ProgramError> {
+        let sqrt_price_lower_x96 = TickMath::get_sqrt_ratio_at_tick(tick_lower)?;
+        let sqrt_price_upper_x96 = TickMath::get_sqrt_ratio_at_tick(tick_upper)?;
+        let (amount0, amount1) = FixedPointMath::get_amounts_for_liquidity_at_price(
+            pool.sqrt_price_x96,
+            sqrt_price_lower_x96,
+            sqrt_price_upper_x96,
+            liquidity,
+        )?;
+
+        Ok(LiquidityBand {
+            tick_lower,
+            tick_upper,
+            sqrt_price_lower_x96,
+            sqrt_price_upper_x96,
+            liquidity,
+            amount0,
+            amount1,
+        })
+    }
+}
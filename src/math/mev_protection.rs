@@ -5,7 +5,7 @@ use solana_program::program_error::ProgramError;
 use std::collections::{VecDeque, HashMap};
 
 ///  TWAP calculation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct OracleObservation {
     pub timestamp: u32,
     pub price: U256,
@@ -169,15 +169,15 @@ impl MevProtectionEngine {
         Ok(deviation_bps <= U256::from(config.max_slippage_bps))
     }
 
+    /// Time-weighted average price over the `window` seconds up to the latest observation
+    ///
+    /// With a single observation in the window, or none spanning any time, the latest
+    /// in-window price is the average.
     pub fn calculate_twap(
         observations: &VecDeque<OracleObservation>,
         window: u32,
     ) -> Result<U256, ProgramError> {
-        if observations.len() < 2 {
-            return Err(CLMMError::InvalidOracle.into());
-        }
-
-        let current_time = observations.back().unwrap().timestamp;
+        let current_time = observations.back().ok_or(CLMMError::InvalidOracle)?.timestamp;
         let window_start = current_time.saturating_sub(window);
 
         let mut valid_observations: Vec<_> = observations
@@ -190,6 +190,7 @@ impl MevProtectionEngine {
         }
 
         valid_observations.sort_by_key(|obs| obs.timestamp);
+        let latest_price = valid_observations[valid_observations.len() - 1].price;
 
         let mut total_weighted_sum = U256_ZERO;
         let mut total_time_weight = U256_ZERO;
@@ -220,7 +221,7 @@ impl MevProtectionEngine {
         }
 
         if total_time_weight == U256_ZERO {
-            return Err(CLMMError::InvalidOracle.into());
+            return Ok(latest_price);
         }
 
         Ok(total_weighted_sum / total_time_weight)
@@ -403,35 +404,46 @@ impl MevProtectionEngine {
         Ok(())
     }
 
-    pub fn validate_swap_mev_protection(
+    /// Tighten a swap's price limit to within `max_slippage_bps` of the TWAP
+    ///
+    /// The TWAP carries the current price forward to `current_time`, so a pool with a
+    /// single observation is already bounded around its spot price. Without observations,
+    /// or with the oracle disabled, the limit is returned unchanged.
+    pub fn bound_swap_price_limit(
         pool: &Pool,
-        _amount_in: U256,
         zero_for_one: bool,
         sqrt_price_limit: U256,
         oracle_observations: &VecDeque<OracleObservation>,
+        current_time: u32,
         config: &MevConfig,
-    ) -> Result<bool, ProgramError> {
-        // 1. Validate TWAP vs spot price
-        if !Self::validate_twap_vs_spot(oracle_observations, pool.sqrt_price_x96, config)? {
-            return Ok(false);
-        }
+    ) -> Result<U256, ProgramError> {
+        let latest = match oracle_observations.back() {
+            Some(latest) if config.oracle_enabled => latest,
+            _ => return Ok(sqrt_price_limit),
+        };
 
-        // 2. Check price limit against TWAP
-        let twap = Self::calculate_twap(oracle_observations, config.oracle_window)?;
+        let mut observations = oracle_observations.clone();
+        if current_time > latest.timestamp {
+            observations.push_back(OracleObservation {
+                timestamp: current_time,
+                price: pool.sqrt_price_x96,
+                tick: pool.tick,
+                liquidity: pool.liquidity,
+            });
+        }
+        let twap = Self::calculate_twap(&observations, config.oracle_window)?;
 
+        let max_slippage_bps = U256::from(config.max_slippage_bps);
+        let bps = U256::from(10000);
         if zero_for_one {
-            // Price decreasing - limit should be >= TWAP
-            if sqrt_price_limit < twap {
-                return Ok(false);
-            }
+            // Price decreasing - stop at the bottom of the band
+            let bound = twap * bps.saturating_sub(max_slippage_bps) / bps;
+            Ok(sqrt_price_limit.max(bound))
         } else {
-            // Price increasing - limit should be <= TWAP
-            if sqrt_price_limit > twap {
-                return Ok(false);
-            }
+            // Price increasing - stop at the top of the band
+            let bound = twap * (bps + max_slippage_bps) / bps;
+            Ok(sqrt_price_limit.min(bound))
         }
-
-        Ok(true)
     }
 
     pub fn get_mev_protection_status(
@@ -831,3 +843,43 @@ impl borsh::BorshDeserialize for MevConfig {
         })
     }
 }
+
+impl borsh::BorshSerialize for OracleObservation {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.timestamp.serialize(writer)?;
+        self.price.serialize(writer)?;
+        self.tick.serialize(writer)?;
+        self.liquidity.serialize(writer)?;
+        Ok(())
+    }
+}
+
+impl borsh::BorshDeserialize for OracleObservation {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let timestamp = u32::deserialize(buf)?;
+        let price = U256::deserialize(buf)?;
+        let tick = i32::deserialize(buf)?;
+        let liquidity = U256::deserialize(buf)?;
+
+        Ok(OracleObservation {
+            timestamp,
+            price,
+            tick,
+            liquidity,
+        })
+    }
+
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let timestamp = u32::deserialize_reader(reader)?;
+        let price = U256::deserialize_reader(reader)?;
+        let tick = i32::deserialize_reader(reader)?;
+        let liquidity = U256::deserialize_reader(reader)?;
+
+        Ok(OracleObservation {
+            timestamp,
+            price,
+            tick,
+            liquidity,
+        })
+    }
+}
@@ -7,13 +7,18 @@ pub mod price_impact;
 pub mod multi_hop;
 pub mod dynamic_fee;
 pub mod mev_protection;
+pub mod quoter;
+pub mod position_analytics;
 
 pub use tick_math::*;
 pub use fixed_point::*;
+pub use liquidity::*;
 pub use swap::*;
 pub use price_impact::*;
 pub use multi_hop::*;
 pub use dynamic_fee::*;
+pub use quoter::*;
+pub use position_analytics::*;
 pub use mev_protection::{
     *, BatchState, BatchStatistics, SocialMediaConfig,
     SocialMediaData, SocialMediaMetrics, SocialMevReport
@@ -1,9 +1,16 @@
 use crate::error::CLMMError;
-use crate::math::tick_math::{U256, U256_ZERO};
+use crate::math::tick_math::{U256, U256_ZERO, U256_ONE, MIN_SQRT_RATIO, MAX_SQRT_RATIO};
 use crate::math::dynamic_fee::MarketDataPoint;
-use crate::state::Pool;
+use crate::math::mev_protection::OracleObservation;
+use crate::math::quoter::Quoter;
+use crate::math::swap::SwapContext;
+use crate::state::{Pool, Tick, TickBitmap};
 use solana_program::{program_error::ProgramError, pubkey::Pubkey};
-use std::collections::{HashMap, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
+
+/// Most routes `find_best_split` divides an order across
+pub const MAX_SPLIT_ROUTES: usize = 8;
 
 /// Multi-hop swap routing system for complex swap paths
 pub struct MultiHopRouter {
@@ -11,6 +18,41 @@ pub struct MultiHopRouter {
     pub pools: HashMap<(Pubkey, Pubkey, u32), Pool>,
     /// Routing graph for path finding
     pub routing_graph: HashMap<Pubkey, Vec<(Pubkey, u32)>>,
+    /// Tick snapshot and swap history of each pool, keyed like `pools`
+    pub pool_states: HashMap<(Pubkey, Pubkey, u32), RouterPoolState>,
+}
+
+/// Off-chain copy of the state a swap reads and updates besides the pool itself
+///
+/// Quotes walk `ticks` like a real swap; executed swaps also update the histories,
+/// which persist from one router swap to the next.
+#[derive(Debug, Clone, Default)]
+pub struct RouterPoolState {
+    /// Initialized ticks of the pool, keyed by tick index
+    pub ticks: BTreeMap<i32, Tick>,
+    /// Bitmap words marking the initialized ticks
+    pub tick_bitmaps: BTreeMap<i16, TickBitmap>,
+    pub price_history: VecDeque<MarketDataPoint>,
+    pub volume_history: VecDeque<MarketDataPoint>,
+    pub impact_history: VecDeque<MarketDataPoint>,
+    pub oracle_observations: VecDeque<OracleObservation>,
+}
+
+impl RouterPoolState {
+    /// State for a pool with the given initialized ticks and no swap history
+    pub fn with_ticks(ticks: Vec<Tick>, tick_spacing: u32) -> Self {
+        let mut state = Self::default();
+        for tick in ticks.into_iter().filter(|tick| tick.initialized) {
+            let (word_position, _) = TickBitmap::position(tick.tick, tick_spacing);
+            state
+                .tick_bitmaps
+                .entry(word_position)
+                .or_insert_with(|| TickBitmap::new(word_position))
+                .flip_tick(tick.tick, tick_spacing);
+            state.ticks.insert(tick.tick, tick);
+        }
+        state
+    }
 }
 
 impl MultiHopRouter {
@@ -19,26 +61,33 @@ impl MultiHopRouter {
         Self {
             pools: HashMap::new(),
             routing_graph: HashMap::new(),
+            pool_states: HashMap::new(),
         }
     }
 
     /// Add a pool to the router
+    ///
+    /// Without a tick snapshot the pool is quoted as if its current liquidity
+    /// extended over the whole price range.
     pub fn add_pool(&mut self, pool: Pool) {
+        self.add_pool_with_ticks(pool, Vec::new());
+    }
+
+    /// Add a pool together with a snapshot of its initialized ticks
+    pub fn add_pool_with_ticks(&mut self, pool: Pool, ticks: Vec<Tick>) {
         let key = (pool.token_a, pool.token_b, pool.fee);
 
         // Add to pools map
         self.pools.insert(key, pool.clone());
+        self.pool_states.insert(key, RouterPoolState::with_ticks(ticks, pool.tick_spacing));
 
-        // Update routing graph
-        self.routing_graph
-            .entry(pool.token_a)
-            .or_insert_with(Vec::new)
-            .push((pool.token_b, pool.fee));
-
-        self.routing_graph
-            .entry(pool.token_b)
-            .or_insert_with(Vec::new)
-            .push((pool.token_a, pool.fee));
+        // Update routing graph, one edge per pool
+        for (token, neighbor) in [(pool.token_a, pool.token_b), (pool.token_b, pool.token_a)] {
+            let edges = self.routing_graph.entry(token).or_default();
+            if !edges.contains(&(neighbor, pool.fee)) {
+                edges.push((neighbor, pool.fee));
+            }
+        }
     }
 
     /// Find the best multi-hop route
@@ -49,141 +98,204 @@ impl MultiHopRouter {
         amount_in: U256,
         max_hops: u8,
     ) -> Result<MultiHopRoute, ProgramError> {
-        let mut best_route = None;
-        let mut best_output = U256_ZERO;
-
-        // Try all possible routes up to max_hops
-        for hops in 1..=max_hops {
-            let routes = self.find_routes(token_in, token_out, hops);
-            for route in routes {
-                let output = self.estimate_route_output(&route, amount_in)?;
-                if output > best_output {
-                    best_output = output;
-                    best_route = Some(route);
-                }
-            }
+        self.find_top_routes(token_in, token_out, amount_in, max_hops, 1)?
+            .into_iter()
+            .next()
+            .map(|quote| quote.route)
+            .ok_or(CLMMError::InvalidInstruction.into())
+    }
+
+    /// Find up to `k` routes with the highest quoted output, best first
+    ///
+    /// Best-first search over the pool graph, where every pool is its own edge so parallel
+    /// fee tiers of a pair are separate routes. Partial routes are expanded in order of
+    /// their quoted amount, and a partial route is dropped once `k` others have reached
+    /// the same token with at least its amount in at most as many hops.
+    pub fn find_top_routes(
+        &self,
+        token_in: &Pubkey,
+        token_out: &Pubkey,
+        amount_in: U256,
+        max_hops: u8,
+        k: usize,
+    ) -> Result<Vec<RouteQuote>, ProgramError> {
+        if k == 0 || token_in == token_out {
+            return Ok(Vec::new());
         }
 
-        best_route.ok_or(CLMMError::InvalidInstruction.into())
-    }
+        // Partial routes live in an arena and point back to their parent, so extending
+        // one never copies its path
+        let mut nodes = vec![SearchNode {
+            token: *token_in,
+            pool: None,
+            amount: amount_in,
+            hops: 0,
+            parent: None,
+        }];
+        let mut queue = BinaryHeap::new();
+        queue.push((amount_in, Reverse(0)));
+        let mut expanded: HashMap<Pubkey, Vec<(U256, u8)>> = HashMap::new();
+        let mut arrivals = Vec::new();
+
+        while let Some((_, Reverse(index))) = queue.pop() {
+            let node = nodes[index].clone();
+            if node.token == *token_out {
+                arrivals.push(index);
+                continue;
+            }
+            if node.hops >= max_hops || Self::is_dominated(&expanded, &node, k) {
+                continue;
+            }
+            expanded.entry(node.token).or_default().push((node.amount, node.hops));
 
-    /// Find all possible routes with a given number of hops
-    fn find_routes(&self, token_in: &Pubkey, token_out: &Pubkey, hops: u8) -> Vec<MultiHopRoute> {
-        let mut routes = Vec::new();
-        let mut current_paths = vec![vec![*token_in]];
-
-        for _ in 0..hops {
-            let mut next_paths = Vec::new();
-            for path in current_paths {
-                let last_token = *path.last().unwrap();
-
-                if let Some(neighbors) = self.routing_graph.get(&last_token) {
-                    for (neighbor, fee) in neighbors {
-                        if !path.contains(neighbor) {
-                            let mut new_path = path.clone();
-                            new_path.push(*neighbor);
-                            next_paths.push((new_path, *fee));
-                        }
-                    }
+            let neighbors = match self.routing_graph.get(&node.token) {
+                Some(neighbors) => neighbors,
+                None => continue,
+            };
+            for (neighbor, fee) in neighbors {
+                if Self::path_contains(&nodes, index, neighbor) {
+                    continue;
                 }
-            }
-            current_paths = next_paths.into_iter().map(|(path, _)| path).collect();
-        }
 
-        // Filter routes that end with token_out
-        for path in current_paths {
-            if let Some(last) = path.last() {
-                if last == token_out && path.len() > 1 {
-                    let mut route = MultiHopRoute {
-                        path: path.clone(),
-                        fees: Vec::new(),
-                        pools: Vec::new(),
-                    };
-
-                    // Extract fees for each hop
-                    for i in 0..path.len() - 1 {
-                        let token_a = path[i];
-                        let token_b = path[i + 1];
-
-                        // Find the fee for this hop
-                        if let Some(neighbors) = self.routing_graph.get(&token_a) {
-                            for (neighbor, fee) in neighbors {
-                                if neighbor == &token_b {
-                                    route.fees.push(*fee);
-                                    break;
-                                }
-                            }
-                        }
-                    }
-
-                    routes.push(route);
+                let pool_key = self.pool_key(&node.token, neighbor, *fee);
+                let pool = match self.pools.get(&pool_key) {
+                    Some(pool) => pool,
+                    None => continue,
+                };
+                let ticks = &self.pool_states[&pool_key].ticks;
+                let zero_for_one = pool.token_a == node.token;
+                // A paused or otherwise unquotable pool just drops out of the search
+                let amount = match Self::estimate_single_hop_output(pool, ticks, node.amount, zero_for_one) {
+                    Ok(amount) if amount != U256_ZERO => amount,
+                    _ => continue,
+                };
+
+                let next = SearchNode {
+                    token: *neighbor,
+                    pool: Some(pool_key),
+                    amount,
+                    hops: node.hops + 1,
+                    parent: Some(index),
+                };
+                if !Self::is_dominated(&expanded, &next, k) {
+                    queue.push((amount, Reverse(nodes.len())));
+                    nodes.push(next);
                 }
             }
         }
 
-        routes
+        arrivals.sort_by(|&a, &b| nodes[b].amount.cmp(&nodes[a].amount));
+        arrivals.truncate(k);
+
+        Ok(arrivals
+            .into_iter()
+            .map(|index| Self::route_quote(&nodes, index))
+            .collect())
     }
 
-    /// Estimate output for a multi-hop route
-    fn estimate_route_output(&self, route: &MultiHopRoute, amount_in: U256) -> Result<U256, ProgramError> {
-        let mut current_amount = amount_in;
+    /// Whether `k` expanded routes reached the node's token with at least its amount
+    /// in at most its number of hops
+    fn is_dominated(expanded: &HashMap<Pubkey, Vec<(U256, u8)>>, node: &SearchNode, k: usize) -> bool {
+        expanded.get(&node.token).is_some_and(|labels| {
+            labels
+                .iter()
+                .filter(|(amount, hops)| *amount >= node.amount && *hops <= node.hops)
+                .count()
+                >= k
+        })
+    }
 
-        for i in 0..route.path.len() - 1 {
-            let token_in = route.path[i];
-            let token_out = route.path[i + 1];
-            let fee = route.fees[i];
+    /// Whether the route ending at `index` already passes through `token`
+    fn path_contains(nodes: &[SearchNode], index: usize, token: &Pubkey) -> bool {
+        let mut current = Some(index);
+        while let Some(i) = current {
+            if nodes[i].token == *token {
+                return true;
+            }
+            current = nodes[i].parent;
+        }
+        false
+    }
 
-            // Find the pool for this hop
-            let pool_key = if token_in < token_out {
-                (token_in, token_out, fee)
-            } else {
-                (token_out, token_in, fee)
-            };
+    /// Rebuild the route ending at `index` together with its per-hop quotes
+    fn route_quote(nodes: &[SearchNode], index: usize) -> RouteQuote {
+        let mut hops = Vec::new();
+        let mut current = index;
+        while let Some(parent) = nodes[current].parent {
+            hops.push(current);
+            current = parent;
+        }
+        hops.reverse();
+
+        let mut path = vec![nodes[current].token];
+        let mut pools = Vec::with_capacity(hops.len());
+        let mut hop_amounts_out = Vec::with_capacity(hops.len());
+        for &hop in &hops {
+            path.push(nodes[hop].token);
+            pools.push(nodes[hop].pool.expect("every hop crosses a pool"));
+            hop_amounts_out.push(nodes[hop].amount);
+        }
+        let fees = pools.iter().map(|&(_, _, fee)| fee).collect();
 
-            if let Some(pool) = self.pools.get(&pool_key) {
-                let zero_for_one = token_in < token_out;
-                current_amount = Self::estimate_single_hop_output(pool, current_amount, zero_for_one)?;
-            } else {
-                return Ok(U256_ZERO); // Pool not found
-            }
+        RouteQuote {
+            route: MultiHopRoute { path, fees, pools },
+            amount_in: nodes[current].amount,
+            amount_out: nodes[index].amount,
+            hop_amounts_out,
         }
+    }
 
-        Ok(current_amount)
+    /// Key of the pool between two tokens at a fee tier, in the order it was added
+    fn pool_key(&self, token_x: &Pubkey, token_y: &Pubkey, fee: u32) -> (Pubkey, Pubkey, u32) {
+        if self.pools.contains_key(&(*token_x, *token_y, fee)) {
+            (*token_x, *token_y, fee)
+        } else {
+            (*token_y, *token_x, fee)
+        }
     }
 
-    /// Estimate output for a single hop
+    /// Estimate output for a single hop, crossing initialized ticks like a real swap
     fn estimate_single_hop_output(
         pool: &Pool,
+        ticks: &BTreeMap<i32, Tick>,
         amount_in: U256,
         zero_for_one: bool,
     ) -> Result<U256, ProgramError> {
-        if pool.liquidity == U256_ZERO {
+        let sqrt_price_limit = Self::edge_sqrt_price(zero_for_one);
+
+        // Nothing left to trade, or the pool was already pushed to the edge of its range
+        if amount_in == U256_ZERO || pool.sqrt_price_x96 == sqrt_price_limit {
             return Ok(U256_ZERO);
         }
 
-        // Calculate fee
-        let fee_amount = amount_in * U256::from(pool.fee) / U256::from(10000);
-        let amount_after_fee = amount_in - fee_amount;
+        let quote = Quoter::quote_exact_input(pool, ticks, amount_in, zero_for_one, sqrt_price_limit)?;
+        Ok(quote.amount_out)
+    }
 
+    /// Price limit at the edge of the tick range, i.e. no limit in the swap direction
+    fn edge_sqrt_price(zero_for_one: bool) -> U256 {
         if zero_for_one {
-            // Token0 -> Token1
-            let price_ratio = pool.sqrt_price_x96 * pool.sqrt_price_x96 / crate::math::tick_math::Q96;
-            Ok(amount_after_fee * crate::math::tick_math::Q96 / price_ratio)
+            MIN_SQRT_RATIO + U256_ONE
         } else {
-            // Token1 -> Token0
-            let price_ratio = crate::math::tick_math::Q96 * crate::math::tick_math::Q96 / (pool.sqrt_price_x96 * pool.sqrt_price_x96);
-            Ok(amount_after_fee * price_ratio / crate::math::tick_math::Q96)
+            MAX_SQRT_RATIO - U256_ONE
         }
     }
 
-    /// Execute a multi-hop swap
+    /// Execute a multi-hop swap against the router's in-memory pools
+    ///
+    /// Only simulates the route; the `SwapRoute` instruction performs it on chain. Each hop
+    /// runs `SwapEngine` on the pool's tick snapshot and swap history at `current_timestamp`.
+    /// Pools and their state are only updated if the whole route succeeds.
     pub fn execute_multi_hop_swap(
         &mut self,
         route: &MultiHopRoute,
         amount_in: U256,
         minimum_amount_out: U256,
         recipient: &Pubkey,
+        current_timestamp: u32,
     ) -> Result<MultiHopSwapResult, ProgramError> {
+        let mut pools = HashMap::new();
+        let mut pool_states = HashMap::new();
         let mut current_amount = amount_in;
         let mut total_fees_paid = U256_ZERO;
         let mut pools_used = Vec::new();
@@ -200,30 +312,40 @@ impl MultiHopRouter {
                 (token_out, token_in, fee)
             };
 
-            if let Some(pool) = self.pools.get_mut(&pool_key) {
+            if let Some(pool) = self.pools.get(&pool_key) {
                 let zero_for_one = token_in < token_out;
 
-                // Execute single hop swap
-                let mut price_history = VecDeque::new();
-                let mut volume_history = VecDeque::new();
-                let mut impact_history = VecDeque::new();
-                let mut oracle_observations = VecDeque::new();
+                // Work on copies, carrying over earlier hops through the same pool
+                let pool = pools.entry(pool_key).or_insert_with(|| pool.clone());
+                let state = pool_states
+                    .entry(pool_key)
+                    .or_insert_with(|| self.pool_states[&pool_key].clone());
+                let mut ticks: HashMap<i32, Tick> = state.ticks.clone().into_iter().collect();
+                let sequence_number = pool.last_sequence_number + 1;
+
+                // No price limit for intermediate hops
+                let mut ctx = SwapContext {
+                    tick_bitmaps: &state.tick_bitmaps,
+                    ticks: &mut ticks,
+                    price_history: &mut state.price_history,
+                    volume_history: &mut state.volume_history,
+                    impact_history: &mut state.impact_history,
+                    oracle_observations: &mut state.oracle_observations,
+                    current_timestamp,
+                    sequence_number,
+                };
                 let hop_result = crate::math::SwapEngine::execute_swap(
                     pool,
+                    &mut ctx,
                     current_amount,
                     zero_for_one,
-                    crate::math::tick_math::U256::MAX, // No price limit for intermediate hops
+                    Self::edge_sqrt_price(zero_for_one),
                     recipient,
-                    &mut price_history,
-                    &mut volume_history,
-                    &mut impact_history,
-                    &mut oracle_observations,
-                    1000, // Use a fixed timestamp for now
-                    1, // Sequence number
                 )?;
+                state.ticks = ticks.into_iter().collect();
 
                 current_amount = hop_result.amount_out;
-                total_fees_paid += hop_result.amount_in - hop_result.amount_out;
+                total_fees_paid += hop_result.amount_in.saturating_sub(hop_result.amount_out);
                 pools_used.push(pool_key);
             } else {
                 return Err(CLMMError::InvalidInstruction.into());
@@ -234,6 +356,9 @@ impl MultiHopRouter {
             return Err(CLMMError::InsufficientLiquidity.into());
         }
 
+        self.pools.extend(pools);
+        self.pool_states.extend(pool_states);
+
         Ok(MultiHopSwapResult {
             amount_in,
             amount_out: current_amount,
@@ -244,6 +369,267 @@ impl MultiHopRouter {
         })
     }
 
+    /// Split `amount_in` across the routes from `token_in` to `token_out` to maximize
+    /// the total output
+    ///
+    /// Candidates are the `MAX_SPLIT_ROUTES` best routes for the full amount. The amount is
+    /// handed out in `parts` equal chunks, each to the route where it adds the most output. Every allocation is quoted along each pool's liquidity curve, with
+    /// the routes executed in order against shared pool state, so routes through the same
+    /// pool see each other's price impact.
+    pub fn find_best_split(
+        &self,
+        token_in: &Pubkey,
+        token_out: &Pubkey,
+        amount_in: U256,
+        max_hops: u8,
+        parts: u8,
+    ) -> Result<SplitRouteQuote, ProgramError> {
+        if parts == 0 {
+            return Err(CLMMError::InvalidInstruction.into());
+        }
+
+        let routes: Vec<MultiHopRoute> = self
+            .find_top_routes(token_in, token_out, amount_in, max_hops, MAX_SPLIT_ROUTES)?
+            .into_iter()
+            .map(|quote| quote.route)
+            .collect();
+        if routes.is_empty() {
+            return Err(CLMMError::InvalidInstruction.into());
+        }
+
+        let chunk = amount_in / U256::from(parts);
+        let mut allocation = vec![U256_ZERO; routes.len()];
+
+        for part in 0..parts {
+            // The last chunk also carries the rounding remainder
+            let amount = if part == parts - 1 {
+                amount_in - chunk * U256::from(parts - 1)
+            } else {
+                chunk
+            };
+
+            let mut best = (0, U256_ZERO);
+            for index in 0..routes.len() {
+                allocation[index] += amount;
+                let total = self
+                    .simulate_split(&routes, &allocation)?
+                    .into_iter()
+                    .fold(U256_ZERO, |sum, output| sum + output);
+                allocation[index] -= amount;
+
+                if total > best.1 {
+                    best = (index, total);
+                }
+            }
+            allocation[best.0] += amount;
+        }
+
+        let outputs = self.simulate_split(&routes, &allocation)?;
+        let splits: Vec<SplitRoute> = routes
+            .into_iter()
+            .zip(allocation)
+            .zip(outputs)
+            .filter(|((_, amount), _)| *amount > U256_ZERO)
+            .map(|((route, amount_in), amount_out)| SplitRoute { route, amount_in, amount_out })
+            .collect();
+        let amount_out = splits.iter().fold(U256_ZERO, |sum, split| sum + split.amount_out);
+
+        Ok(SplitRouteQuote {
+            amount_in,
+            amount_out,
+            splits,
+        })
+    }
+
+    /// Quote every route in order for its allocated input, against copies of the pools
+    fn simulate_split(&self, routes: &[MultiHopRoute], allocation: &[U256]) -> Result<Vec<U256>, ProgramError> {
+        let mut pools = self.route_pools(routes);
+        routes
+            .iter()
+            .zip(allocation)
+            .map(|(route, &amount_in)| {
+                let hop_amounts_out = self.simulate_route(&mut pools, route, amount_in)?;
+                Ok(hop_amounts_out.last().copied().unwrap_or(U256_ZERO))
+            })
+            .collect()
+    }
+
+    /// Copies of the pools the routes pass through
+    fn route_pools(&self, routes: &[MultiHopRoute]) -> HashMap<(Pubkey, Pubkey, u32), Pool> {
+        routes
+            .iter()
+            .flat_map(|route| &route.pools)
+            .filter_map(|key| self.pools.get(key).map(|pool| (*key, pool.clone())))
+            .collect()
+    }
+
+    /// Quote a route, moving each pool's price and liquidity as the swap would,
+    /// and return the output of each hop
+    fn simulate_route(
+        &self,
+        pools: &mut HashMap<(Pubkey, Pubkey, u32), Pool>,
+        route: &MultiHopRoute,
+        amount_in: U256,
+    ) -> Result<Vec<U256>, ProgramError> {
+        let mut amount = amount_in;
+        let mut hop_amounts_out = Vec::with_capacity(route.pools.len());
+
+        for (token_in, pool_key) in route.path.iter().zip(&route.pools) {
+            let pool = pools.get_mut(pool_key).ok_or(CLMMError::InvalidInstruction)?;
+            let zero_for_one = pool.token_a == *token_in;
+            let sqrt_price_limit = Self::edge_sqrt_price(zero_for_one);
+
+            // Nothing left to trade, or the pool was already pushed to the edge of its range
+            if amount == U256_ZERO || pool.sqrt_price_x96 == sqrt_price_limit {
+                amount = U256_ZERO;
+                hop_amounts_out.push(amount);
+                continue;
+            }
+
+            // A paused or otherwise unquotable pool delivers nothing
+            let ticks = &self.pool_states[pool_key].ticks;
+            let quote = match Quoter::quote_exact_input(pool, ticks, amount, zero_for_one, sqrt_price_limit) {
+                Ok(quote) => quote,
+                Err(_) => {
+                    amount = U256_ZERO;
+                    hop_amounts_out.push(amount);
+                    continue;
+                }
+            };
+            pool.sqrt_price_x96 = quote.sqrt_price_after_x96;
+            pool.tick = quote.tick_after;
+            pool.liquidity = quote.liquidity_after;
+            amount = quote.amount_out;
+            hop_amounts_out.push(amount);
+        }
+
+        Ok(hop_amounts_out)
+    }
+
+    /// Find cycles from `token` back to itself that return more than they take, after fees
+    ///
+    /// Cycles are walked over the routing graph through at most `max_hops` distinct pools,
+    /// so two fee tiers of one pair form a two-hop cycle. Each cycle's input is sized in
+    /// `[0, max_amount_in]` to maximize profit, with every hop quoted like a real swap and
+    /// a pool used twice seeing its own price impact. Profit is in `token`; the result is
+    /// ranked by it, best first.
+    pub fn find_arbitrage_cycles(
+        &self,
+        token: &Pubkey,
+        max_hops: u8,
+        max_amount_in: U256,
+    ) -> Result<Vec<ArbitrageCycle>, ProgramError> {
+        let mut routes = Vec::new();
+        self.collect_cycles(token, max_hops as usize, &mut vec![*token], &mut Vec::new(), &mut routes);
+
+        let mut cycles = Vec::new();
+        for route in routes {
+            if let Some(cycle) = self.size_arbitrage(route, max_amount_in)? {
+                cycles.push(cycle);
+            }
+        }
+        cycles.sort_by_key(|cycle| Reverse(cycle.profit));
+
+        Ok(cycles)
+    }
+
+    /// Depth-first walk collecting every cycle that closes back at `path[0]`
+    fn collect_cycles(
+        &self,
+        start: &Pubkey,
+        max_hops: usize,
+        path: &mut Vec<Pubkey>,
+        pools: &mut Vec<(Pubkey, Pubkey, u32)>,
+        routes: &mut Vec<MultiHopRoute>,
+    ) {
+        let last_token = *path.last().unwrap();
+        let neighbors = match self.routing_graph.get(&last_token) {
+            Some(neighbors) => neighbors,
+            None => return,
+        };
+
+        for (neighbor, fee) in neighbors {
+            let pool_key = self.pool_key(&last_token, neighbor, *fee);
+            if pools.contains(&pool_key) {
+                continue;
+            }
+
+            if neighbor == start {
+                let mut cycle_path = path.clone();
+                cycle_path.push(*start);
+                let mut cycle_pools = pools.clone();
+                cycle_pools.push(pool_key);
+                let fees = cycle_pools.iter().map(|&(_, _, fee)| fee).collect();
+                routes.push(MultiHopRoute { path: cycle_path, fees, pools: cycle_pools });
+            } else if pools.len() + 1 < max_hops && !path.contains(neighbor) {
+                path.push(*neighbor);
+                pools.push(pool_key);
+                self.collect_cycles(start, max_hops, path, pools, routes);
+                path.pop();
+                pools.pop();
+            }
+        }
+    }
+
+    /// Size the input of a cycle for maximum profit, or `None` if it never profits
+    ///
+    /// Output along a cycle is concave in its input, so profit rises to a single peak:
+    /// a cycle that loses on a small probe loses on any larger input, and otherwise a
+    /// ternary search finds the peak.
+    fn size_arbitrage(&self, route: MultiHopRoute, max_amount_in: U256) -> Result<Option<ArbitrageCycle>, ProgramError> {
+        let probe = (max_amount_in / U256::from(1000)).max(U256_ONE);
+        if probe > max_amount_in || self.quote_cycle(&route, probe)? <= probe {
+            return Ok(None);
+        }
+
+        let mut low = U256_ZERO;
+        let mut high = max_amount_in;
+        while high - low > U256::from(2) {
+            let third = (high - low) / U256::from(3);
+            let (m1, m2) = (low + third, high - third);
+            // profit(m1) < profit(m2), without going negative
+            if self.quote_cycle(&route, m1)? + m2 < self.quote_cycle(&route, m2)? + m1 {
+                low = m1;
+            } else {
+                high = m2;
+            }
+        }
+
+        let mut best: Option<(U256, U256)> = None;
+        let mut amount_in = low.max(U256_ONE);
+        while amount_in <= high {
+            let amount_out = self.quote_cycle(&route, amount_in)?;
+            if amount_out > amount_in && best.is_none_or(|(i, o)| amount_out - amount_in > o - i) {
+                best = Some((amount_in, amount_out));
+            }
+            amount_in += U256_ONE;
+        }
+
+        let (amount_in, _) = match best {
+            Some(best) => best,
+            None => return Ok(None),
+        };
+        let hop_amounts_out = self.simulate_route(&mut self.route_pools(std::slice::from_ref(&route)), &route, amount_in)?;
+        let amount_out = *hop_amounts_out.last().unwrap();
+
+        Ok(Some(ArbitrageCycle {
+            profit: amount_out - amount_in,
+            quote: RouteQuote {
+                route,
+                amount_in,
+                hop_amounts_out,
+                amount_out,
+            },
+        }))
+    }
+
+    /// Output of a cycle for `amount_in`
+    fn quote_cycle(&self, route: &MultiHopRoute, amount_in: U256) -> Result<U256, ProgramError> {
+        let mut pools = self.route_pools(std::slice::from_ref(route));
+        let hop_amounts_out = self.simulate_route(&mut pools, route, amount_in)?;
+        Ok(hop_amounts_out.last().copied().unwrap_or(U256_ZERO))
+    }
+
     /// Get available tokens in the system
     pub fn get_available_tokens(&self) -> Vec<Pubkey> {
         let mut tokens = std::collections::HashSet::new();
@@ -256,19 +642,11 @@ impl MultiHopRouter {
 
     /// Get pools for a specific token pair
     pub fn get_pools_for_pair(&self, token_a: &Pubkey, token_b: &Pubkey) -> Vec<&Pool> {
-        let mut pools = Vec::new();
-
-        let key1 = (*token_a, *token_b, 0);
-        let key2 = (*token_b, *token_a, 0);
-
-        if let Some(pool) = self.pools.get(&key1) {
-            pools.push(pool);
-        }
-        if let Some(pool) = self.pools.get(&key2) {
-            pools.push(pool);
-        }
-
-        pools
+        self.pools
+            .iter()
+            .filter(|((a, b, _), _)| (a == token_a && b == token_b) || (a == token_b && b == token_a))
+            .map(|(_, pool)| pool)
+            .collect()
     }
 }
 
@@ -280,6 +658,70 @@ pub struct MultiHopRoute {
     pub pools: Vec<(Pubkey, Pubkey, u32)>, // Pool keys for each hop
 }
 
+/// A route with the quoted output of each hop
+#[derive(Debug, Clone)]
+pub struct RouteQuote {
+    pub route: MultiHopRoute,
+    pub amount_in: U256,
+    /// Output of each hop, which is the input of the next
+    pub hop_amounts_out: Vec<U256>,
+    pub amount_out: U256,
+}
+
+/// A profitable cycle sized for maximum profit
+#[derive(Debug, Clone)]
+pub struct ArbitrageCycle {
+    /// The cycle at its optimal input, starting and ending at the same token
+    pub quote: RouteQuote,
+    /// Output minus input, in the cycle's token
+    pub profit: U256,
+}
+
+/// Partial route in `find_top_routes`, linked to the route it extends
+#[derive(Debug, Clone)]
+struct SearchNode {
+    token: Pubkey,
+    /// Pool crossed to reach `token`, none for the start
+    pool: Option<(Pubkey, Pubkey, u32)>,
+    /// Quoted amount of `token` held at this point
+    amount: U256,
+    hops: u8,
+    parent: Option<usize>,
+}
+
+/// Share of a split order sent through one route
+#[derive(Debug, Clone)]
+pub struct SplitRoute {
+    pub route: MultiHopRoute,
+    /// Input allocated to the route
+    pub amount_in: U256,
+    /// Quoted output of the route for that input
+    pub amount_out: U256,
+}
+
+/// An order split across several routes
+#[derive(Debug, Clone)]
+pub struct SplitRouteQuote {
+    pub amount_in: U256,
+    /// Total output across all splits
+    pub amount_out: U256,
+    /// Routes with a non-zero allocation
+    pub splits: Vec<SplitRoute>,
+}
+
+impl SplitRouteQuote {
+    /// Share of the input sent through each split, in basis points
+    pub fn allocation_bps(&self) -> Vec<u32> {
+        if self.amount_in == U256_ZERO {
+            return vec![0; self.splits.len()];
+        }
+        self.splits
+            .iter()
+            .map(|split| (split.amount_in * U256::from(10000) / self.amount_in).low_u32())
+            .collect()
+    }
+}
+
 /// Result of a multi-hop swap execution
 #[derive(Debug, Clone)]
 pub struct MultiHopSwapResult {
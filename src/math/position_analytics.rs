@@ -0,0 +1,196 @@
+use crate::math::fixed_point::FixedPointMath;
+use crate::math::tick_math::{TickMath, U256};
+use crate::state::{Pool, Position, Tick};
+use solana_program::program_error::ProgramError;
+
+/// Seconds in a 365-day year, for annualizing fee yield
+pub const SECONDS_PER_YEAR: f64 = 31_536_000.0;
+
+/// Valuation, PnL and fee yield of a liquidity position
+pub struct PositionAnalyzer;
+
+/// What was put into a position: the entry price and the amounts paid for its liquidity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionEntry {
+    /// Pool sqrt price at entry
+    pub sqrt_price_x96: U256,
+    /// Total liquidity added
+    pub liquidity: U256,
+    /// Token0 deposited
+    pub amount0: u64,
+    /// Token1 deposited
+    pub amount1: u64,
+    /// Deposits valued in token1 at the pool price of each deposit
+    pub value1: U256,
+    /// When the position was opened
+    pub timestamp: u32,
+}
+
+impl From<&Position> for PositionEntry {
+    fn from(position: &Position) -> Self {
+        PositionEntry {
+            sqrt_price_x96: position.entry_sqrt_price_x96,
+            liquidity: position.liquidity_added,
+            amount0: position.amount0_deposited,
+            amount1: position.amount1_deposited,
+            value1: position.deposit_value1,
+            timestamp: position.created_at,
+        }
+    }
+}
+
+/// An amount of both tokens expressed in units of either one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenValue {
+    pub in_token0: f64,
+    pub in_token1: f64,
+}
+
+impl TokenValue {
+    /// Value `amount0` and `amount1` at `price` (token1 per token0)
+    pub fn at_price(amount0: f64, amount1: f64, price: f64) -> Self {
+        let in_token1 = amount0 * price + amount1;
+        TokenValue {
+            in_token0: if price > 0.0 { in_token1 / price } else { amount0 },
+            in_token1,
+        }
+    }
+
+    fn add(self, other: TokenValue) -> TokenValue {
+        TokenValue {
+            in_token0: self.in_token0 + other.in_token0,
+            in_token1: self.in_token1 + other.in_token1,
+        }
+    }
+
+    fn sub(self, other: TokenValue) -> TokenValue {
+        TokenValue {
+            in_token0: self.in_token0 - other.in_token0,
+            in_token1: self.in_token1 - other.in_token1,
+        }
+    }
+
+    fn scale(self, factor: f64) -> TokenValue {
+        TokenValue {
+            in_token0: self.in_token0 * factor,
+            in_token1: self.in_token1 * factor,
+        }
+    }
+}
+
+/// Analytics report for a position at the pool's current price
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionReport {
+    /// Token0 principal the position's liquidity holds now
+    pub amount0: U256,
+    /// Token1 principal the position's liquidity holds now
+    pub amount1: U256,
+    /// Token0 fees earned but not yet collected
+    pub fees_owed0: U256,
+    /// Token1 fees earned but not yet collected
+    pub fees_owed1: U256,
+    /// Current principal plus uncollected fees
+    pub value: TokenValue,
+    /// What the deposited amounts would be worth if they had simply been held
+    pub hold_value: TokenValue,
+    /// Principal now and already withdrawn versus holding, as a fraction; negative is a loss
+    pub divergence_loss: f64,
+    /// Withdrawn principal and collected fees, less the cost of the liquidity withdrawn
+    pub realized_pnl: TokenValue,
+    /// Current principal and uncollected fees, less the cost of the liquidity still held
+    pub unrealized_pnl: TokenValue,
+    /// Collected and uncollected fees over the hold value, annualized since entry
+    pub fee_apr: f64,
+}
+
+impl PositionAnalyzer {
+    /// Analyze `position` in `pool`, given its boundary ticks and entry
+    ///
+    /// Everything is valued at the pool's current price, except the cost basis used for
+    /// PnL, which values each deposit at the price it was made at. Withdrawn liquidity is
+    /// costed pro rata by its share of `entry.liquidity`.
+    pub fn analyze(
+        position: &Position,
+        pool: &Pool,
+        entry: &PositionEntry,
+        tick_lower: &Tick,
+        tick_upper: &Tick,
+        current_timestamp: u32,
+    ) -> Result<PositionReport, ProgramError> {
+        let sqrt_price_lower = TickMath::get_sqrt_ratio_at_tick(position.tick_lower)?;
+        let sqrt_price_upper = TickMath::get_sqrt_ratio_at_tick(position.tick_upper)?;
+        let (amount0, amount1) = FixedPointMath::get_amounts_for_liquidity_at_price(
+            pool.sqrt_price_x96,
+            sqrt_price_lower,
+            sqrt_price_upper,
+            position.liquidity,
+        )?;
+
+        // Fees owed so far plus those accrued since the last checkpoint
+        let (fee_growth_inside0, fee_growth_inside1) = Tick::fee_growth_inside(
+            tick_lower,
+            tick_upper,
+            pool.tick,
+            pool.fee_growth_global0_x128,
+            pool.fee_growth_global1_x128,
+        );
+        let (accrued0, accrued1) = position.fees_earned(fee_growth_inside0, fee_growth_inside1)?;
+        let fees_owed0 = position.tokens_owed0.saturating_add(accrued0);
+        let fees_owed1 = position.tokens_owed1.saturating_add(accrued1);
+
+        let price = FixedPointMath::sqrt_price_x96_to_price(pool.sqrt_price_x96);
+        let value_of = |a0: f64, a1: f64| TokenValue::at_price(a0, a1, price);
+
+        let principal = value_of(to_f64(amount0), to_f64(amount1));
+        let uncollected_fees = value_of(to_f64(fees_owed0), to_f64(fees_owed1));
+        let withdrawn = value_of(position.amount0_withdrawn as f64, position.amount1_withdrawn as f64);
+        let collected_fees = value_of(position.fees0_collected as f64, position.fees1_collected as f64);
+        let hold_value = value_of(entry.amount0 as f64, entry.amount1 as f64);
+        let cost = value_of(0.0, to_f64(entry.value1));
+
+        let divergence_loss = if hold_value.in_token1 > 0.0 {
+            (principal.in_token1 + withdrawn.in_token1) / hold_value.in_token1 - 1.0
+        } else {
+            0.0
+        };
+
+        let withdrawn_share = if entry.liquidity.is_zero() {
+            0.0
+        } else {
+            1.0 - to_f64(position.liquidity) / to_f64(entry.liquidity)
+        };
+        let value = principal.add(uncollected_fees);
+        let realized_pnl = withdrawn.add(collected_fees).sub(cost.scale(withdrawn_share));
+        let unrealized_pnl = value.sub(cost.scale(1.0 - withdrawn_share));
+
+        let elapsed = current_timestamp.saturating_sub(entry.timestamp) as f64;
+        let fee_apr = if elapsed > 0.0 && hold_value.in_token1 > 0.0 {
+            (collected_fees.in_token1 + uncollected_fees.in_token1) / hold_value.in_token1
+                * (SECONDS_PER_YEAR / elapsed)
+        } else {
+            0.0
+        };
+
+        Ok(PositionReport {
+            amount0,
+            amount1,
+            fees_owed0,
+            fees_owed1,
+            value,
+            hold_value,
+            divergence_loss,
+            realized_pnl,
+            unrealized_pnl,
+            fee_apr,
+        })
+    }
+}
+
+/// Token amount as a float; amounts beyond 128 bits saturate
+fn to_f64(amount: U256) -> f64 {
+    if amount.bits() > 128 {
+        u128::MAX as f64
+    } else {
+        amount.low_u128() as f64
+    }
+}
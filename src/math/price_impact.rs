@@ -1,7 +1,9 @@
-use crate::math::tick_math::{U256, Q96, U256_ZERO};
+use crate::math::tick_math::{U256, Q96, U256_ZERO, U256_ONE, MAX_SQRT_RATIO, MIN_SQRT_RATIO};
 use crate::math::fixed_point::FixedPointMath;
-use crate::state::Pool;
+use crate::math::quoter::{Quote, Quoter};
+use crate::state::{Pool, Tick};
 use solana_program::program_error::ProgramError;
+use std::collections::BTreeMap;
 
 /// Advanced price impact calculator with slippage protection
 pub struct PriceImpactCalculator;
@@ -84,6 +86,9 @@ impl PriceImpactCalculator {
     }
 
     /// Calculate optimal swap amount to minimize price impact
+    ///
+    /// Estimates from the spot price alone; `max_amount_for_impact` sizes the trade
+    /// exactly against the pool's ticks.
     pub fn calculate_optimal_swap_amount(
         pool: &Pool,
         target_price_impact_bps: u32,
@@ -99,6 +104,9 @@ impl PriceImpactCalculator {
         let mut optimal_amount = U256_ZERO;
 
         for _ in 0..64 {
+            if low > high {
+                break;
+            }
             let mid = (low + high) / U256::from(2);
             let impact_result = Self::calculate_price_impact(pool, mid, zero_for_one)?;
 
@@ -113,6 +121,60 @@ impl PriceImpactCalculator {
         Ok(optimal_amount)
     }
 
+    /// Largest trade that moves the price by at most `impact_bps` in the swap direction
+    ///
+    /// Walks `ticks` to the sqrt price `impact_bps` away from the current price, clamped
+    /// to the valid price range, and quotes the exact input (fee included) to get there.
+    pub fn max_amount_for_impact(
+        pool: &Pool,
+        ticks: &BTreeMap<i32, Tick>,
+        impact_bps: u32,
+        zero_for_one: bool,
+    ) -> Result<Quote, ProgramError> {
+        let sqrt_price_target = Self::sqrt_price_at_impact(pool.sqrt_price_x96, impact_bps, zero_for_one)?;
+        Quoter::quote_to_sqrt_price(pool, ticks, sqrt_price_target, zero_for_one)
+    }
+
+    /// Exact trade size at each of `impacts_bps`, in the order given
+    pub fn calculate_slippage_curve(
+        pool: &Pool,
+        ticks: &BTreeMap<i32, Tick>,
+        impacts_bps: &[u32],
+        zero_for_one: bool,
+    ) -> Result<Vec<SlippagePoint>, ProgramError> {
+        impacts_bps
+            .iter()
+            .map(|&impact_bps| {
+                let quote = Self::max_amount_for_impact(pool, ticks, impact_bps, zero_for_one)?;
+                Ok(SlippagePoint {
+                    impact_bps,
+                    sqrt_price_x96: quote.sqrt_price_after_x96,
+                    amount_in: quote.amount_in,
+                    amount_out: quote.amount_out,
+                    fee_amount: quote.fee_amount,
+                })
+            })
+            .collect()
+    }
+
+    /// Sqrt price `impact_bps` below (zero for one) or above the current price
+    fn sqrt_price_at_impact(
+        sqrt_price_x96: U256,
+        impact_bps: u32,
+        zero_for_one: bool,
+    ) -> Result<U256, ProgramError> {
+        if zero_for_one {
+            if impact_bps >= 10000 {
+                return Ok(MIN_SQRT_RATIO + U256_ONE);
+            }
+            let target = FixedPointMath::scale_sqrt_price_x96(sqrt_price_x96, 10000 - impact_bps as u64)?;
+            Ok(target.max(MIN_SQRT_RATIO + U256_ONE))
+        } else {
+            let target = FixedPointMath::scale_sqrt_price_x96(sqrt_price_x96, 10000 + impact_bps as u64)?;
+            Ok(target.min(MAX_SQRT_RATIO - U256_ONE))
+        }
+    }
+
     /// Get recommended slippage protection based on price impact
     pub fn get_recommended_slippage_bps(impact_bps: u32) -> u32 {
         match Self::classify_impact_severity(impact_bps) {
@@ -136,38 +198,37 @@ impl PriceImpactCalculator {
     }
 
     /// Calculate impermanent loss for a position (bonus feature)
+    ///
+    /// Compares the position's value at the current price with holding the tokens it
+    /// held at the entry price. The result is a fraction, negative for a loss.
     pub fn calculate_impermanent_loss(
         position_lower_sqrt_price: U256,
         position_upper_sqrt_price: U256,
+        entry_sqrt_price: U256,
         current_sqrt_price: U256,
         initial_liquidity: U256,
     ) -> Result<f64, ProgramError> {
-        let position_price_lower = FixedPointMath::sqrt_price_x96_to_price(position_lower_sqrt_price);
-        let position_price_upper = FixedPointMath::sqrt_price_x96_to_price(position_upper_sqrt_price);
         let current_price = FixedPointMath::sqrt_price_x96_to_price(current_sqrt_price);
 
         // Calculate amounts at current price
-        let (amount0_current, amount1_current) = FixedPointMath::get_amounts_for_liquidity(
+        let (amount0_current, amount1_current) = FixedPointMath::get_amounts_for_liquidity_at_price(
+            current_sqrt_price,
             position_lower_sqrt_price,
             position_upper_sqrt_price,
             initial_liquidity,
-        );
+        )?;
 
-        // Calculate amounts if price stayed the same (HODL)
-        let hodl_amount0 = FixedPointMath::get_amount0_for_liquidity(
+        // Calculate amounts deposited at the entry price (HODL)
+        let (hodl_amount0, hodl_amount1) = FixedPointMath::get_amounts_for_liquidity_at_price(
+            entry_sqrt_price,
             position_lower_sqrt_price,
             position_upper_sqrt_price,
             initial_liquidity,
-        );
-        let hodl_amount1 = FixedPointMath::get_amount1_for_liquidity(
-            position_lower_sqrt_price,
-            position_upper_sqrt_price,
-            initial_liquidity,
-        );
+        )?;
 
-        // Calculate current value vs HODL value
-        let current_value = (amount0_current.low_u128() as f64) + (amount1_current.low_u128() as f64) * current_price;
-        let hodl_value = (hodl_amount0.low_u128() as f64) + (hodl_amount1.low_u128() as f64) * current_price;
+        // Calculate current value vs HODL value, both in token1 at the current price
+        let current_value = (amount0_current.low_u128() as f64) * current_price + (amount1_current.low_u128() as f64);
+        let hodl_value = (hodl_amount0.low_u128() as f64) * current_price + (hodl_amount1.low_u128() as f64);
 
         if hodl_value == 0.0 {
             return Ok(0.0);
@@ -178,6 +239,21 @@ impl PriceImpactCalculator {
     }
 }
 
+/// Trade size for one point of a slippage curve
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlippagePoint {
+    /// Price impact in basis points
+    pub impact_bps: u32,
+    /// Sqrt price the trade ends at
+    pub sqrt_price_x96: U256,
+    /// Input needed, fee included
+    pub amount_in: U256,
+    /// Output received
+    pub amount_out: U256,
+    /// Fee paid
+    pub fee_amount: U256,
+}
+
 /// Price impact analysis result
 #[derive(Debug, Clone)]
 pub struct PriceImpactResult {
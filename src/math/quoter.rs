@@ -0,0 +1,218 @@
+use crate::error::CLMMError;
+use crate::math::swap::SwapEngine;
+use crate::math::tick_math::{TickMath, I256, U256, U256_ZERO, MIN_TICK, MAX_TICK};
+use crate::state::{Pool, Tick};
+use solana_program::program_error::ProgramError;
+use std::collections::BTreeMap;
+
+/// Read-only swap quoter
+///
+/// Runs the same step math as `SwapEngine` against a pool and a snapshot of its
+/// initialized ticks, without touching either and without oracle or fee history.
+/// The pool's current `fee` is used as-is; the dynamic fee adjustment that
+/// `SwapEngine` applies before a swap is not simulated.
+pub struct Quoter;
+
+/// One stretch of constant liquidity traversed by a quoted swap
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteStep {
+    /// Sqrt price at the start of the step
+    pub sqrt_price_start_x96: U256,
+    /// Sqrt price at the end of the step
+    pub sqrt_price_end_x96: U256,
+    /// Active liquidity during the step
+    pub liquidity: U256,
+    /// Input spent in the step, excluding the fee
+    pub amount_in: U256,
+    /// Output received in the step
+    pub amount_out: U256,
+    /// Fee paid in the step
+    pub fee_amount: U256,
+    /// Initialized tick crossed at the end of the step, if any
+    pub tick_crossed: Option<i32>,
+}
+
+/// Result of a quoted swap
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    /// Total input, fee included
+    pub amount_in: U256,
+    /// Total output
+    pub amount_out: U256,
+    /// Total fee paid
+    pub fee_amount: U256,
+    /// Sqrt price after the swap
+    pub sqrt_price_after_x96: U256,
+    /// Tick after the swap
+    pub tick_after: i32,
+    /// Active liquidity after the swap
+    pub liquidity_after: U256,
+    /// Initialized ticks crossed, in order
+    pub ticks_crossed: Vec<i32>,
+    /// Per-step trace
+    pub steps: Vec<QuoteStep>,
+}
+
+impl Quoter {
+    /// Quote a swap of an exact input amount (fee included)
+    pub fn quote_exact_input(
+        pool: &Pool,
+        ticks: &BTreeMap<i32, Tick>,
+        amount_in: U256,
+        zero_for_one: bool,
+        sqrt_price_limit: U256,
+    ) -> Result<Quote, ProgramError> {
+        Self::quote(pool, ticks, amount_in, true, zero_for_one, sqrt_price_limit)
+    }
+
+    /// Quote a swap for an exact output amount
+    pub fn quote_exact_output(
+        pool: &Pool,
+        ticks: &BTreeMap<i32, Tick>,
+        amount_out: U256,
+        zero_for_one: bool,
+        sqrt_price_limit: U256,
+    ) -> Result<Quote, ProgramError> {
+        Self::quote(pool, ticks, amount_out, false, zero_for_one, sqrt_price_limit)
+    }
+
+    /// Quote the input needed to move the price exactly to `sqrt_price_target`
+    ///
+    /// Every step runs to its boundary, so each one is the closed-form amount for its
+    /// stretch of liquidity plus its fee, and the total is exact across crossed ticks.
+    /// Swapping `amount_in` with `sqrt_price_target` as the limit lands on the target.
+    pub fn quote_to_sqrt_price(
+        pool: &Pool,
+        ticks: &BTreeMap<i32, Tick>,
+        sqrt_price_target: U256,
+        zero_for_one: bool,
+    ) -> Result<Quote, ProgramError> {
+        if sqrt_price_target == pool.sqrt_price_x96 {
+            return Ok(Quote {
+                amount_in: U256_ZERO,
+                amount_out: U256_ZERO,
+                fee_amount: U256_ZERO,
+                sqrt_price_after_x96: pool.sqrt_price_x96,
+                tick_after: pool.tick,
+                liquidity_after: pool.liquidity,
+                ticks_crossed: Vec::new(),
+                steps: Vec::new(),
+            });
+        }
+
+        // An unbounded input only stops at the target
+        Self::quote(pool, ticks, U256::MAX, true, zero_for_one, sqrt_price_target)
+    }
+
+    /// Shared quote loop, mirroring `SwapEngine`'s swap loop on local copies of the pool state
+    fn quote(
+        pool: &Pool,
+        ticks: &BTreeMap<i32, Tick>,
+        amount_specified: U256,
+        exact_input: bool,
+        zero_for_one: bool,
+        sqrt_price_limit: U256,
+    ) -> Result<Quote, ProgramError> {
+        if !pool.unlocked || pool.flash_in_progress {
+            return Err(CLMMError::PoolLocked.into());
+        }
+        if !SwapEngine::validate_price_limit(pool.sqrt_price_x96, sqrt_price_limit, zero_for_one) {
+            return Err(CLMMError::InvalidPrice.into());
+        }
+
+        let mut sqrt_price = pool.sqrt_price_x96;
+        let mut tick = pool.tick;
+        let mut liquidity = pool.liquidity;
+        let mut amount_remaining = amount_specified;
+        let mut amount_in = U256_ZERO;
+        let mut amount_out = U256_ZERO;
+        let mut fee_amount = U256_ZERO;
+        let mut ticks_crossed = Vec::new();
+        let mut steps = Vec::new();
+
+        while amount_remaining > U256_ZERO && sqrt_price != sqrt_price_limit {
+            let (next_tick, next_liquidity_net) = Self::next_initialized_tick(ticks, tick, zero_for_one);
+            let next_sqrt_price = TickMath::get_sqrt_ratio_at_tick(next_tick)?;
+
+            let target_sqrt_price = if zero_for_one {
+                next_sqrt_price.max(sqrt_price_limit)
+            } else {
+                next_sqrt_price.min(sqrt_price_limit)
+            };
+
+            let (new_sqrt_price, step_in, step_out, step_fee) = SwapEngine::compute_swap_step(
+                sqrt_price,
+                target_sqrt_price,
+                liquidity,
+                amount_remaining,
+                exact_input,
+                pool.fee,
+            )?;
+
+            let mut step = QuoteStep {
+                sqrt_price_start_x96: sqrt_price,
+                sqrt_price_end_x96: new_sqrt_price,
+                liquidity,
+                amount_in: step_in,
+                amount_out: step_out,
+                fee_amount: step_fee,
+                tick_crossed: None,
+            };
+
+            amount_in += step_in + step_fee;
+            amount_out += step_out;
+            fee_amount += step_fee;
+            let consumed = if exact_input { step_in + step_fee } else { step_out };
+            amount_remaining = amount_remaining
+                .checked_sub(consumed)
+                .ok_or(CLMMError::MathOverflow)?;
+
+            if new_sqrt_price == next_sqrt_price {
+                if let Some(liquidity_net) = next_liquidity_net {
+                    liquidity = SwapEngine::apply_liquidity_net(liquidity, liquidity_net, zero_for_one)?;
+                    ticks_crossed.push(next_tick);
+                    step.tick_crossed = Some(next_tick);
+                }
+                tick = if zero_for_one { next_tick - 1 } else { next_tick };
+            } else if new_sqrt_price != sqrt_price {
+                tick = TickMath::get_tick_at_sqrt_ratio(new_sqrt_price)?;
+            }
+
+            sqrt_price = new_sqrt_price;
+            steps.push(step);
+        }
+
+        Ok(Quote {
+            amount_in,
+            amount_out,
+            fee_amount,
+            sqrt_price_after_x96: sqrt_price,
+            tick_after: tick,
+            liquidity_after: liquidity,
+            ticks_crossed,
+            steps,
+        })
+    }
+
+    /// Next initialized tick in the swap direction, with its net liquidity.
+    ///
+    /// Searching down includes `tick` itself, searching up starts above it. Without an
+    /// initialized tick the search ends at `MIN_TICK`/`MAX_TICK` with no liquidity change.
+    fn next_initialized_tick(
+        ticks: &BTreeMap<i32, Tick>,
+        tick: i32,
+        zero_for_one: bool,
+    ) -> (i32, Option<I256>) {
+        let next = if zero_for_one {
+            ticks.range(..=tick).rev().find(|(_, t)| t.initialized)
+        } else {
+            ticks.range(tick.saturating_add(1)..).find(|(_, t)| t.initialized)
+        };
+
+        match next {
+            Some((&index, t)) => (index, Some(t.liquidity_net)),
+            None if zero_for_one => (MIN_TICK, None),
+            None => (MAX_TICK, None),
+        }
+    }
+}
@@ -1,15 +1,32 @@
 use crate::error::CLMMError;
-use crate::math::tick_math::{U256, Q96, U256_ZERO};
+use crate::math::tick_math::{
+    TickMath, I256, U256, Q96, U256_ZERO, U256_ONE, MIN_TICK, MAX_TICK, MIN_SQRT_RATIO,
+    MAX_SQRT_RATIO,
+};
 use crate::math::fixed_point::FixedPointMath;
 use crate::math::dynamic_fee::{DynamicFeeEngine, MarketDataPoint};
 use crate::math::mev_protection::{MevProtectionEngine, OracleObservation};
-use crate::state::Pool;
+use crate::state::{Pool, Tick, TickBitmap};
 use solana_program::program_error::ProgramError;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 /// Advanced swap engine with price impact calculation and slippage protection
 pub struct SwapEngine;
 
+/// Tick and market state a swap reads and updates alongside the pool
+pub struct SwapContext<'a> {
+    /// Bitmap words marking the initialized ticks, keyed by word position
+    pub tick_bitmaps: &'a BTreeMap<i16, TickBitmap>,
+    /// Initialized ticks the swap may reach, keyed by tick index
+    pub ticks: &'a mut HashMap<i32, Tick>,
+    pub price_history: &'a mut VecDeque<MarketDataPoint>,
+    pub volume_history: &'a mut VecDeque<MarketDataPoint>,
+    pub impact_history: &'a mut VecDeque<MarketDataPoint>,
+    pub oracle_observations: &'a mut VecDeque<OracleObservation>,
+    pub current_timestamp: u32,
+    pub sequence_number: u64,
+}
+
 impl SwapEngine {
     /// Update dynamic fees based on market conditions
     pub fn update_dynamic_fees(
@@ -60,25 +77,64 @@ impl SwapEngine {
         Ok(true) // Fee was adjusted
     }
     /// Execute a swap with advanced features, dynamic fee adjustment, and MEV protection
+    ///
+    /// The swap walks `ctx.tick_bitmaps` (bitmap words keyed by word position) to find the
+    /// next initialized tick and crosses every tick it fully passes, updating `pool.liquidity`
+    /// and the tick's outside accumulators in `ctx.ticks`. Every initialized tick the swap
+    /// reaches must be present in `ctx.ticks`.
     pub fn execute_swap(
         pool: &mut Pool,
+        ctx: &mut SwapContext,
         amount_in: U256,
         zero_for_one: bool,
         sqrt_price_limit: U256,
         recipient: &solana_program::pubkey::Pubkey,
-        price_history: &mut VecDeque<MarketDataPoint>,
-        volume_history: &mut VecDeque<MarketDataPoint>,
-        impact_history: &mut VecDeque<MarketDataPoint>,
-        oracle_observations: &mut VecDeque<OracleObservation>,
-        current_timestamp: u32,
-        sequence_number: u64,
     ) -> Result<SwapResult, ProgramError> {
-        if !pool.unlocked {
-            return Err(CLMMError::Unauthorized.into());
+        Self::execute_swap_internal(pool, ctx, amount_in, true, zero_for_one, sqrt_price_limit, recipient)
+    }
+
+    /// Execute a swap that delivers exactly `amount_out` of the output token
+    ///
+    /// Walks and crosses ticks like `execute_swap`, but each step is sized by the output
+    /// still owed. The required input is rounded up in the pool's favor and returned in
+    /// `SwapResult::amount_in`, fee included. If `sqrt_price_limit` is reached first the
+    /// swap stops short and `amount_out` is less than requested.
+    pub fn execute_swap_exact_out(
+        pool: &mut Pool,
+        ctx: &mut SwapContext,
+        amount_out: U256,
+        zero_for_one: bool,
+        sqrt_price_limit: U256,
+        recipient: &solana_program::pubkey::Pubkey,
+    ) -> Result<SwapResult, ProgramError> {
+        Self::execute_swap_internal(pool, ctx, amount_out, false, zero_for_one, sqrt_price_limit, recipient)
+    }
+
+    /// Shared swap loop; `amount_specified` is an input amount when `exact_input`
+    /// is set and an output amount otherwise
+    fn execute_swap_internal(
+        pool: &mut Pool,
+        ctx: &mut SwapContext,
+        amount_specified: U256,
+        exact_input: bool,
+        zero_for_one: bool,
+        sqrt_price_limit: U256,
+        _recipient: &solana_program::pubkey::Pubkey,
+    ) -> Result<SwapResult, ProgramError> {
+        if !pool.unlocked || pool.flash_in_progress {
+            return Err(CLMMError::PoolLocked.into());
         }
 
-        // Calculate price impact
-        let price_impact = Self::calculate_price_impact(pool, amount_in, zero_for_one)?;
+        // Pre-swap checks are sized by the input, which an exact-output swap only implies
+        let amount_in = if exact_input {
+            amount_specified
+        } else {
+            Self::estimate_swap_input(pool, amount_specified, zero_for_one)?
+        };
+
+        // Estimated price impact, for the dynamic fee
+        let estimated_price_impact = Self::calculate_price_impact(pool, amount_in, zero_for_one)?;
+        let sqrt_price_start = pool.sqrt_price_x96;
 
         // Check price limit
         if !Self::validate_price_limit(pool.sqrt_price_x96, sqrt_price_limit, zero_for_one) {
@@ -86,77 +142,79 @@ impl SwapEngine {
         }
 
         // Validate transaction ordering for MEV protection
-        if !MevProtectionEngine::validate_transaction_ordering(sequence_number, pool.last_sequence_number)? {
+        if !MevProtectionEngine::validate_transaction_ordering(ctx.sequence_number, pool.last_sequence_number)? {
             return Err(CLMMError::InvalidInstruction.into());
         }
 
-        // Validate swap against MEV protection measures
-        if !MevProtectionEngine::validate_swap_mev_protection(
+        // Keep the swap within max_slippage_bps of the TWAP, which must still leave room to trade
+        let sqrt_price_limit = MevProtectionEngine::bound_swap_price_limit(
             pool,
-            amount_in,
             zero_for_one,
             sqrt_price_limit,
-            oracle_observations,
+            ctx.oracle_observations,
+            ctx.current_timestamp,
             &pool.mev_config,
-        )? {
+        )?;
+        if !Self::validate_price_limit(pool.sqrt_price_x96, sqrt_price_limit, zero_for_one) {
             return Err(CLMMError::InvalidPrice.into());
         }
 
         // Update dynamic fees based on market conditions
         let fee_adjusted = Self::update_dynamic_fees(
             pool,
-            price_history,
-            volume_history,
-            impact_history,
-            current_timestamp,
+            ctx.price_history,
+            ctx.volume_history,
+            ctx.impact_history,
+            ctx.current_timestamp,
             pool.sqrt_price_x96,
             amount_in,
-            price_impact,
+            estimated_price_impact,
         )?;
 
-        let current_tick = pool.tick;
         let mut amount_out = U256_ZERO;
         let mut amount_in_used = U256_ZERO;
-
-        // Execute the swap step by step
-        while amount_in_used < amount_in {
-            let remaining_amount = amount_in - amount_in_used;
-            let step_result = Self::swap_step(pool, remaining_amount, zero_for_one)?;
-
-            amount_in_used = amount_in_used + step_result.amount_in;
-            amount_out = amount_out + step_result.amount_out;
-
-            // Check if we've hit the price limit
-            if Self::check_price_limit_hit(pool.sqrt_price_x96, sqrt_price_limit, zero_for_one) {
-                break;
-            }
-
-            // Prevent infinite loops
-            if step_result.amount_in == U256_ZERO {
-                break;
-            }
+        let mut amount_remaining = amount_specified;
+
+        // Execute the swap step by step, one initialized tick (or bitmap word) at a time
+        while amount_remaining > U256_ZERO && pool.sqrt_price_x96 != sqrt_price_limit {
+            let step_result = Self::swap_step(
+                pool,
+                ctx,
+                amount_remaining,
+                exact_input,
+                zero_for_one,
+                sqrt_price_limit,
+            )?;
+
+            let step_amount_in = step_result.amount_in + step_result.fee_amount;
+            amount_in_used += step_amount_in;
+            amount_out += step_result.amount_out;
+
+            let consumed = if exact_input { step_amount_in } else { step_result.amount_out };
+            amount_remaining = amount_remaining
+                .checked_sub(consumed)
+                .ok_or(CLMMError::MathOverflow)?;
         }
 
-        // Update pool state
-        Self::update_pool_after_swap(pool, amount_in_used, amount_out, zero_for_one)?;
+        pool.update_timestamp(ctx.current_timestamp);
 
         // Update oracle observations and sequence number
-        pool.last_sequence_number = sequence_number;
+        pool.last_sequence_number = ctx.sequence_number;
         MevProtectionEngine::update_oracle_observations(
-            oracle_observations,
+            ctx.oracle_observations,
             pool,
-            current_timestamp,
+            ctx.current_timestamp,
             100, // Max 100 oracle observations
         )?;
 
         // Calculate TWAP for result
-        let twap_price = MevProtectionEngine::calculate_twap(oracle_observations, pool.mev_config.oracle_window)
+        let twap_price = MevProtectionEngine::calculate_twap(ctx.oracle_observations, pool.mev_config.oracle_window)
             .unwrap_or(pool.sqrt_price_x96);
 
         Ok(SwapResult {
             amount_in: amount_in_used,
             amount_out,
-            price_impact,
+            price_impact: Self::realized_price_impact(sqrt_price_start, pool.sqrt_price_x96),
             final_sqrt_price: pool.sqrt_price_x96,
             final_tick: pool.tick,
             fee_adjusted,
@@ -167,81 +225,173 @@ impl SwapEngine {
     }
 
     /// Single swap step for concentrated liquidity
+    ///
+    /// Moves the price towards the next initialized tick (bounded by the price limit)
+    /// and crosses that tick if it is reached.
     fn swap_step(
         pool: &mut Pool,
+        ctx: &mut SwapContext,
         amount_remaining: U256,
+        exact_input: bool,
         zero_for_one: bool,
+        sqrt_price_limit: U256,
     ) -> Result<SwapStepResult, ProgramError> {
         let current_sqrt_price = pool.sqrt_price_x96;
-        let current_tick = pool.tick;
-        let current_liquidity = pool.liquidity;
 
         // Find the next tick to cross
-        let (next_tick, next_sqrt_price) = if zero_for_one {
+        let (next_tick, next_sqrt_price, initialized) = if zero_for_one {
             // Swapping token0 for token1 (price decreases)
-            Self::find_next_tick_down(pool, current_tick)?
+            Self::find_next_tick_down(pool, ctx.tick_bitmaps)?
         } else {
             // Swapping token1 for token0 (price increases)
-            Self::find_next_tick_up(pool, current_tick)?
+            Self::find_next_tick_up(pool, ctx.tick_bitmaps)?
         };
 
-        // Calculate maximum amount that can be swapped in this step
-        let max_amount_in_step = Self::calculate_max_amount_in_step(
-            current_sqrt_price,
-            next_sqrt_price,
-            current_liquidity,
-            zero_for_one,
-        )?;
-
-        let amount_in_step = amount_remaining.min(max_amount_in_step);
-
-        if amount_in_step == U256_ZERO {
-            return Ok(SwapStepResult {
-                amount_in: U256_ZERO,
-                amount_out: U256_ZERO,
-                sqrt_price_next: current_sqrt_price,
-                tick_next: current_tick,
-                liquidity_next: current_liquidity,
-            });
-        }
-
-        // Calculate output amount
-        let amount_out_step = if zero_for_one {
-            FixedPointMath::get_amount1_delta(
-                current_sqrt_price,
-                next_sqrt_price,
-                current_liquidity,
-                false,
-            )
+        // Never move past the caller's price limit
+        let target_sqrt_price = if zero_for_one {
+            next_sqrt_price.max(sqrt_price_limit)
         } else {
-            FixedPointMath::get_amount0_delta(
-                current_sqrt_price,
-                next_sqrt_price,
-                current_liquidity,
-                false,
-            )
+            next_sqrt_price.min(sqrt_price_limit)
         };
 
-        // Update pool state
-        let new_sqrt_price = Self::calculate_new_sqrt_price(
+        let (new_sqrt_price, amount_in_step, amount_out_step, fee_amount) = Self::compute_swap_step(
             current_sqrt_price,
-            current_liquidity,
-            amount_in_step,
-            zero_for_one,
+            target_sqrt_price,
+            pool.liquidity,
+            amount_remaining,
+            exact_input,
+            pool.fee,
         )?;
 
+        // Accrue fees against the liquidity that earned them, before any crossing
+        Self::accrue_fee(pool, fee_amount, zero_for_one)?;
+
         pool.sqrt_price_x96 = new_sqrt_price;
-        pool.tick = Self::get_tick_at_sqrt_price(new_sqrt_price)?;
+
+        if new_sqrt_price == next_sqrt_price {
+            if initialized {
+                // Skipping an initialized tick would leave the active liquidity wrong
+                let tick = ctx.ticks.get_mut(&next_tick).ok_or(CLMMError::InvalidAccount)?;
+                let liquidity_net = tick.cross(
+                    pool.fee_growth_global0_x128,
+                    pool.fee_growth_global1_x128,
+                    ctx.current_timestamp,
+                );
+                pool.liquidity = Self::apply_liquidity_net(pool.liquidity, liquidity_net, zero_for_one)?;
+                pool.tick = if zero_for_one { next_tick - 1 } else { next_tick };
+                Self::fill_range_orders(pool, ctx.ticks, next_tick, zero_for_one)?;
+            } else {
+                pool.tick = if zero_for_one { next_tick - 1 } else { next_tick };
+            }
+        } else if new_sqrt_price != current_sqrt_price {
+            pool.tick = Self::get_tick_at_sqrt_price(new_sqrt_price)?;
+        }
 
         Ok(SwapStepResult {
             amount_in: amount_in_step,
             amount_out: amount_out_step,
-            sqrt_price_next: new_sqrt_price,
-            tick_next: pool.tick,
-            liquidity_next: current_liquidity,
+            fee_amount,
         })
     }
 
+    /// Compute the result of swapping within a single price range of constant liquidity.
+    ///
+    /// `amount_remaining` is the input still to spend when `exact_input` is set, and the
+    /// output still owed otherwise. Returns `(sqrt_price_next, amount_in, amount_out,
+    /// fee_amount)`, where `amount_in` excludes the fee. The fee is taken from the input
+    /// in basis points of `fee`; input amounts round up and output amounts round down.
+    pub(crate) fn compute_swap_step(
+        sqrt_price_current: U256,
+        sqrt_price_target: U256,
+        liquidity: U256,
+        amount_remaining: U256,
+        exact_input: bool,
+        fee: u32,
+    ) -> Result<(U256, U256, U256, U256), ProgramError> {
+        let zero_for_one = sqrt_price_current >= sqrt_price_target;
+        let fee_denominator = U256::from(10000u32);
+
+        if liquidity == U256_ZERO {
+            // Nothing to trade against - the price moves freely to the target
+            return Ok((sqrt_price_target, U256_ZERO, U256_ZERO, U256_ZERO));
+        }
+
+        let max_amount_in = Self::calculate_max_amount_in_step(
+            sqrt_price_current,
+            sqrt_price_target,
+            liquidity,
+            zero_for_one,
+        )?;
+        let max_amount_out = if zero_for_one {
+            FixedPointMath::get_amount1_delta(sqrt_price_target, sqrt_price_current, liquidity, false)?
+        } else {
+            FixedPointMath::get_amount0_delta(sqrt_price_current, sqrt_price_target, liquidity, false)?
+        };
+
+        let amount_remaining_less_fee = FixedPointMath::mul_div(
+            amount_remaining,
+            fee_denominator - U256::from(fee),
+            fee_denominator,
+        )?;
+
+        let sqrt_price_next = if exact_input {
+            if amount_remaining_less_fee >= max_amount_in {
+                sqrt_price_target
+            } else {
+                Self::calculate_new_sqrt_price(
+                    sqrt_price_current,
+                    liquidity,
+                    amount_remaining_less_fee,
+                    zero_for_one,
+                )?
+            }
+        } else if amount_remaining >= max_amount_out {
+            sqrt_price_target
+        } else {
+            TickMath::get_next_sqrt_price_from_output(
+                sqrt_price_current,
+                liquidity,
+                amount_remaining,
+                zero_for_one,
+            )?
+        };
+        let reached_target = sqrt_price_next == sqrt_price_target;
+
+        let amount_in = if reached_target {
+            max_amount_in
+        } else if zero_for_one {
+            FixedPointMath::get_amount0_delta(sqrt_price_next, sqrt_price_current, liquidity, true)?
+        } else {
+            FixedPointMath::get_amount1_delta(sqrt_price_current, sqrt_price_next, liquidity, true)?
+        };
+
+        let mut amount_out = if reached_target {
+            max_amount_out
+        } else if zero_for_one {
+            FixedPointMath::get_amount1_delta(sqrt_price_next, sqrt_price_current, liquidity, false)?
+        } else {
+            FixedPointMath::get_amount0_delta(sqrt_price_current, sqrt_price_next, liquidity, false)?
+        };
+
+        // Never pay out more than was asked for
+        if !exact_input && amount_out > amount_remaining {
+            amount_out = amount_remaining;
+        }
+
+        let fee_amount = if exact_input && !reached_target {
+            // The price did not reach the target, so the whole remainder is consumed
+            amount_remaining - amount_in
+        } else {
+            FixedPointMath::mul_div_rounding_up(
+                amount_in,
+                U256::from(fee),
+                fee_denominator - U256::from(fee),
+            )?
+        };
+
+        Ok((sqrt_price_next, amount_in, amount_out, fee_amount))
+    }
+
     /// Calculate price impact of a swap
     pub fn calculate_price_impact(
         pool: &Pool,
@@ -297,50 +447,175 @@ impl SwapEngine {
         }
     }
 
-    /// Validate price limit for swap
-    fn validate_price_limit(
-        current_price: U256,
-        limit_price: U256,
+    /// Estimate the input, fee included, that buys `amount_out` at the spot price
+    pub fn estimate_swap_input(
+        pool: &Pool,
+        amount_out: U256,
         zero_for_one: bool,
-    ) -> bool {
-        if zero_for_one {
-            // Price decreasing, limit should be >= current price
-            limit_price >= current_price
+    ) -> Result<U256, ProgramError> {
+        let sqrt_price = pool.sqrt_price_x96;
+        let amount_after_fee = if zero_for_one {
+            // Token0 -> Token1: token0 needed is amount_out / price
+            let scaled = FixedPointMath::mul_div_rounding_up(amount_out, Q96, sqrt_price)?;
+            FixedPointMath::mul_div_rounding_up(scaled, Q96, sqrt_price)?
         } else {
-            // Price increasing, limit should be <= current price
-            limit_price <= current_price
+            // Token1 -> Token0: token1 needed is amount_out * price
+            let scaled = FixedPointMath::mul_div_rounding_up(amount_out, sqrt_price, Q96)?;
+            FixedPointMath::mul_div_rounding_up(scaled, sqrt_price, Q96)?
+        };
+
+        // Gross the input back up by the fee taken from it
+        let fee_denominator = U256::from(10000u32);
+        FixedPointMath::mul_div_rounding_up(
+            amount_after_fee,
+            fee_denominator,
+            fee_denominator - U256::from(pool.fee),
+        )
+    }
+
+    /// Price movement from `sqrt_price_start` to `sqrt_price_end` in basis points, capped at 100%
+    pub fn realized_price_impact(sqrt_price_start: U256, sqrt_price_end: U256) -> u32 {
+        let start_price = FixedPointMath::sqrt_price_x96_to_price(sqrt_price_start);
+        if start_price <= 0.0 {
+            return 10000;
         }
+        let end_price = FixedPointMath::sqrt_price_x96_to_price(sqrt_price_end);
+        let impact = ((end_price - start_price).abs() / start_price) * 10000.0;
+        impact.min(10000.0) as u32
     }
 
-    /// Check if price limit is hit
-    fn check_price_limit_hit(
+    /// Validate price limit for swap
+    pub(crate) fn validate_price_limit(
         current_price: U256,
         limit_price: U256,
         zero_for_one: bool,
     ) -> bool {
         if zero_for_one {
-            current_price <= limit_price
+            // Price decreasing, limit must be below the current price
+            limit_price < current_price && limit_price > MIN_SQRT_RATIO
         } else {
-            current_price >= limit_price
+            // Price increasing, limit must be above the current price
+            limit_price > current_price && limit_price < MAX_SQRT_RATIO
         }
     }
 
-    /// Find next tick moving down (for zero_for_one swaps)
-    fn find_next_tick_down(pool: &mut Pool, current_tick: i32) -> Result<(i32, U256), ProgramError> {
-        // Simplified - in a real implementation, this would search the tick bitmap
-        let next_tick = current_tick - pool.tick_spacing as i32;
-        let next_sqrt_price = crate::math::TickMath::get_sqrt_ratio_at_tick(next_tick)?;
+    /// Find next initialized tick at or below the current tick (for zero_for_one swaps).
+    ///
+    /// Returns the tick, its sqrt price and whether it is initialized. When no word in
+    /// `tick_bitmaps` has an initialized tick below the price, `MIN_TICK` is returned.
+    fn find_next_tick_down(
+        pool: &Pool,
+        tick_bitmaps: &BTreeMap<i16, TickBitmap>,
+    ) -> Result<(i32, U256, bool), ProgramError> {
+        let (next_tick, initialized) = match TickBitmap::next_initialized_tick_in_words(
+            tick_bitmaps,
+            pool.tick,
+            pool.tick_spacing,
+            true,
+        ) {
+            Some(tick) => (tick.max(MIN_TICK), true),
+            None => (MIN_TICK, false),
+        };
+
+        let next_sqrt_price = TickMath::get_sqrt_ratio_at_tick(next_tick)?;
 
-        Ok((next_tick, next_sqrt_price))
+        Ok((next_tick, next_sqrt_price, initialized))
     }
 
-    /// Find next tick moving up (for one_for_zero swaps)
-    fn find_next_tick_up(pool: &mut Pool, current_tick: i32) -> Result<(i32, U256), ProgramError> {
-        // Simplified - in a real implementation, this would search the tick bitmap
-        let next_tick = current_tick + pool.tick_spacing as i32;
-        let next_sqrt_price = crate::math::TickMath::get_sqrt_ratio_at_tick(next_tick)?;
+    /// Find next initialized tick above the current tick (for one_for_zero swaps)
+    fn find_next_tick_up(
+        pool: &Pool,
+        tick_bitmaps: &BTreeMap<i16, TickBitmap>,
+    ) -> Result<(i32, U256, bool), ProgramError> {
+        let (next_tick, initialized) = match TickBitmap::next_initialized_tick_in_words(
+            tick_bitmaps,
+            pool.tick,
+            pool.tick_spacing,
+            false,
+        ) {
+            Some(tick) => (tick.min(MAX_TICK), true),
+            None => (MAX_TICK, false),
+        };
+
+        let next_sqrt_price = TickMath::get_sqrt_ratio_at_tick(next_tick)?;
 
-        Ok((next_tick, next_sqrt_price))
+        Ok((next_tick, next_sqrt_price, initialized))
+    }
+
+    /// Fill the range orders that complete at a tick the swap just crossed.
+    ///
+    /// Orders are filled by the crossing that leaves their range on the far side, so the
+    /// whole position has been converted. Their liquidity is taken out of both boundary
+    /// ticks' net liquidity, which keeps it from being re-activated if the price returns.
+    /// The other boundary tick of the range must have been supplied with the swap.
+    fn fill_range_orders(
+        pool: &Pool,
+        ticks: &mut HashMap<i32, Tick>,
+        crossed_tick: i32,
+        zero_for_one: bool,
+    ) -> Result<(), ProgramError> {
+        let (upward, liquidity) = match ticks.get(&crossed_tick) {
+            Some(tick) => (tick.range_order_upward, tick.range_order_liquidity),
+            None => return Ok(()),
+        };
+
+        // Only a crossing in the orders' direction completes them
+        if liquidity.is_zero() || upward == zero_for_one {
+            return Ok(());
+        }
+
+        let spacing = pool.tick_spacing as i32;
+        let (lower_index, upper_index) = if upward {
+            (crossed_tick - spacing, crossed_tick)
+        } else {
+            (crossed_tick, crossed_tick + spacing)
+        };
+
+        let mut lower = ticks.get(&lower_index).cloned().ok_or(CLMMError::InvalidAccount)?;
+        let mut upper = ticks.get(&upper_index).cloned().ok_or(CLMMError::InvalidAccount)?;
+
+        let liquidity_net = I256::try_from(liquidity)?;
+        upper.liquidity_net = upper.liquidity_net.checked_sub(liquidity_net).ok_or(CLMMError::MathOverflow)?;
+        lower.liquidity_net = lower.liquidity_net.checked_add(liquidity_net).ok_or(CLMMError::MathOverflow)?;
+
+        // Fees stop accruing to the orders once they are filled
+        let (fee_growth_inside0, fee_growth_inside1) = Tick::fee_growth_inside(
+            &lower,
+            &upper,
+            pool.tick,
+            pool.fee_growth_global0_x128,
+            pool.fee_growth_global1_x128,
+        );
+        if upward {
+            upper.fill_range_orders(fee_growth_inside0, fee_growth_inside1);
+        } else {
+            lower.fill_range_orders(fee_growth_inside0, fee_growth_inside1);
+        }
+
+        ticks.insert(lower_index, lower);
+        ticks.insert(upper_index, upper);
+        Ok(())
+    }
+
+    /// Apply a crossed tick's net liquidity to the active liquidity
+    pub(crate) fn apply_liquidity_net(
+        liquidity: U256,
+        liquidity_net: I256,
+        zero_for_one: bool,
+    ) -> Result<U256, ProgramError> {
+        // Lower ticks carry -L: moving down adds the net, moving up subtracts it
+        let delta = if zero_for_one {
+            liquidity_net
+        } else {
+            liquidity_net.checked_neg().ok_or(CLMMError::MathOverflow)?
+        };
+
+        let result = if delta.is_negative() {
+            liquidity.checked_sub(delta.unsigned_abs())
+        } else {
+            liquidity.checked_add(delta.unsigned_abs())
+        };
+        result.ok_or(CLMMError::MathOverflow.into())
     }
 
     /// Calculate maximum amount that can be swapped in this step
@@ -351,19 +626,19 @@ impl SwapEngine {
         zero_for_one: bool,
     ) -> Result<U256, ProgramError> {
         if zero_for_one {
-            Ok(FixedPointMath::get_amount0_delta(
+            FixedPointMath::get_amount0_delta(
                 current_sqrt_price,
                 next_sqrt_price,
                 liquidity,
-                false,
-            ))
+                true,
+            )
         } else {
-            Ok(FixedPointMath::get_amount1_delta(
+            FixedPointMath::get_amount1_delta(
                 current_sqrt_price,
                 next_sqrt_price,
                 liquidity,
-                false,
-            ))
+                true,
+            )
         }
     }
 
@@ -374,21 +649,7 @@ impl SwapEngine {
         amount_in: U256,
         zero_for_one: bool,
     ) -> Result<U256, ProgramError> {
-        if zero_for_one {
-            crate::math::TickMath::get_next_sqrt_price_from_amount0_rounding_up(
-                current_sqrt_price,
-                liquidity,
-                amount_in,
-                true,
-            )
-        } else {
-            crate::math::TickMath::get_next_sqrt_price_from_amount1_rounding_down(
-                current_sqrt_price,
-                liquidity,
-                amount_in,
-                false,
-            )
-        }
+        TickMath::get_next_sqrt_price_from_input(current_sqrt_price, liquidity, amount_in, zero_for_one)
     }
 
     /// Get tick at sqrt price
@@ -396,28 +657,39 @@ impl SwapEngine {
         crate::math::TickMath::get_tick_at_sqrt_ratio(sqrt_price)
     }
 
-    /// Update pool state after swap
-    fn update_pool_after_swap(
+    /// Credit a fee paid in token0 (`zero_for_one`) or token1 to the protocol and liquidity providers
+    pub fn accrue_fee(
         pool: &mut Pool,
-        amount_in: U256,
-        amount_out: U256,
+        fee_amount: U256,
         zero_for_one: bool,
     ) -> Result<(), ProgramError> {
-        // Update global fee growth using current pool fee
-        let fee_amount = amount_in * U256::from(pool.fee) / U256::from(10000);
-        let amount_after_fee = amount_in - fee_amount;
+        if fee_amount == U256_ZERO {
+            return Ok(());
+        }
 
+        // The protocol takes its cut first, liquidity providers share the rest
+        let (lp_fee, protocol_fee) = pool.split_protocol_fee(fee_amount);
         if zero_for_one {
-            // Fee on token0
-            let fee_growth = fee_amount * Q96 / pool.liquidity;
-            pool.fee_growth_global0_x128 = pool.fee_growth_global0_x128 + fee_growth;
+            pool.protocol_fees_token0 = pool.protocol_fees_token0.checked_add(protocol_fee)
+                .ok_or(CLMMError::MathOverflow)?;
         } else {
-            // Fee on token1
-            let fee_growth = fee_amount * Q96 / pool.liquidity;
-            pool.fee_growth_global1_x128 = pool.fee_growth_global1_x128 + fee_growth;
+            pool.protocol_fees_token1 = pool.protocol_fees_token1.checked_add(protocol_fee)
+                .ok_or(CLMMError::MathOverflow)?;
+        }
+
+        if pool.liquidity == U256_ZERO || lp_fee == U256_ZERO {
+            return Ok(());
         }
 
-        pool.update_timestamp(chrono::Utc::now().timestamp() as u32);
+        // Fee growth is tracked per unit of liquidity in Q128 format
+        let fee_growth = FixedPointMath::mul_div(lp_fee, U256_ONE << 128, pool.liquidity)?;
+
+        // Fees are taken in the input token and wrap like the Uniswap accumulators
+        if zero_for_one {
+            pool.fee_growth_global0_x128 = pool.fee_growth_global0_x128.overflowing_add(fee_growth).0;
+        } else {
+            pool.fee_growth_global1_x128 = pool.fee_growth_global1_x128.overflowing_add(fee_growth).0;
+        }
 
         Ok(())
     }
@@ -428,6 +700,7 @@ impl SwapEngine {
 pub struct SwapResult {
     pub amount_in: U256,
     pub amount_out: U256,
+    /// How far the swap moved the price, in basis points
     pub price_impact: u32,
     pub final_sqrt_price: U256,
     pub final_tick: i32,
@@ -442,8 +715,5 @@ pub struct SwapResult {
 struct SwapStepResult {
     pub amount_in: U256,
     pub amount_out: U256,
-    pub sqrt_price_next: U256,
-    pub tick_next: i32,
-    pub liquidity_next: U256,
+    pub fee_amount: U256,
 }
-
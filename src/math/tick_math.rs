@@ -1,27 +1,214 @@
 use crate::error::CLMMError;
 use solana_program::program_error::ProgramError;
 use uint::construct_uint;
-use std::ops::Shl;
-use std::convert::TryInto;
 
 construct_uint! {
     pub struct Uint256(4);
 }
 construct_uint! {
-    pub struct Int256(4);
+    pub struct Uint512(8);
 }
 
-pub type U256 = Uint256;
-pub type I256 = Int256;
+pub use self::Uint256 as U256;
+pub use self::Int256 as I256;
 
 pub const U256_ZERO: U256 = Uint256::zero();
 pub const I256_ZERO: I256 = Int256::zero();
 pub const U256_ONE: U256 = Uint256::one();
 
+/// Two's-complement signed 256-bit integer, stored as four little-endian `u64` words
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Int256(pub [u64; 4]);
+
+impl Int256 {
+    /// Largest representable value, `2^255 - 1`
+    pub const MAX: Int256 = Int256([u64::MAX, u64::MAX, u64::MAX, i64::MAX as u64]);
+    /// Smallest representable value, `-2^255`
+    pub const MIN: Int256 = Int256([0, 0, 0, 1 << 63]);
+
+    pub const fn zero() -> Self {
+        Int256([0; 4])
+    }
+
+    pub const fn one() -> Self {
+        Int256([1, 0, 0, 0])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0[3] >> 63 == 1
+    }
+
+    pub fn is_positive(&self) -> bool {
+        !self.is_negative() && !self.is_zero()
+    }
+
+    /// Reinterpret the raw two's-complement bits as an unsigned value
+    fn as_bits(&self) -> U256 {
+        Uint256(self.0)
+    }
+
+    fn from_bits(bits: U256) -> Self {
+        Int256(bits.0)
+    }
+
+    /// Absolute value as an unsigned integer; never overflows, even for `MIN`
+    pub fn unsigned_abs(&self) -> U256 {
+        if self.is_negative() {
+            (!self.as_bits()).overflowing_add(U256_ONE).0
+        } else {
+            self.as_bits()
+        }
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let result = self.wrapping_add(other);
+        // Overflow iff both operands share a sign that the result does not
+        if self.is_negative() == other.is_negative() && result.is_negative() != self.is_negative() {
+            None
+        } else {
+            Some(result)
+        }
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let result = self.wrapping_sub(other);
+        // Overflow iff the operands differ in sign and the result took the subtrahend's sign
+        if self.is_negative() != other.is_negative() && result.is_negative() != self.is_negative() {
+            None
+        } else {
+            Some(result)
+        }
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        if self == Self::MIN {
+            None
+        } else {
+            Some(self.wrapping_neg())
+        }
+    }
+
+    pub fn wrapping_add(self, other: Self) -> Self {
+        Self::from_bits(self.as_bits().overflowing_add(other.as_bits()).0)
+    }
+
+    pub fn wrapping_sub(self, other: Self) -> Self {
+        Self::from_bits(self.as_bits().overflowing_sub(other.as_bits()).0)
+    }
+
+    pub fn wrapping_neg(self) -> Self {
+        Self::from_bits((!self.as_bits()).overflowing_add(U256_ONE).0)
+    }
+}
+
+impl PartialOrd for Int256 {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Int256 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            // Same sign: two's-complement bits order the same way as the values
+            _ => self.as_bits().cmp(&other.as_bits()),
+        }
+    }
+}
+
+impl std::fmt::Display for Int256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_negative() {
+            write!(f, "-{}", self.unsigned_abs())
+        } else {
+            write!(f, "{}", self.as_bits())
+        }
+    }
+}
+
+impl std::fmt::Debug for Int256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+impl From<i128> for Int256 {
+    fn from(value: i128) -> Self {
+        // Sign-extend into the upper words
+        let fill = if value < 0 { u64::MAX } else { 0 };
+        Int256([value as u64, (value >> 64) as u64, fill, fill])
+    }
+}
+
+impl From<i64> for Int256 {
+    fn from(value: i64) -> Self {
+        Int256::from(value as i128)
+    }
+}
+
+impl From<i32> for Int256 {
+    fn from(value: i32) -> Self {
+        Int256::from(value as i128)
+    }
+}
+
+impl From<u128> for Int256 {
+    fn from(value: u128) -> Self {
+        Int256([value as u64, (value >> 64) as u64, 0, 0])
+    }
+}
+
+impl TryFrom<Uint256> for Int256 {
+    type Error = CLMMError;
+
+    fn try_from(value: Uint256) -> Result<Self, Self::Error> {
+        let result = Int256(value.0);
+        if result.is_negative() {
+            Err(CLMMError::MathOverflow)
+        } else {
+            Ok(result)
+        }
+    }
+}
+
+impl TryFrom<Int256> for Uint256 {
+    type Error = CLMMError;
+
+    fn try_from(value: Int256) -> Result<Self, Self::Error> {
+        if value.is_negative() {
+            Err(CLMMError::MathOverflow)
+        } else {
+            Ok(value.as_bits())
+        }
+    }
+}
+
+impl TryFrom<Int256> for i128 {
+    type Error = CLMMError;
+
+    fn try_from(value: Int256) -> Result<Self, Self::Error> {
+        let low = (value.0[1] as u128) << 64 | value.0[0] as u128;
+        let result = low as i128;
+        // Fits iff the upper words are pure sign extension of the low 128 bits
+        let fill = if result < 0 { u64::MAX } else { 0 };
+        if value.0[2] == fill && value.0[3] == fill {
+            Ok(result)
+        } else {
+            Err(CLMMError::MathOverflow)
+        }
+    }
+}
+
 impl borsh::BorshSerialize for I256 {
     fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        for i in 0..4 {
-            self.0[i].serialize(writer)?;
+        for word in self.0 {
+            word.serialize(writer)?;
         }
         Ok(())
     }
@@ -30,16 +217,16 @@ impl borsh::BorshSerialize for I256 {
 impl borsh::BorshDeserialize for I256 {
     fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
         let mut arr = [0u64; 4];
-        for i in 0..4 {
-            arr[i] = u64::deserialize(buf)?;
+        for word in arr.iter_mut() {
+            *word = u64::deserialize(buf)?;
         }
         Ok(Int256(arr))
     }
 
     fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
         let mut arr = [0u64; 4];
-        for i in 0..4 {
-            arr[i] = u64::deserialize_reader(reader)?;
+        for word in arr.iter_mut() {
+            *word = u64::deserialize_reader(reader)?;
         }
         Ok(Int256(arr))
     }
@@ -49,150 +236,106 @@ pub const MIN_TICK: i32 = -887272;
 pub const MAX_TICK: i32 = 887272;
 pub const Q96: U256 = Uint256([0, 4294967296u64, 0, 0]);
 
+/// Sqrt price at `MIN_TICK` (X96 format)
+pub const MIN_SQRT_RATIO: U256 = Uint256([4295128739u64, 0, 0, 0]);
+/// Sqrt price at `MAX_TICK` (X96 format)
+pub const MAX_SQRT_RATIO: U256 = Uint256([0x5d951d5263988d26, 0xefd1fc6a50648849, 0xfffd8963, 0]);
+
 pub struct TickMath;
 
 impl TickMath {
     /// Calculate the sqrt price ratio at a given tick
     pub fn get_sqrt_ratio_at_tick(tick: i32) -> Result<U256, ProgramError> {
-        if tick < MIN_TICK || tick > MAX_TICK {
+        if !(MIN_TICK..=MAX_TICK).contains(&tick) {
             return Err(CLMMError::InvalidTickRange.into());
         }
 
-        let abs_tick = if tick < 0 {
-            Uint256::from(tick.abs() as u64)
-        } else {
-            Uint256::from(tick as u64)
-        };
+        let abs_tick = tick.unsigned_abs();
 
-        let mut ratio = if tick % 2 == 0 {
-            Uint256::from(0xfffcb933bd6fad37aa2d162d1a594001_u128)
+        // ratio is a Q128.128 value of 1 / sqrt(1.0001)^abs_tick
+        let mut ratio = if abs_tick & 0x1 != 0 {
+            U256::from(0xfffcb933bd6fad37aa2d162d1a594001_u128)
         } else {
-            U256::from(0xfff97272373d413259a46990580e213a_u128)
+            U256_ONE << 128
         };
 
-        if tick & 0x02 != 0 {
-            ratio = (ratio * U256::from(0xfff2e50f5f656932ef12357cf3c7fdcc_u128)) >> 128;
-        }
-        if tick & 0x04 != 0 {
-            ratio = (ratio * U256::from(0xffe5caca7e10e4e61c3624eaa0941cd0_u128)) >> 128;
-        }
-        if tick & 0x08 != 0 {
-            ratio = (ratio * U256::from(0xffcb9843d60f6159c9db58835c926644_u128)) >> 128;
-        }
-        if tick & 0x10 != 0 {
-            ratio = (ratio * U256::from(0xff973b41fa98c081472e6896dfb254c0_u128)) >> 128;
-        }
-        if tick & 0x20 != 0 {
-            ratio = (ratio * U256::from(0xff2ea16466c96a3843ec78b326b52861_u128)) >> 128;
-        }
-        if tick & 0x40 != 0 {
-            ratio = (ratio * U256::from(0xfe5dee046a99a2a811c461f1969c3053_u128)) >> 128;
-        }
-        if tick & 0x80 != 0 {
-            ratio = (ratio * U256::from(0xfcbe86c7900a88aedcffc83b479aa3a4_u128)) >> 128;
-        }
-        if tick & 0x100 != 0 {
-            ratio = (ratio * U256::from(0xf987a7253acae65be8623aa479a2ddf0_u128)) >> 128;
-        }
-        if tick & 0x200 != 0 {
-            ratio = (ratio * U256::from(0xf3392b0822b70005940c7a398e4b70f3_u128)) >> 128;
-        }
-        if tick & 0x400 != 0 {
-            ratio = (ratio * U256::from(0xe7159475a2c29be046d0ccceb0512d9_u128)) >> 128;
-        }
-        if tick & 0x800 != 0 {
-            ratio = (ratio * U256::from(0xd097f3bdfd2022b8845ad8f792aa5825_u128)) >> 128;
-        }
-        if tick & 0x1000 != 0 {
-            ratio = (ratio * U256::from(0xa9f746462d870fdf8a65dc1f90e061e5_u128)) >> 128;
-        }
-        if tick & 0x2000 != 0 {
-            ratio = (ratio * U256::from(0x70d869a156d2a1b890bb3df62baf32f7_u128)) >> 128;
-        }
-        if tick & 0x4000 != 0 {
-            ratio = (ratio * U256::from(0x31be135f97d08fd981231505542fcfa6_u128)) >> 128;
-        }
-        if tick & 0x8000 != 0 {
-            ratio = (ratio * U256::from(0x9aa508b5b7a84e1c677de54f3e99bc9_u128)) >> 128;
-        }
-        if tick & 0x10000 != 0 {
-            ratio = (ratio * U256::from(0x5d6af8dedb81196699c329225ee604_u128)) >> 128;
-        }
-        if tick & 0x20000 != 0 {
-            ratio = (ratio * U256::from(0x2216e584f5fa1ea926041bedfe98_u128)) >> 128;
-        }
-        if tick & 0x40000 != 0 {
-            ratio = (ratio * U256::from(0x48a170391f7dc42444e8fa2_u128)) >> 128;
+        const FACTORS: [(u32, u128); 19] = [
+            (0x2, 0xfff97272373d413259a46990580e213a),
+            (0x4, 0xfff2e50f5f656932ef12357cf3c7fdcc),
+            (0x8, 0xffe5caca7e10e4e61c3624eaa0941cd0),
+            (0x10, 0xffcb9843d60f6159c9db58835c926644),
+            (0x20, 0xff973b41fa98c081472e6896dfb254c0),
+            (0x40, 0xff2ea16466c96a3843ec78b326b52861),
+            (0x80, 0xfe5dee046a99a2a811c461f1969c3053),
+            (0x100, 0xfcbe86c7900a88aedcffc83b479aa3a4),
+            (0x200, 0xf987a7253ac413176f2b074cf7815e54),
+            (0x400, 0xf3392b0822b70005940c7a398e4b70f3),
+            (0x800, 0xe7159475a2c29b7443b29c7fa6e889d9),
+            (0x1000, 0xd097f3bdfd2022b8845ad8f792aa5825),
+            (0x2000, 0xa9f746462d870fdf8a65dc1f90e061e5),
+            (0x4000, 0x70d869a156d2a1b890bb3df62baf32f7),
+            (0x8000, 0x31be135f97d08fd981231505542fcfa6),
+            (0x10000, 0x9aa508b5b7a84e1c677de54f3e99bc9),
+            (0x20000, 0x5d6af8dedb81196699c329225ee604),
+            (0x40000, 0x2216e584f5fa1ea926041bedfe98),
+            (0x80000, 0x48a170391f7dc42444e8fa2),
+        ];
+
+        for (bit, factor) in FACTORS {
+            if abs_tick & bit != 0 {
+                ratio = (ratio * U256::from(factor)) >> 128;
+            }
         }
 
         if tick > 0 {
             ratio = U256::MAX / ratio;
         }
 
-        Ok(ratio)
+        // Downcast from Q128.128 to Q64.96, rounding up so that
+        // get_tick_at_sqrt_ratio(get_sqrt_ratio_at_tick(tick)) == tick
+        let remainder = ratio & ((U256_ONE << 32) - U256_ONE);
+        let mut sqrt_price_x96 = ratio >> 32;
+        if remainder != U256_ZERO {
+            sqrt_price_x96 += U256_ONE;
+        }
+
+        Ok(sqrt_price_x96)
     }
 
-    /// Get the tick at a given sqrt price ratio
+    /// Get the greatest tick whose sqrt price is less than or equal to the given sqrt price
     pub fn get_tick_at_sqrt_ratio(sqrt_price_x96: U256) -> Result<i32, ProgramError> {
-        let min_price = Uint256([4295128739u64, 0, 0, 0]);
-        let max_price = Uint256([0xFFFFFFFFFFFFFFFFu64, 0xFFFFFFFFFFFFFFFFu64, 0xFFFFFFFFFFFFFFFFu64, 0xFFFFFFFFFFFFFFFFu64]);
-        if sqrt_price_x96 < min_price || sqrt_price_x96 >= max_price {
+        if sqrt_price_x96 < MIN_SQRT_RATIO || sqrt_price_x96 >= MAX_SQRT_RATIO {
             return Err(CLMMError::InvalidPrice.into());
         }
 
-        let ratio = sqrt_price_x96;
-        let mut r = ratio;
-        let mut msb = 0u8;
-
-        // Binary search for most significant bit
-        let mut f = if r > U256::from(0xFFFFFFFFFFFFFFFFFFFFFFFFu128) { 1u8 } else { 0u8 } << 7;
-        msb |= f;
-        r >>= f;
-
-        f = if r > U256::from(0xFFFFFFFFFFFFFFFFu64) { 1u8 } else { 0u8 } << 6;
-        msb |= f;
-        r >>= f;
-
-        f = if r > U256::from(0xFFFFFFFFu32) { 1u8 } else { 0u8 } << 5;
-        msb |= f;
-        r >>= f;
-
-        f = if r > U256::from(0xFFFFu16) { 1u8 } else { 0u8 } << 4;
-        msb |= f;
-        r >>= f;
-
-        f = if r > U256::from(0xFFu8) { 1u8 } else { 0u8 } << 3;
-        msb |= f;
-        r >>= f;
-
-        f = if r > U256::from(0xF) { 1u8 } else { 0u8 } << 2;
-        msb |= f;
-        r >>= f;
-
-        f = if r > U256::from(0x3) { 1u8 } else { 0u8 } << 1;
-        msb |= f;
-        r >>= f;
-
-        f = if r > U256::from(0x1) { 1u8 } else { 0u8 };
-        msb |= f;
-
-        let log_2 = (U256::from(msb) - U256::from(64)) << 64;
-
-        let mut r2 = (sqrt_price_x96 * sqrt_price_x96) >> 128;
-        r2 = (r2 * sqrt_price_x96) >> 128;
-
-        let tick_low = (log_2 - Uint256([0, 0, 1u64, 0])) >> 128;
-        let tick_high = (log_2 + Uint256([0, 0, 1u64, 0])) >> 128;
+        // log2 of the price as a Q128.128 ratio, with the integer part from the msb
+        let ratio = sqrt_price_x96 << 32;
+        let msb = ratio.bits() as i64 - 1;
+        let mut r = if msb >= 127 { ratio >> (msb - 127) as usize } else { ratio << (127 - msb) as usize };
+        let mut log_2 = to_twos_complement(msb - 128) << 64;
+
+        // 14 fractional bits by repeated squaring of the normalized mantissa
+        for shift in (50..64).rev() {
+            r = (r * r) >> 127;
+            let f = r >> 128;
+            log_2 |= f << shift;
+            r >>= f.low_u32() as usize;
+        }
 
-        let tick = if tick_low == tick_high {
-            tick_low.low_u32() as i32
-        } else if Self::get_sqrt_ratio_at_tick(tick_low.low_u32() as i32)? <= sqrt_price_x96 {
-            tick_low.low_u32() as i32
+        // log_sqrt(1.0001)(price), Q128.128, then the error bounds of the 14-bit estimate
+        let log_sqrt10001 = log_2.overflowing_mul(U256::from(255738958999603826347141u128)).0;
+        let tick_low = arithmetic_shr_128(
+            log_sqrt10001.overflowing_sub(U256::from(3402992956809132418596140100660247210u128)).0,
+        );
+        let tick_high = arithmetic_shr_128(
+            log_sqrt10001.overflowing_add(U256::from(291339464771989622907027621153398088495u128)).0,
+        );
+
+        if tick_low == tick_high || Self::get_sqrt_ratio_at_tick(tick_high)? > sqrt_price_x96 {
+            Ok(tick_low)
         } else {
-            tick_high.low_u32() as i32
-        };
-
-        Ok(tick)
+            Ok(tick_high)
+        }
     }
 
     /// Calculate the next sqrt price from amount0 rounding up
@@ -205,21 +348,21 @@ impl TickMath {
         if liquidity == U256_ZERO {
             return Err(CLMMError::InsufficientLiquidity.into());
         }
+        if amount == U256_ZERO {
+            return Ok(sqrt_px96);
+        }
 
         let numerator1 = liquidity << 96;
+        let product = amount.checked_mul(sqrt_px96).ok_or(CLMMError::MathOverflow)?;
+
         if add {
-            let liquidity_after = liquidity.checked_add(
-                Self::mul_div_rounding_up(amount, Q96, sqrt_px96)?
-            ).ok_or(CLMMError::MathOverflow)?;
-            if liquidity_after == liquidity {
-                return Err(CLMMError::MathOverflow.into());
-            }
-            Ok(Self::mul_div_rounding_up(numerator1, sqrt_px96, liquidity_after)?)
+            let denominator = numerator1.checked_add(product).ok_or(CLMMError::MathOverflow)?;
+            Self::mul_div_rounding_up(numerator1, sqrt_px96, denominator)
         } else {
-            let liquidity_after = liquidity.checked_sub(
-                Self::mul_div_rounding_up(amount, Q96, sqrt_px96)?
-            ).ok_or(CLMMError::InsufficientLiquidity)?;
-            Ok(numerator1 * sqrt_px96 / liquidity_after)
+            if numerator1 <= product {
+                return Err(CLMMError::InsufficientLiquidity.into());
+            }
+            Self::mul_div_rounding_up(numerator1, sqrt_px96, numerator1 - product)
         }
     }
 
@@ -230,52 +373,93 @@ impl TickMath {
         amount: U256,
         add: bool,
     ) -> Result<U256, ProgramError> {
+        if liquidity == U256_ZERO {
+            return Err(CLMMError::InsufficientLiquidity.into());
+        }
+
         if add {
-            let liquidity_after = liquidity.checked_add((amount << 96) / sqrt_px96)
-                .ok_or(CLMMError::MathOverflow)?;
-            Ok(liquidity_after * sqrt_px96 / Q96)
+            let quotient = Self::mul_div(amount, Q96, liquidity)?;
+            sqrt_px96.checked_add(quotient).ok_or(CLMMError::MathOverflow.into())
+        } else {
+            let quotient = Self::mul_div_rounding_up(amount, Q96, liquidity)?;
+            if sqrt_px96 <= quotient {
+                return Err(CLMMError::InsufficientLiquidity.into());
+            }
+            Ok(sqrt_px96 - quotient)
+        }
+    }
+
+    /// Calculate the next sqrt price after swapping `amount_in` of the input token,
+    /// rounding so the price never moves further than the input pays for
+    pub fn get_next_sqrt_price_from_input(
+        sqrt_px96: U256,
+        liquidity: U256,
+        amount_in: U256,
+        zero_for_one: bool,
+    ) -> Result<U256, ProgramError> {
+        if zero_for_one {
+            Self::get_next_sqrt_price_from_amount0_rounding_up(sqrt_px96, liquidity, amount_in, true)
         } else {
-            let liquidity_after = liquidity.checked_sub((amount << 96) / sqrt_px96)
-                .ok_or(CLMMError::InsufficientLiquidity)?;
-            Ok(liquidity_after * sqrt_px96 / Q96)
+            Self::get_next_sqrt_price_from_amount1_rounding_down(sqrt_px96, liquidity, amount_in, true)
+        }
+    }
+
+    /// Calculate the next sqrt price after taking `amount_out` of the output token out of
+    /// the pool, rounding so the price always moves at least as far as the output requires
+    pub fn get_next_sqrt_price_from_output(
+        sqrt_px96: U256,
+        liquidity: U256,
+        amount_out: U256,
+        zero_for_one: bool,
+    ) -> Result<U256, ProgramError> {
+        if zero_for_one {
+            Self::get_next_sqrt_price_from_amount1_rounding_down(sqrt_px96, liquidity, amount_out, false)
+        } else {
+            Self::get_next_sqrt_price_from_amount0_rounding_up(sqrt_px96, liquidity, amount_out, false)
         }
     }
 
     /// Multiply and divide with rounding up
     pub fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> Result<U256, ProgramError> {
         let result = Self::mul_div(a, b, denominator)?;
-        if a * b % denominator != U256_ZERO {
-            Ok(result + U256_ONE)
+        let remainder = (Self::widen(a) * Self::widen(b)) % Self::widen(denominator);
+        if remainder != Uint512::zero() {
+            result.checked_add(U256_ONE).ok_or(CLMMError::MathOverflow.into())
         } else {
             Ok(result)
         }
     }
 
-    /// Multiply and divide
+    /// Multiply and divide with a full 512-bit intermediate product
     pub fn mul_div(a: U256, b: U256, denominator: U256) -> Result<U256, ProgramError> {
         if denominator == U256_ZERO {
             return Err(CLMMError::MathOverflow.into());
         }
 
-        let a_low = a.low_u128() as u64;
-        let b_low = b.low_u128() as u64;
-        let denominator_low = denominator.low_u128() as u64;
-
-        let a_high = (a >> 128).low_u128() as u64;
-        let b_high = (b >> 128).low_u128() as u64;
-        let denominator_high = (denominator >> 128).low_u128() as u64;
-
-        let bd = b_low * denominator_low;
-        let bn = b_high * denominator_low;
-        let ad = a_low * denominator_high;
-        let an = a_high * denominator_high;
+        let quotient = (Self::widen(a) * Self::widen(b)) / Self::widen(denominator);
+        if quotient.0[4..].iter().any(|&word| word != 0) {
+            return Err(CLMMError::MathOverflow.into());
+        }
 
-        let mut result = (a_low * b_low) as u128;
-        let carry = if result > u64::MAX as u128 { 1u128 } else { 0u128 };
+        Ok(Uint256([quotient.0[0], quotient.0[1], quotient.0[2], quotient.0[3]]))
+    }
 
-        result += carry << 64;
+    fn widen(value: U256) -> Uint512 {
+        Uint512([value.0[0], value.0[1], value.0[2], value.0[3], 0, 0, 0, 0])
+    }
+}
 
-        Ok(U256::from(result))
+/// Two's-complement encoding of `value` in 256 bits
+fn to_twos_complement(value: i64) -> U256 {
+    if value < 0 {
+        !U256::from(value.unsigned_abs()) + U256_ONE
+    } else {
+        U256::from(value as u64)
     }
 }
 
+/// Arithmetic right shift by 128 of a two's-complement value known to fit in an `i32`
+fn arithmetic_shr_128(value: U256) -> i32 {
+    let shifted = if value.bit(255) { !((!value) >> 128) } else { value >> 128 };
+    shifted.low_u64() as i64 as i32
+}
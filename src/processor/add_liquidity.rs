@@ -2,46 +2,141 @@ use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
+    program::set_return_data,
     program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
     sysvar::Sysvar,
 };
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use crate::error::CLMMError;
-use crate::state::{Pool, Position, Tick};
-use crate::math::tick_math::{U256, I256, U256_ZERO, I256_ZERO};
+use crate::state::{Pool, Position, RangeOrder, Tick, TickBitmap, TICK_BITMAP_ACCOUNT_SIZE};
+use crate::math::tick_math::{U256, I256, U256_ZERO};
 use crate::utils::{
     assert_signer, assert_writable, assert_owned_by, assert_initialized,
-    write_account_data, get_current_timestamp, token_transfer,
-    create_account, derive_position_address, derive_tick_address,
-    derive_pool_authority_address,
+    write_account_data, get_current_timestamp, token_transfer_received, get_transfer_fee,
+    create_account, assert_position_holder, derive_tick_address,
+    derive_tick_bitmap_address, derive_pool_authority_address, wrap_native, unwrap_native,
+    NativeSolAccounts,
 };
 
-/// Add liquidity to a position
+/// Add liquidity to an open position
 ///
 /// Accounts expected:
-/// 0. `[signer]` Position owner
+/// 0. `[signer]` Position token holder
 /// 1. `[writable]` Pool account
 /// 2. `[writable]` Position account (PDA)
-/// 3. `[writable]` Tick lower account (PDA)
-/// 4. `[writable]` Tick upper account (PDA)
-/// 5. `[writable]` User token A account
-/// 6. `[writable]` User token B account
-/// 7. `[writable]` Pool vault A
-/// 8. `[writable]` Pool vault B
-/// 9. `[]` Pool authority (PDA)
-/// 10. `[]` Token program
-/// 11. `[]` System program
-/// 12. `[]` Rent sysvar
+/// 3. `[]` Holder's position token account
+/// 4. `[writable]` Tick lower account (PDA)
+/// 5. `[writable]` Tick upper account (PDA)
+/// 6. `[writable]` User token A account
+/// 7. `[writable]` User token B account
+/// 8. `[writable]` Pool vault A
+/// 9. `[writable]` Pool vault B
+/// 10. `[]` Pool authority (PDA)
+/// 11. `[]` Token program for token A
+/// 12. `[]` System program
+/// 13. `[]` Rent sysvar
+/// 14. `[writable]` Tick bitmap account for the lower tick's word (PDA)
+/// 15. `[writable]` Tick bitmap account for the upper tick's word (PDA, may equal 14)
+/// 16. `[]` Token program for token B
+/// 17. `[]` Token A mint
+/// 18. `[]` Token B mint
+///
+/// Maximum amounts bound what the user sends, transfer fees included. The position is
+/// credited with what the vaults actually receive.
+///
+/// With `native_sol`, the user token account for the pool's SOL side is the holder's
+/// wrapped SOL PDA, funded from the holder's lamports for exactly the deposit and
+/// closed again afterwards.
 pub fn process(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    tick_lower: i32,
-    tick_upper: i32,
     liquidity_delta: u128,
     amount_0_max: u64,
     amount_1_max: u64,
+    native_sol: bool,
+) -> ProgramResult {
+    add_liquidity(program_id, accounts, LiquidityAmount::Liquidity {
+        liquidity_delta,
+        amount_0_max,
+        amount_1_max,
+    }, false, native_sol)
+}
+
+/// Add as much liquidity as the desired token amounts allow at the current price
+///
+/// Accounts expected: same as `process`
+pub fn process_by_amounts(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount_0_desired: u64,
+    amount_1_desired: u64,
+    amount_0_min: u64,
+    amount_1_min: u64,
+    native_sol: bool,
+) -> ProgramResult {
+    add_liquidity(program_id, accounts, LiquidityAmount::Amounts {
+        amount_0_desired,
+        amount_1_desired,
+        amount_0_min,
+        amount_1_min,
+    }, false, native_sol)
+}
+
+/// Turn an empty single-spacing position on one side of the price into a range order
+///
+/// The order sells token0 when placed above the current tick and token1 when placed
+/// below it, and is filled once a swap crosses its far tick.
+///
+/// Accounts expected: same as `process`
+pub fn process_range_order(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    liquidity_delta: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+) -> ProgramResult {
+    add_liquidity(program_id, accounts, LiquidityAmount::Liquidity {
+        liquidity_delta,
+        amount_0_max,
+        amount_1_max,
+    }, true, false)
+}
+
+/// How much liquidity to add and the bounds on the tokens it may take
+enum LiquidityAmount {
+    /// Exact liquidity, paying at most the given amounts
+    Liquidity {
+        liquidity_delta: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+    },
+    /// Liquidity derived from the desired amounts, depositing at least the minimums
+    Amounts {
+        amount_0_desired: u64,
+        amount_1_desired: u64,
+        amount_0_min: u64,
+        amount_1_min: u64,
+    },
+}
+
+/// Return data set by `AddLiquidity` and `AddLiquidityByAmounts`
+///
+/// Amounts are those received by the pool vaults, net of any transfer fee.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct AddLiquidityResult {
+    pub liquidity_delta: u128,
+    pub amount_0: u64,
+    pub amount_1: u64,
+}
+
+fn add_liquidity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: LiquidityAmount,
+    range_order: bool,
+    native_sol: bool,
 ) -> ProgramResult {
     msg!("Adding liquidity to position...");
 
@@ -51,6 +146,7 @@ pub fn process(
     let owner = next_account_info(account_info_iter)?;
     let pool_account = next_account_info(account_info_iter)?;
     let position_account = next_account_info(account_info_iter)?;
+    let position_token_account = next_account_info(account_info_iter)?;
     let tick_lower_account = next_account_info(account_info_iter)?;
     let tick_upper_account = next_account_info(account_info_iter)?;
     let user_token_a = next_account_info(account_info_iter)?;
@@ -58,11 +154,16 @@ pub fn process(
     let vault_a = next_account_info(account_info_iter)?;
     let vault_b = next_account_info(account_info_iter)?;
     let pool_authority = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
+    let token_program_a = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
-    let _rent_sysvar = next_account_info(account_info_iter)?;
-
-    // Validate owner is signer
+    let rent_sysvar = next_account_info(account_info_iter)?;
+    let bitmap_lower_account = next_account_info(account_info_iter)?;
+    let bitmap_upper_account = next_account_info(account_info_iter)?;
+    let token_program_b = next_account_info(account_info_iter)?;
+    let mint_a = next_account_info(account_info_iter)?;
+    let mint_b = next_account_info(account_info_iter)?;
+
+    // Validate holder is signer
     assert_signer(owner)?;
 
     // Validate writable accounts
@@ -70,33 +171,111 @@ pub fn process(
     assert_writable(position_account)?;
     assert_writable(tick_lower_account)?;
     assert_writable(tick_upper_account)?;
+    assert_writable(bitmap_lower_account)?;
+    assert_writable(bitmap_upper_account)?;
     assert_writable(user_token_a)?;
     assert_writable(user_token_b)?;
     assert_writable(vault_a)?;
     assert_writable(vault_b)?;
 
-    // Validate pool is owned by this program
+    // Validate pool and position are owned by this program
     assert_owned_by(pool_account, program_id)?;
+    assert_owned_by(position_account, program_id)?;
     assert_initialized(pool_account)?;
+    assert_initialized(position_account)?;
 
     // Deserialize pool
     let pool_data = pool_account.try_borrow_data()?;
     let mut pool = Pool::deserialize(&mut &pool_data[..])?;
     drop(pool_data);
 
+    // Deserialize position
+    let position_data = position_account.try_borrow_data()?;
+    let mut position = Position::deserialize(&mut &position_data[..])?;
+    drop(position_data);
+
+    if &position.pool_id != pool_account.key {
+        msg!("Position belongs to a different pool");
+        return Err(CLMMError::InvalidAccount.into());
+    }
+
+    // Whoever holds the position token controls the position
+    assert_position_holder(position_token_account, &position.position_mint, owner.key)
+        .map_err(|_| {
+            msg!("Signer does not hold the position token");
+            CLMMError::Unauthorized
+        })?;
+
+    pool.validate_token_accounts(token_program_a.key, token_program_b.key, mint_a.key, mint_b.key)
+        .map_err(|e| {
+            msg!("{}", e);
+            CLMMError::InvalidAccount
+        })?;
+
+    let tick_lower = position.tick_lower;
+    let tick_upper = position.tick_upper;
+
+    // Range orders span one tick spacing entirely on one side of the price
+    let range_order_side = if range_order {
+        if position.is_range_order() || !position.is_empty() {
+            msg!("Range orders must be placed on an empty position");
+            return Err(CLMMError::InvalidAccount.into());
+        }
+
+        if tick_upper - tick_lower != pool.tick_spacing as i32 {
+            msg!("Range orders must span exactly one tick spacing");
+            return Err(CLMMError::InvalidTickRange.into());
+        }
+
+        if pool.tick < tick_lower {
+            RangeOrder::SellToken0
+        } else if pool.tick >= tick_upper {
+            RangeOrder::SellToken1
+        } else {
+            msg!("Range orders must lie entirely above or below the current tick");
+            return Err(CLMMError::InvalidTickRange.into());
+        }
+    } else {
+        if position.is_range_order() {
+            msg!("Liquidity cannot be added to a range order");
+            return Err(CLMMError::InvalidAccount.into());
+        }
+        RangeOrder::None
+    };
+
+    // New liquidity cannot enter a paused pool
+    if !pool.unlocked {
+        msg!("Pool is paused");
+        return Err(CLMMError::PoolLocked.into());
+    }
+    if pool.flash_in_progress {
+        msg!("Flash loan in progress");
+        return Err(CLMMError::PoolLocked.into());
+    }
+
+    let transfer_fee_0 = get_transfer_fee(mint_a)?;
+    let transfer_fee_1 = get_transfer_fee(mint_b)?;
+
+    // Resolve the liquidity to add, funded only by what reaches the vaults
+    let liquidity_delta = match amount {
+        LiquidityAmount::Liquidity { liquidity_delta, .. } => liquidity_delta,
+        LiquidityAmount::Amounts { amount_0_desired, amount_1_desired, .. } => {
+            calculate_liquidity_for_amounts(
+                &pool,
+                tick_lower,
+                tick_upper,
+                amount_0_desired - transfer_fee_0.calculate_fee(amount_0_desired),
+                amount_1_desired - transfer_fee_1.calculate_fee(amount_1_desired),
+            )?
+        }
+    };
+
     // Validate liquidity delta
     if liquidity_delta == 0 {
         msg!("Liquidity delta cannot be zero");
         return Err(CLMMError::InsufficientLiquidity.into());
     }
 
-    // Validate tick range
-    pool.validate_tick_range(tick_lower, tick_upper)
-        .map_err(|e| {
-            msg!("Invalid tick range: {}", e);
-            CLMMError::InvalidTickRange
-        })?;
-
     // Get current timestamp
     let current_time = get_current_timestamp()? as u32;
 
@@ -120,141 +299,180 @@ pub fn process(
         liquidity_u256,
     )?;
 
-    // Validate amounts don't exceed maximums
-    let amount_0_u64 = amount_0.low_u64();
-    let amount_1_u64 = amount_1.low_u64();
-
-    if amount_0_u64 > amount_0_max {
-        msg!("Amount 0 ({}) exceeds maximum ({})", amount_0_u64, amount_0_max);
-        return Err(CLMMError::InsufficientLiquidity.into());
+    let amount_0_u64 = u64::try_from(amount_0).map_err(|_| CLMMError::MathOverflow)?;
+    let amount_1_u64 = u64::try_from(amount_1).map_err(|_| CLMMError::MathOverflow)?;
+
+    // Gross up for transfer fees so the vaults receive the full amounts
+    let send_0 = transfer_fee_0.calculate_pre_fee_amount(amount_0_u64).ok_or(CLMMError::MathOverflow)?;
+    let send_1 = transfer_fee_1.calculate_pre_fee_amount(amount_1_u64).ok_or(CLMMError::MathOverflow)?;
+
+    match amount {
+        LiquidityAmount::Liquidity { amount_0_max, amount_1_max, .. } => {
+            // Validate amounts don't exceed maximums
+            if send_0 > amount_0_max {
+                msg!("Amount 0 ({}) exceeds maximum ({})", send_0, amount_0_max);
+                return Err(CLMMError::InsufficientLiquidity.into());
+            }
+
+            if send_1 > amount_1_max {
+                msg!("Amount 1 ({}) exceeds maximum ({})", send_1, amount_1_max);
+                return Err(CLMMError::InsufficientLiquidity.into());
+            }
+        }
+        LiquidityAmount::Amounts { amount_0_desired, amount_1_desired, amount_0_min, amount_1_min } => {
+            // Rounding may never take more than desired
+            if send_0 > amount_0_desired || send_1 > amount_1_desired {
+                msg!("Amounts ({}, {}) exceed desired ({}, {})",
+                    send_0, send_1, amount_0_desired, amount_1_desired);
+                return Err(CLMMError::MathOverflow.into());
+            }
+
+            // Validate amounts meet minimums
+            if amount_0_u64 < amount_0_min {
+                msg!("Amount 0 ({}) below minimum ({})", amount_0_u64, amount_0_min);
+                return Err(CLMMError::SlippageExceeded.into());
+            }
+
+            if amount_1_u64 < amount_1_min {
+                msg!("Amount 1 ({}) below minimum ({})", amount_1_u64, amount_1_min);
+                return Err(CLMMError::SlippageExceeded.into());
+            }
+        }
     }
 
-    if amount_1_u64 > amount_1_max {
-        msg!("Amount 1 ({}) exceeds maximum ({})", amount_1_u64, amount_1_max);
-        return Err(CLMMError::InsufficientLiquidity.into());
-    }
-
-    // Get rent
     let rent = Rent::get()?;
-
-    // Handle position account (create or update)
-    let (expected_position, position_bump) = derive_position_address(
+    let funding = PoolAccountFunding {
         program_id,
-        pool_account.key,
-        owner.key,
-        tick_lower,
-        tick_upper,
-    );
-
-    if position_account.key != &expected_position {
-        msg!("Invalid position PDA");
-        return Err(ProgramError::InvalidSeeds);
-    }
-
-    let mut position = if position_account.data_is_empty() || position_account.lamports() == 0 {
-        // Create new position
-        msg!("Creating new position");
-
-        let position_seeds = &[
-            b"position",
-            pool_account.key.as_ref(),
-            owner.key.as_ref(),
-            &tick_lower.to_le_bytes(),
-            &tick_upper.to_le_bytes(),
-            &[position_bump],
-        ];
-
-        let position_size = std::mem::size_of::<Position>() + 8;
-
-        create_account(
-            owner,
-            position_account,
-            system_program,
-            program_id,
-            &rent,
-            position_size,
-            position_seeds,
-        )?;
-
-        let position_id = pool.position_count;
-        pool.position_count += 1;
-
-        Position::new(
-            *pool_account.key,
-            *owner.key,
-            tick_lower,
-            tick_upper,
-            position_id,
-            current_time,
-        ).map_err(|e| {
-            msg!("Failed to create position: {}", e);
-            CLMMError::InvalidTickRange
-        })?
-    } else {
-        // Load existing position
-        msg!("Updating existing position");
-        let position_data = position_account.try_borrow_data()?;
-        Position::deserialize(&mut &position_data[..])?
+        pool_key: pool_account.key,
+        payer: owner,
+        system_program,
+        rent: &rent,
     };
 
-    // Update position liquidity
-    position.liquidity = position.liquidity + liquidity_u256;
-    position.updated_at = current_time;
-
     // Handle ticks
-    update_tick(
-        program_id,
-        pool_account.key,
+    let (mut lower_tick, lower_flipped) = update_tick(
+        &funding,
+        &pool,
         tick_lower_account,
         tick_lower,
-        I256::from_dec_str(&liquidity_delta.to_string()).unwrap_or(I256_ZERO),
+        I256::from(liquidity_delta),
         false, // lower tick
-        owner,
-        system_program,
-        &rent,
     )?;
 
-    update_tick(
-        program_id,
-        pool_account.key,
+    let (mut upper_tick, upper_flipped) = update_tick(
+        &funding,
+        &pool,
         tick_upper_account,
         tick_upper,
-        I256::from_dec_str(&liquidity_delta.to_string()).unwrap_or(I256_ZERO),
+        I256::from(liquidity_delta),
         true, // upper tick
-        owner,
-        system_program,
-        &rent,
     )?;
 
+    // The completion tick tracks the orders it will fill
+    match range_order_side {
+        RangeOrder::SellToken0 => {
+            position.range_order_epoch = place_range_order(tick_upper_account, &mut upper_tick, liquidity_u256, true)?;
+        }
+        RangeOrder::SellToken1 => {
+            position.range_order_epoch = place_range_order(tick_lower_account, &mut lower_tick, liquidity_u256, false)?;
+        }
+        RangeOrder::None => {}
+    }
+    position.range_order = range_order_side;
+
+    // Settle fees earned by the existing liquidity before it changes
+    let (fee_growth_inside_0, fee_growth_inside_1) = Tick::fee_growth_inside(
+        &lower_tick,
+        &upper_tick,
+        pool.tick,
+        pool.fee_growth_global0_x128,
+        pool.fee_growth_global1_x128,
+    );
+    let (fees_0, fees_1) = position.fees_earned(fee_growth_inside_0, fee_growth_inside_1)?;
+    position.add_tokens_owed(fees_0, fees_1);
+    position.update_fee_growth(fee_growth_inside_0, fee_growth_inside_1, current_time);
+
+    // Update position liquidity
+    position.liquidity += liquidity_u256;
+    position.record_deposit(liquidity_u256, amount_0_u64, amount_1_u64, pool.sqrt_price_x96)?;
+
+    // Newly initialized ticks are recorded in their bitmap words
+    if lower_flipped {
+        flip_tick_bitmap(&funding, bitmap_lower_account, tick_lower, pool.tick_spacing)?;
+    }
+
+    if upper_flipped {
+        flip_tick_bitmap(&funding, bitmap_upper_account, tick_upper, pool.tick_spacing)?;
+    }
+
     // Update pool liquidity if position is in range
     if pool.tick >= tick_lower && pool.tick < tick_upper {
         pool.liquidity = pool.liquidity + liquidity_u256;
         msg!("Updated pool liquidity: {}", pool.liquidity);
     }
 
-    // Transfer tokens from user to pool vaults
-    if amount_0_u64 > 0 {
-        msg!("Transferring {} of token A from user to pool", amount_0_u64);
-        token_transfer(
+    // Wrap exactly the SOL side's deposit from the holder's lamports
+    let native_side = if native_sol {
+        let native_is_a = pool.native_mint_is_a().map_err(|e| {
+            msg!("{}", e);
+            CLMMError::InvalidAccount
+        })?;
+        let (wsol_account, native_mint, token_program, lamports) = if native_is_a {
+            (user_token_a, mint_a, token_program_a, send_0)
+        } else {
+            (user_token_b, mint_b, token_program_b, send_1)
+        };
+        let native_side = NativeSolAccounts {
+            owner,
+            wsol_account,
+            native_mint,
             token_program,
+            system_program,
+            rent_sysvar,
+        };
+        wrap_native(program_id, &native_side, lamports)?;
+        Some(native_side)
+    } else {
+        None
+    };
+
+    // Transfer tokens from user to pool vaults, crediting what actually arrives
+    let mut received_0 = 0;
+    if send_0 > 0 {
+        msg!("Transferring {} of token A from user to pool", send_0);
+        received_0 = token_transfer_received(
+            token_program_a,
             user_token_a,
+            mint_a,
             vault_a,
             owner,
-            amount_0_u64,
+            send_0,
         )?;
     }
 
-    if amount_1_u64 > 0 {
-        msg!("Transferring {} of token B from user to pool", amount_1_u64);
-        token_transfer(
-            token_program,
+    let mut received_1 = 0;
+    if send_1 > 0 {
+        msg!("Transferring {} of token B from user to pool", send_1);
+        received_1 = token_transfer_received(
+            token_program_b,
             user_token_b,
+            mint_b,
             vault_b,
             owner,
-            amount_1_u64,
+            send_1,
         )?;
     }
 
+    if received_0 < amount_0_u64 || received_1 < amount_1_u64 {
+        msg!("Vaults received ({}, {}), position requires ({}, {})",
+            received_0, received_1, amount_0_u64, amount_1_u64);
+        return Err(CLMMError::InsufficientAmountReceived.into());
+    }
+
+    if let Some(native_side) = native_side {
+        unwrap_native(&native_side)?;
+    }
+
     // Save updated states
     write_account_data(position_account, &position)?;
     write_account_data(pool_account, &pool)?;
@@ -262,13 +480,53 @@ pub fn process(
     msg!("Liquidity added successfully");
     msg!("  Position: {}", position_account.key);
     msg!("  Liquidity: {}", liquidity_delta);
-    msg!("  Amount 0: {}", amount_0_u64);
-    msg!("  Amount 1: {}", amount_1_u64);
+    msg!("  Amount 0: {}", received_0);
+    msg!("  Amount 1: {}", received_1);
     msg!("  Tick range: [{}, {}]", tick_lower, tick_upper);
 
+    let result = AddLiquidityResult {
+        liquidity_delta,
+        amount_0: received_0,
+        amount_1: received_1,
+    };
+    let mut return_data = Vec::new();
+    result.serialize(&mut return_data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    set_return_data(&return_data);
+
     Ok(())
 }
 
+/// Largest liquidity the given amounts can fund at the pool's current price
+fn calculate_liquidity_for_amounts(
+    pool: &Pool,
+    tick_lower: i32,
+    tick_upper: i32,
+    amount_0: u64,
+    amount_1: u64,
+) -> Result<u128, ProgramError> {
+    use crate::math::TickMath;
+    use crate::math::FixedPointMath;
+
+    let sqrt_price_lower = TickMath::get_sqrt_ratio_at_tick(tick_lower)?;
+    let sqrt_price_upper = TickMath::get_sqrt_ratio_at_tick(tick_upper)?;
+
+    let liquidity = FixedPointMath::get_liquidity_for_amounts(
+        pool.sqrt_price_x96,
+        sqrt_price_lower,
+        sqrt_price_upper,
+        U256::from(amount_0),
+        U256::from(amount_1),
+    );
+
+    if liquidity > U256::from(u128::MAX) {
+        msg!("Liquidity overflows u128");
+        return Err(CLMMError::MathOverflow.into());
+    }
+
+    Ok(liquidity.as_u128())
+}
+
 /// Calculate token amounts needed for liquidity
 fn calculate_amounts_for_liquidity(
     pool: &Pool,
@@ -290,7 +548,7 @@ fn calculate_amounts_for_liquidity(
             sqrt_price_upper,
             liquidity,
             true,
-        );
+        )?;
         (amount_0, U256_ZERO)
     } else if current_sqrt_price < sqrt_price_upper {
         // Price in range - both tokens needed
@@ -299,13 +557,13 @@ fn calculate_amounts_for_liquidity(
             sqrt_price_upper,
             liquidity,
             true,
-        );
+        )?;
         let amount_1 = FixedPointMath::get_amount1_delta(
             sqrt_price_lower,
             current_sqrt_price,
             liquidity,
             true,
-        );
+        )?;
         (amount_0, amount_1)
     } else {
         // Price above range - only token1 needed
@@ -314,25 +572,32 @@ fn calculate_amounts_for_liquidity(
             sqrt_price_upper,
             liquidity,
             true,
-        );
+        )?;
         (U256_ZERO, amount_1)
     };
 
     Ok((amount_0, amount_1))
 }
 
-/// Update or create a tick
+/// The pool and the accounts paying for tick and bitmap accounts created on first use
+struct PoolAccountFunding<'a, 'b> {
+    program_id: &'b Pubkey,
+    pool_key: &'b Pubkey,
+    payer: &'b AccountInfo<'a>,
+    system_program: &'b AccountInfo<'a>,
+    rent: &'b Rent,
+}
+
+/// Update or create a tick, returning it and whether it flipped between initialized and uninitialized
 fn update_tick<'a>(
-    program_id: &Pubkey,
-    pool_key: &Pubkey,
+    funding: &PoolAccountFunding<'a, '_>,
+    pool: &Pool,
     tick_account: &AccountInfo<'a>,
     tick_index: i32,
     liquidity_delta: I256,
     upper: bool,
-    payer: &AccountInfo<'a>,
-    system_program: &AccountInfo<'a>,
-    rent: &Rent,
-) -> ProgramResult {
+) -> Result<(Tick, bool), ProgramError> {
+    let PoolAccountFunding { program_id, pool_key, payer, system_program, rent } = *funding;
     let (expected_tick, tick_bump) = derive_tick_address(program_id, pool_key, tick_index);
 
     if tick_account.key != &expected_tick {
@@ -369,10 +634,87 @@ fn update_tick<'a>(
     };
 
     // Update tick liquidity
-    tick.update_liquidity(liquidity_delta, upper);
+    let flipped = tick.update_liquidity(liquidity_delta, upper)?;
+
+    // By convention all fee growth before a tick is initialized happened below it
+    if flipped {
+        if tick_index <= pool.tick {
+            tick.update_fee_growth_outside(pool.fee_growth_global0_x128, pool.fee_growth_global1_x128);
+        } else {
+            tick.update_fee_growth_outside(U256_ZERO, U256_ZERO);
+        }
+    }
 
     // Save tick
     write_account_data(tick_account, &tick)?;
 
+    Ok((tick, flipped))
+}
+
+/// Register a range order on its completion tick, returning the epoch it was placed in
+fn place_range_order(
+    tick_account: &AccountInfo,
+    tick: &mut Tick,
+    liquidity: U256,
+    upward: bool,
+) -> Result<u32, ProgramError> {
+    let epoch = tick.add_range_order(liquidity, upward)
+        .map_err(|e| {
+            msg!("Cannot place range order: {}", e);
+            CLMMError::RangeOrderFilled
+        })?;
+
+    write_account_data(tick_account, tick)?;
+
+    Ok(epoch)
+}
+
+/// Flip a tick's bit in its bitmap word, creating the word account on first use
+fn flip_tick_bitmap<'a>(
+    funding: &PoolAccountFunding<'a, '_>,
+    bitmap_account: &AccountInfo<'a>,
+    tick_index: i32,
+    tick_spacing: u32,
+) -> ProgramResult {
+    let PoolAccountFunding { program_id, pool_key, payer, system_program, rent } = *funding;
+    let (word_position, _bit_position) = TickBitmap::position(tick_index, tick_spacing);
+    let (expected_bitmap, bitmap_bump) = derive_tick_bitmap_address(program_id, pool_key, word_position);
+
+    if bitmap_account.key != &expected_bitmap {
+        msg!("Invalid tick bitmap PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut tick_bitmap = if bitmap_account.data_is_empty() || bitmap_account.lamports() == 0 {
+        // Create new bitmap word
+        let bitmap_seeds = &[
+            b"bitmap",
+            pool_key.as_ref(),
+            &word_position.to_le_bytes(),
+            &[bitmap_bump],
+        ];
+
+        create_account(
+            payer,
+            bitmap_account,
+            system_program,
+            program_id,
+            rent,
+            TICK_BITMAP_ACCOUNT_SIZE,
+            bitmap_seeds,
+        )?;
+
+        TickBitmap::new(word_position)
+    } else {
+        // Load existing bitmap word
+        let bitmap_data = bitmap_account.try_borrow_data()?;
+        TickBitmap::deserialize(&mut &bitmap_data[..])?
+    };
+
+    tick_bitmap.flip_tick(tick_index, tick_spacing);
+
+    // Save bitmap word
+    write_account_data(bitmap_account, &tick_bitmap)?;
+
     Ok(())
 }
@@ -0,0 +1,88 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use borsh::BorshDeserialize;
+use crate::error::CLMMError;
+use crate::state::Position;
+use crate::math::tick_math::U256_ZERO;
+use crate::utils::{
+    assert_signer, assert_writable, assert_owned_by, assert_initialized,
+    assert_position_holder, token_burn, close_account,
+};
+
+/// Close an empty position, burning its position token
+///
+/// Accounts expected:
+/// 0. `[signer, writable]` Position token holder (receives the rent)
+/// 1. `[writable]` Position account
+/// 2. `[writable]` Holder's position token account
+/// 3. `[writable]` Position mint
+/// 4. `[]` Token program
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    msg!("Closing position...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    // Parse accounts
+    let holder = next_account_info(account_info_iter)?;
+    let position_account = next_account_info(account_info_iter)?;
+    let position_token_account = next_account_info(account_info_iter)?;
+    let position_mint = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    assert_signer(holder)?;
+    assert_writable(holder)?;
+    assert_writable(position_account)?;
+    assert_writable(position_token_account)?;
+    assert_writable(position_mint)?;
+    assert_owned_by(position_account, program_id)?;
+    assert_initialized(position_account)?;
+
+    if token_program.key != &spl_token::id() {
+        msg!("Invalid token program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let position_data = position_account.try_borrow_data()?;
+    let position = Position::deserialize(&mut &position_data[..])?;
+    drop(position_data);
+
+    if position_mint.key != &position.position_mint {
+        msg!("Position mint mismatch");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Whoever holds the position token controls the position
+    assert_position_holder(position_token_account, &position.position_mint, holder.key)
+        .map_err(|_| {
+            msg!("Signer does not hold the position token");
+            CLMMError::Unauthorized
+        })?;
+
+    // Liquidity and owed tokens must be withdrawn first
+    if position.liquidity != U256_ZERO {
+        msg!("Position still has liquidity: {}", position.liquidity);
+        return Err(CLMMError::InvalidAccount.into());
+    }
+
+    if position.tokens_owed0 != U256_ZERO || position.tokens_owed1 != U256_ZERO {
+        msg!("Position still has uncollected fees");
+        return Err(CLMMError::InvalidAccount.into());
+    }
+
+    token_burn(token_program, position_token_account, position_mint, holder, 1)?;
+    close_account(position_account, holder)?;
+
+    msg!("Position closed successfully");
+    msg!("  Position: {}", position_account.key);
+    msg!("  Position mint: {}", position_mint.key);
+
+    Ok(())
+}
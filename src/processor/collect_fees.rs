@@ -7,31 +7,44 @@ use solana_program::{
 };
 use borsh::BorshDeserialize;
 use crate::error::CLMMError;
-use crate::state::{Pool, Position};
-use crate::math::tick_math::{U256, U256_ZERO};
+use crate::state::{Pool, Position, Tick};
+use crate::math::tick_math::U256;
 use crate::utils::{
     assert_signer, assert_writable, assert_owned_by, assert_initialized,
-    write_account_data, get_current_timestamp, token_transfer_signed,
-    derive_pool_authority_address, pool_authority_seeds,
+    write_account_data, get_current_timestamp, token_transfer_signed, assert_position_holder,
+    derive_pool_authority_address, derive_tick_address, pool_authority_seeds,
+    wrap_native, unwrap_native, NativeSolAccounts,
 };
 
 /// Collect fees from a position
 ///
 /// Accounts expected:
-/// 0. `[signer]` Position owner
+/// 0. `[signer]` Position token holder (writable with `native_sol`)
 /// 1. `[writable]` Pool account
 /// 2. `[writable]` Position account
-/// 3. `[writable]` User token A account (recipient)
-/// 4. `[writable]` User token B account (recipient)
-/// 5. `[writable]` Pool vault A
-/// 6. `[writable]` Pool vault B
-/// 7. `[]` Pool authority (PDA)
-/// 8. `[]` Token program
+/// 3. `[]` Holder's position token account
+/// 4. `[]` Tick lower account (PDA)
+/// 5. `[]` Tick upper account (PDA)
+/// 6. `[writable]` User token A account (recipient)
+/// 7. `[writable]` User token B account (recipient)
+/// 8. `[writable]` Pool vault A
+/// 9. `[writable]` Pool vault B
+/// 10. `[]` Pool authority (PDA)
+/// 11. `[]` Token program for token A
+/// 12. `[]` Token program for token B
+/// 13. `[]` Token A mint
+/// 14. `[]` Token B mint
+/// 15. `[]` System program (only with `native_sol`)
+/// 16. `[]` Rent sysvar (only with `native_sol`)
+///
+/// With `native_sol`, the user token account for the pool's SOL side is the holder's
+/// wrapped SOL PDA; it is created for the collection and closed so the holder receives SOL.
 pub fn process(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     amount_0_requested: u64,
     amount_1_requested: u64,
+    native_sol: bool,
 ) -> ProgramResult {
     msg!("Collecting fees from position...");
 
@@ -41,14 +54,25 @@ pub fn process(
     let owner = next_account_info(account_info_iter)?;
     let pool_account = next_account_info(account_info_iter)?;
     let position_account = next_account_info(account_info_iter)?;
+    let position_token_account = next_account_info(account_info_iter)?;
+    let tick_lower_account = next_account_info(account_info_iter)?;
+    let tick_upper_account = next_account_info(account_info_iter)?;
     let user_token_a = next_account_info(account_info_iter)?;
     let user_token_b = next_account_info(account_info_iter)?;
     let vault_a = next_account_info(account_info_iter)?;
     let vault_b = next_account_info(account_info_iter)?;
     let pool_authority = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
+    let token_program_a = next_account_info(account_info_iter)?;
+    let token_program_b = next_account_info(account_info_iter)?;
+    let mint_a = next_account_info(account_info_iter)?;
+    let mint_b = next_account_info(account_info_iter)?;
+    let native_accounts = if native_sol {
+        Some((next_account_info(account_info_iter)?, next_account_info(account_info_iter)?))
+    } else {
+        None
+    };
 
-    // Validate owner is signer
+    // Validate holder is signer
     assert_signer(owner)?;
 
     // Validate writable accounts
@@ -67,26 +91,32 @@ pub fn process(
 
     // Deserialize pool
     let pool_data = pool_account.try_borrow_data()?;
-    let mut pool = Pool::deserialize(&mut &pool_data[..])?;
+    let pool = Pool::deserialize(&mut &pool_data[..])?;
     drop(pool_data);
 
+    pool.validate_token_accounts(token_program_a.key, token_program_b.key, mint_a.key, mint_b.key)
+        .map_err(|e| {
+            msg!("{}", e);
+            CLMMError::InvalidAccount
+        })?;
+
     // Deserialize position
     let position_data = position_account.try_borrow_data()?;
     let mut position = Position::deserialize(&mut &position_data[..])?;
     drop(position_data);
 
-    // Validate position owner
-    if &position.owner != owner.key {
-        msg!("Position owner mismatch");
-        return Err(CLMMError::Unauthorized.into());
-    }
-
-    // Validate position is active
-    if !position.is_active {
-        msg!("Position is not active");
+    if &position.pool_id != pool_account.key {
+        msg!("Position belongs to a different pool");
         return Err(CLMMError::InvalidAccount.into());
     }
 
+    // Whoever holds the position token controls the position
+    assert_position_holder(position_token_account, &position.position_mint, owner.key)
+        .map_err(|_| {
+            msg!("Signer does not hold the position token");
+            CLMMError::Unauthorized
+        })?;
+
     // Get current timestamp
     let current_time = get_current_timestamp()? as u32;
 
@@ -101,50 +131,92 @@ pub fn process(
         return Err(ProgramError::InvalidSeeds);
     }
 
-    // Calculate all fees earned (including already owed)
-    let (accrued_fees_0, accrued_fees_1) = calculate_accrued_fees(&pool, &mut position)?;
+    // Load the range's boundary ticks
+    let tick_lower = load_tick(program_id, pool_account.key, tick_lower_account, position.tick_lower)?;
+    let tick_upper = load_tick(program_id, pool_account.key, tick_upper_account, position.tick_upper)?;
+
+    // Fees of a filled range order are paid out by settlement
+    if position.is_range_order_filled(&tick_lower, &tick_upper) {
+        msg!("Range order has been filled, settle it instead");
+        return Err(CLMMError::RangeOrderFilled.into());
+    }
+
+    // Only fee growth inside the position's range accrues to it
+    let (fee_growth_inside_0, fee_growth_inside_1) = Tick::fee_growth_inside(
+        &tick_lower,
+        &tick_upper,
+        pool.tick,
+        pool.fee_growth_global0_x128,
+        pool.fee_growth_global1_x128,
+    );
+    let (accrued_fees_0, accrued_fees_1) = position.fees_earned(fee_growth_inside_0, fee_growth_inside_1)?;
+    msg!("Accrued fees since last update: {} token A, {} token B", accrued_fees_0, accrued_fees_1);
 
     // Add newly accrued fees to tokens owed
     position.add_tokens_owed(accrued_fees_0, accrued_fees_1);
 
     // Update fee growth tracking to current values
     position.update_fee_growth(
-        pool.fee_growth_global0_x128,
-        pool.fee_growth_global1_x128,
+        fee_growth_inside_0,
+        fee_growth_inside_1,
         current_time,
     );
 
     // Determine amounts to collect
-    let amount_0_to_collect = if amount_0_requested == 0 || amount_0_requested > position.tokens_owed0.low_u64() {
-        position.tokens_owed0.low_u64()
+    let amount_0_to_collect = if amount_0_requested == 0 {
+        position.tokens_owed0
     } else {
-        amount_0_requested
+        position.tokens_owed0.min(U256::from(amount_0_requested))
     };
 
-    let amount_1_to_collect = if amount_1_requested == 0 || amount_1_requested > position.tokens_owed1.low_u64() {
-        position.tokens_owed1.low_u64()
+    let amount_1_to_collect = if amount_1_requested == 0 {
+        position.tokens_owed1
     } else {
-        amount_1_requested
+        position.tokens_owed1.min(U256::from(amount_1_requested))
     };
 
     // Check if there are fees to collect
-    if amount_0_to_collect == 0 && amount_1_to_collect == 0 {
+    if amount_0_to_collect.is_zero() && amount_1_to_collect.is_zero() {
         msg!("No fees to collect");
         return Ok(());
     }
 
     // Collect tokens from position
     let (collected_0, collected_1) = position.collect_tokens_owed(
-        U256::from(amount_0_to_collect),
-        U256::from(amount_1_to_collect),
+        amount_0_to_collect,
+        amount_1_to_collect,
     );
 
-    let collected_0_u64 = collected_0.low_u64();
-    let collected_1_u64 = collected_1.low_u64();
-
-    // Update pool protocol fees (if any)
-    // Note: In a production system, a percentage of fees might go to the protocol
-    // For now, all fees go to liquidity providers
+    let collected_0_u64 = u64::try_from(collected_0).map_err(|_| CLMMError::MathOverflow)?;
+    let collected_1_u64 = u64::try_from(collected_1).map_err(|_| CLMMError::MathOverflow)?;
+    position.record_fees_collected(collected_0_u64, collected_1_u64);
+
+    // The SOL side is paid into a temporary wrapped SOL account and unwrapped below
+    let native_side = match native_accounts {
+        Some((system_program, rent_sysvar)) => {
+            assert_writable(owner)?;
+            let native_is_a = pool.native_mint_is_a().map_err(|e| {
+                msg!("{}", e);
+                CLMMError::InvalidAccount
+            })?;
+            let (wsol_account, native_mint, token_program) = if native_is_a {
+                (user_token_a, mint_a, token_program_a)
+            } else {
+                (user_token_b, mint_b, token_program_b)
+            };
+            let native_side = NativeSolAccounts {
+                owner,
+                wsol_account,
+                native_mint,
+                token_program,
+                system_program,
+                rent_sysvar,
+            };
+            wrap_native(program_id, &native_side, 0)?;
+            Some(native_side)
+        }
+        None => None,
+    };
 
     // Transfer collected fees from pool vaults to user
     let authority_bump_arr = [authority_bump];
@@ -156,31 +228,31 @@ pub fn process(
     if collected_0_u64 > 0 {
         msg!("Transferring {} of token A fees to user", collected_0_u64);
         token_transfer_signed(
-            token_program,
+            token_program_a,
             vault_a,
+            mint_a,
             user_token_a,
             pool_authority,
             collected_0_u64,
             &authority_seeds,
         )?;
-
-        // Update pool protocol fees tracking
-        pool.protocol_fees_token0 = pool.protocol_fees_token0 + collected_0;
     }
 
     if collected_1_u64 > 0 {
         msg!("Transferring {} of token B fees to user", collected_1_u64);
         token_transfer_signed(
-            token_program,
+            token_program_b,
             vault_b,
+            mint_b,
             user_token_b,
             pool_authority,
             collected_1_u64,
             &authority_seeds,
         )?;
+    }
 
-        // Update pool protocol fees tracking
-        pool.protocol_fees_token1 = pool.protocol_fees_token1 + collected_1;
+    if let Some(native_side) = native_side {
+        unwrap_native(&native_side)?;
     }
 
     // Update position timestamp
@@ -200,80 +272,26 @@ pub fn process(
     Ok(())
 }
 
-/// Calculate fees accrued since last update
-fn calculate_accrued_fees(
-    pool: &Pool,
-    position: &Position,
-) -> Result<(U256, U256), ProgramError> {
-    // If position has no liquidity, no new fees accrued
-    if position.liquidity == U256_ZERO {
-        return Ok((U256_ZERO, U256_ZERO));
-    }
-
-    // Calculate fee growth inside the position's range since last update
-    let fee_growth_delta_0 = pool.fee_growth_global0_x128
-        .checked_sub(position.fee_growth_inside0_last_x128)
-        .unwrap_or(U256_ZERO);
-
-    let fee_growth_delta_1 = pool.fee_growth_global1_x128
-        .checked_sub(position.fee_growth_inside1_last_x128)
-        .unwrap_or(U256_ZERO);
-
-    // Calculate fees: (liquidity * fee_growth_delta) / 2^128
-    let fees_0 = (position.liquidity * fee_growth_delta_0) / (U256::from(1u128) << 128);
-    let fees_1 = (position.liquidity * fee_growth_delta_1) / (U256::from(1u128) << 128);
-
-    msg!("Accrued fees since last update: {} token A, {} token B", fees_0, fees_1);
-
-    Ok((fees_0, fees_1))
-}
-
-/// Calculate fee growth inside a tick range
-/// This is a simplified version - in production, you'd need to fetch tick data
-#[allow(dead_code)]
-fn calculate_fee_growth_inside(
-    pool: &Pool,
-    tick_lower: i32,
-    tick_upper: i32,
-    fee_growth_outside_lower_0: U256,
-    fee_growth_outside_lower_1: U256,
-    fee_growth_outside_upper_0: U256,
-    fee_growth_outside_upper_1: U256,
-) -> (U256, U256) {
-    let current_tick = pool.tick;
-
-    // Calculate fee growth below lower tick
-    let fee_growth_below_0;
-    let fee_growth_below_1;
-
-    if current_tick >= tick_lower {
-        fee_growth_below_0 = fee_growth_outside_lower_0;
-        fee_growth_below_1 = fee_growth_outside_lower_1;
-    } else {
-        fee_growth_below_0 = pool.fee_growth_global0_x128 - fee_growth_outside_lower_0;
-        fee_growth_below_1 = pool.fee_growth_global1_x128 - fee_growth_outside_lower_1;
-    }
-
-    // Calculate fee growth above upper tick
-    let fee_growth_above_0;
-    let fee_growth_above_1;
-
-    if current_tick < tick_upper {
-        fee_growth_above_0 = fee_growth_outside_upper_0;
-        fee_growth_above_1 = fee_growth_outside_upper_1;
-    } else {
-        fee_growth_above_0 = pool.fee_growth_global0_x128 - fee_growth_outside_upper_0;
-        fee_growth_above_1 = pool.fee_growth_global1_x128 - fee_growth_outside_upper_1;
+/// Load one of the position's boundary ticks, checking its PDA
+fn load_tick(
+    program_id: &Pubkey,
+    pool_key: &Pubkey,
+    tick_account: &AccountInfo,
+    tick_index: i32,
+) -> Result<Tick, ProgramError> {
+    let (expected_tick, _tick_bump) = derive_tick_address(program_id, pool_key, tick_index);
+
+    if tick_account.key != &expected_tick {
+        msg!("Invalid tick PDA");
+        return Err(ProgramError::InvalidSeeds);
     }
 
-    // Calculate fee growth inside the range
-    let fee_growth_inside_0 = pool.fee_growth_global0_x128
-        - fee_growth_below_0
-        - fee_growth_above_0;
+    assert_owned_by(tick_account, program_id)?;
+    assert_initialized(tick_account)?;
 
-    let fee_growth_inside_1 = pool.fee_growth_global1_x128
-        - fee_growth_below_1
-        - fee_growth_above_1;
+    let tick_data = tick_account.try_borrow_data()?;
+    let tick = Tick::deserialize(&mut &tick_data[..])?;
+    drop(tick_data);
 
-    (fee_growth_inside_0, fee_growth_inside_1)
+    Ok(tick)
 }
@@ -0,0 +1,160 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use borsh::BorshDeserialize;
+use crate::error::CLMMError;
+use crate::state::Pool;
+use crate::math::tick_math::U256;
+use crate::utils::{
+    assert_signer, assert_writable, assert_owned_by, assert_initialized,
+    write_account_data, token_transfer_signed, derive_pool_authority_address,
+    derive_pool_vault_a_address, derive_pool_vault_b_address, pool_authority_seeds,
+};
+use super::set_protocol_fee::load_protocol_config;
+
+/// Withdraw protocol fees accrued by a pool
+///
+/// Accounts expected:
+/// 0. `[signer]` Protocol authority
+/// 1. `[]` Protocol config account (PDA)
+/// 2. `[writable]` Pool account
+/// 3. `[writable]` Recipient token A account
+/// 4. `[writable]` Recipient token B account
+/// 5. `[writable]` Pool vault A
+/// 6. `[writable]` Pool vault B
+/// 7. `[]` Pool authority (PDA)
+/// 8. `[]` Token program for token A
+/// 9. `[]` Token program for token B
+/// 10. `[]` Token A mint
+/// 11. `[]` Token B mint
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount_0_requested: u64,
+    amount_1_requested: u64,
+) -> ProgramResult {
+    msg!("Collecting protocol fees...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    // Parse accounts
+    let authority = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+    let recipient_token_a = next_account_info(account_info_iter)?;
+    let recipient_token_b = next_account_info(account_info_iter)?;
+    let vault_a = next_account_info(account_info_iter)?;
+    let vault_b = next_account_info(account_info_iter)?;
+    let pool_authority = next_account_info(account_info_iter)?;
+    let token_program_a = next_account_info(account_info_iter)?;
+    let token_program_b = next_account_info(account_info_iter)?;
+    let mint_a = next_account_info(account_info_iter)?;
+    let mint_b = next_account_info(account_info_iter)?;
+
+    assert_signer(authority)?;
+    assert_writable(pool_account)?;
+    assert_writable(recipient_token_a)?;
+    assert_writable(recipient_token_b)?;
+    assert_writable(vault_a)?;
+    assert_writable(vault_b)?;
+    assert_owned_by(pool_account, program_id)?;
+    assert_initialized(pool_account)?;
+
+    load_protocol_config(program_id, config_account, authority)?;
+
+    let pool_data = pool_account.try_borrow_data()?;
+    let mut pool = Pool::deserialize(&mut &pool_data[..])?;
+    drop(pool_data);
+
+    pool.validate_token_accounts(token_program_a.key, token_program_b.key, mint_a.key, mint_b.key)
+        .map_err(|e| {
+            msg!("{}", e);
+            CLMMError::InvalidAccount
+        })?;
+
+    // Validate pool authority and vault PDAs
+    let (expected_authority, authority_bump) = derive_pool_authority_address(
+        program_id,
+        pool_account.key,
+    );
+    if pool_authority.key != &expected_authority {
+        msg!("Invalid pool authority");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (expected_vault_a, _) = derive_pool_vault_a_address(program_id, pool_account.key);
+    let (expected_vault_b, _) = derive_pool_vault_b_address(program_id, pool_account.key);
+    if vault_a.key != &expected_vault_a || vault_b.key != &expected_vault_b {
+        msg!("Invalid pool vault");
+        return Err(CLMMError::InvalidAccount.into());
+    }
+
+    // Determine amounts to collect (0 = everything accrued)
+    let owed_0 = pool.protocol_fees_token0.min(U256::from(u64::MAX)).low_u64();
+    let owed_1 = pool.protocol_fees_token1.min(U256::from(u64::MAX)).low_u64();
+    let amount_0 = if amount_0_requested == 0 || amount_0_requested > owed_0 {
+        owed_0
+    } else {
+        amount_0_requested
+    };
+    let amount_1 = if amount_1_requested == 0 || amount_1_requested > owed_1 {
+        owed_1
+    } else {
+        amount_1_requested
+    };
+
+    if amount_0 == 0 && amount_1 == 0 {
+        msg!("No protocol fees to collect");
+        return Ok(());
+    }
+
+    pool.protocol_fees_token0 -= U256::from(amount_0);
+    pool.protocol_fees_token1 -= U256::from(amount_1);
+
+    let authority_bump_arr = [authority_bump];
+    let authority_seeds = pool_authority_seeds(
+        pool_account.key,
+        &authority_bump_arr,
+    );
+
+    if amount_0 > 0 {
+        msg!("Transferring {} of token A protocol fees", amount_0);
+        token_transfer_signed(
+            token_program_a,
+            vault_a,
+            mint_a,
+            recipient_token_a,
+            pool_authority,
+            amount_0,
+            &authority_seeds,
+        )?;
+    }
+
+    if amount_1 > 0 {
+        msg!("Transferring {} of token B protocol fees", amount_1);
+        token_transfer_signed(
+            token_program_b,
+            vault_b,
+            mint_b,
+            recipient_token_b,
+            pool_authority,
+            amount_1,
+            &authority_seeds,
+        )?;
+    }
+
+    write_account_data(pool_account, &pool)?;
+
+    msg!("Protocol fees collected successfully");
+    msg!("  Pool: {}", pool_account.key);
+    msg!("  Token A collected: {}", amount_0);
+    msg!("  Token B collected: {}", amount_1);
+    msg!("  Token A remaining: {}", pool.protocol_fees_token0);
+    msg!("  Token B remaining: {}", pool.protocol_fees_token1);
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::error::CLMMError;
+use crate::state::Pool;
+use crate::math::SwapEngine;
+use crate::math::tick_math::{U256, U256_ZERO};
+use crate::utils::{
+    assert_signer, assert_writable, assert_owned_by, assert_initialized,
+    write_account_data, token_transfer_signed, get_token_balance,
+    derive_pool_authority_address, derive_pool_vault_a_address,
+    derive_pool_vault_b_address, pool_authority_seeds,
+};
+
+/// Instruction data the borrower program is invoked with
+///
+/// The borrower must return `amount + fee` of each token to the pool vaults
+/// before its instruction finishes.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct FlashCallback {
+    pub amount_0: u64,
+    pub amount_1: u64,
+    pub fee_0: u64,
+    pub fee_1: u64,
+    /// Opaque data forwarded from the `Flash` instruction
+    pub data: Vec<u8>,
+}
+
+/// Lend tokens from the pool vaults for the duration of a borrower CPI
+///
+/// Accounts expected:
+/// 0. `[signer]` Caller
+/// 1. `[writable]` Pool account
+/// 2. `[writable]` Recipient token A account
+/// 3. `[writable]` Recipient token B account
+/// 4. `[writable]` Pool vault A
+/// 5. `[writable]` Pool vault B
+/// 6. `[]` Pool authority (PDA)
+/// 7. `[]` Token program for token A
+/// 8. `[]` Borrower program
+/// 9. `[]` Token program for token B
+/// 10. `[]` Token A mint
+/// 11. `[]` Token B mint
+/// 12. `[]` Accounts forwarded to the borrower program (zero or more)
+pub fn process<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    amount_0: u64,
+    amount_1: u64,
+    data: Vec<u8>,
+) -> ProgramResult {
+    msg!("Processing flash loan...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    // Parse accounts
+    let caller = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+    let recipient_token_a = next_account_info(account_info_iter)?;
+    let recipient_token_b = next_account_info(account_info_iter)?;
+    let vault_a = next_account_info(account_info_iter)?;
+    let vault_b = next_account_info(account_info_iter)?;
+    let pool_authority = next_account_info(account_info_iter)?;
+    let token_program_a = next_account_info(account_info_iter)?;
+    let borrower_program = next_account_info(account_info_iter)?;
+    let token_program_b = next_account_info(account_info_iter)?;
+    let mint_a = next_account_info(account_info_iter)?;
+    let mint_b = next_account_info(account_info_iter)?;
+    let borrower_accounts: Vec<AccountInfo<'a>> = account_info_iter.cloned().collect();
+
+    assert_signer(caller)?;
+    assert_writable(pool_account)?;
+    assert_writable(recipient_token_a)?;
+    assert_writable(recipient_token_b)?;
+    assert_writable(vault_a)?;
+    assert_writable(vault_b)?;
+    assert_owned_by(pool_account, program_id)?;
+    assert_initialized(pool_account)?;
+
+    if borrower_program.key == program_id || !borrower_program.executable {
+        msg!("Invalid borrower program");
+        return Err(CLMMError::InvalidAccount.into());
+    }
+
+    let pool_data = pool_account.try_borrow_data()?;
+    let mut pool = Pool::deserialize(&mut &pool_data[..])?;
+    drop(pool_data);
+
+    pool.validate_token_accounts(token_program_a.key, token_program_b.key, mint_a.key, mint_b.key)
+        .map_err(|e| {
+            msg!("{}", e);
+            CLMMError::InvalidAccount
+        })?;
+
+    if !pool.unlocked {
+        msg!("Pool is paused");
+        return Err(CLMMError::PoolLocked.into());
+    }
+    if pool.flash_in_progress {
+        msg!("Flash loan in progress");
+        return Err(CLMMError::PoolLocked.into());
+    }
+
+    if pool.liquidity == U256_ZERO {
+        msg!("Pool has no liquidity to earn flash fees");
+        return Err(CLMMError::InsufficientLiquidity.into());
+    }
+
+    // Validate pool authority and vault PDAs
+    let (expected_authority, authority_bump) = derive_pool_authority_address(
+        program_id,
+        pool_account.key,
+    );
+    if pool_authority.key != &expected_authority {
+        msg!("Invalid pool authority");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (expected_vault_a, _) = derive_pool_vault_a_address(program_id, pool_account.key);
+    let (expected_vault_b, _) = derive_pool_vault_b_address(program_id, pool_account.key);
+    if vault_a.key != &expected_vault_a || vault_b.key != &expected_vault_b {
+        msg!("Invalid pool vault");
+        return Err(CLMMError::InvalidAccount.into());
+    }
+
+    let fee_0 = pool.flash_fee(amount_0);
+    let fee_1 = pool.flash_fee(amount_1);
+
+    let balance_0_before = get_token_balance(vault_a)?;
+    let balance_1_before = get_token_balance(vault_b)?;
+
+    // Guard against reentry before any tokens leave the vaults; the pause state is left alone
+    pool.flash_in_progress = true;
+    write_account_data(pool_account, &pool)?;
+
+    let authority_bump_arr = [authority_bump];
+    let authority_seeds = pool_authority_seeds(
+        pool_account.key,
+        &authority_bump_arr,
+    );
+
+    if amount_0 > 0 {
+        msg!("Lending {} of token A", amount_0);
+        token_transfer_signed(
+            token_program_a,
+            vault_a,
+            mint_a,
+            recipient_token_a,
+            pool_authority,
+            amount_0,
+            &authority_seeds,
+        )?;
+    }
+
+    if amount_1 > 0 {
+        msg!("Lending {} of token B", amount_1);
+        token_transfer_signed(
+            token_program_b,
+            vault_b,
+            mint_b,
+            recipient_token_b,
+            pool_authority,
+            amount_1,
+            &authority_seeds,
+        )?;
+    }
+
+    // Hand control to the borrower
+    let callback = FlashCallback {
+        amount_0,
+        amount_1,
+        fee_0,
+        fee_1,
+        data,
+    };
+    let mut callback_data = Vec::new();
+    callback.serialize(&mut callback_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let callback_ix = Instruction {
+        program_id: *borrower_program.key,
+        accounts: borrower_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: callback_data,
+    };
+
+    let mut callback_infos = borrower_accounts;
+    callback_infos.push(borrower_program.clone());
+    invoke(&callback_ix, &callback_infos)?;
+
+    // Principal plus fee must be back in the vaults
+    let balance_0_after = get_token_balance(vault_a)?;
+    let balance_1_after = get_token_balance(vault_b)?;
+
+    let required_0 = balance_0_before.checked_add(fee_0).ok_or(CLMMError::MathOverflow)?;
+    let required_1 = balance_1_before.checked_add(fee_1).ok_or(CLMMError::MathOverflow)?;
+    if balance_0_after < required_0 || balance_1_after < required_1 {
+        msg!("Flash loan not repaid");
+        msg!("  Token A: required {}, got {}", required_0, balance_0_after);
+        msg!("  Token B: required {}, got {}", required_1, balance_1_after);
+        return Err(CLMMError::FlashLoanNotRepaid.into());
+    }
+
+    // Everything paid above the principal is a fee for liquidity providers
+    let paid_0 = balance_0_after - balance_0_before;
+    let paid_1 = balance_1_after - balance_1_before;
+
+    // Reload in case the borrower withdrew liquidity during the loan
+    let pool_data = pool_account.try_borrow_data()?;
+    let mut pool = Pool::deserialize(&mut &pool_data[..])?;
+    drop(pool_data);
+
+    SwapEngine::accrue_fee(&mut pool, U256::from(paid_0), true)?;
+    SwapEngine::accrue_fee(&mut pool, U256::from(paid_1), false)?;
+    pool.flash_in_progress = false;
+
+    write_account_data(pool_account, &pool)?;
+
+    msg!("Flash loan repaid");
+    msg!("  Pool: {}", pool_account.key);
+    msg!("  Token A borrowed: {}, fee paid: {}", amount_0, paid_0);
+    msg!("  Token B borrowed: {}, fee paid: {}", amount_1, paid_1);
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar::Sysvar,
+};
+use borsh::BorshDeserialize;
+use crate::error::CLMMError;
+use crate::state::Oracle;
+use crate::utils::{
+    assert_signer, assert_writable, assert_owned_by, assert_initialized,
+    write_account_data, realloc_account, derive_oracle_address,
+};
+
+/// Grow the number of observations a pool's oracle can hold
+///
+/// Accounts expected:
+/// 0. `[signer, writable]` Payer
+/// 1. `[]` Pool account
+/// 2. `[writable]` Oracle account (PDA)
+/// 3. `[]` System program
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    cardinality_next: u16,
+) -> ProgramResult {
+    msg!("Increasing oracle cardinality...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    // Parse accounts
+    let payer = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+    let oracle_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    assert_signer(payer)?;
+    assert_writable(payer)?;
+    assert_writable(oracle_account)?;
+    assert_owned_by(pool_account, program_id)?;
+    assert_owned_by(oracle_account, program_id)?;
+    assert_initialized(pool_account)?;
+
+    if system_program_account.key != &system_program::ID {
+        msg!("Invalid system program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let (expected_oracle, _oracle_bump) = derive_oracle_address(program_id, pool_account.key);
+    if oracle_account.key != &expected_oracle {
+        msg!("Invalid oracle PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let oracle_data = oracle_account.try_borrow_data()?;
+    let mut oracle = Oracle::deserialize(&mut &oracle_data[..])?;
+    drop(oracle_data);
+
+    if cardinality_next <= oracle.cardinality_next {
+        msg!("Oracle already holds {} observations", oracle.cardinality_next);
+        return Ok(());
+    }
+
+    // A single instruction may only grow an account by MAX_PERMITTED_DATA_INCREASE bytes
+    let new_size = Oracle::space(cardinality_next);
+    if new_size - oracle_account.data_len() > MAX_PERMITTED_DATA_INCREASE {
+        msg!("Cardinality increase too large for one instruction");
+        return Err(CLMMError::InvalidInstruction.into());
+    }
+
+    let rent = Rent::get()?;
+    realloc_account(oracle_account, new_size, payer, system_program_account, &rent)?;
+
+    let previous = oracle.cardinality_next;
+    oracle.grow(cardinality_next);
+    write_account_data(oracle_account, &oracle)?;
+
+    msg!("Oracle cardinality increased");
+    msg!("  Oracle: {}", oracle_account.key);
+    msg!("  Cardinality: {} -> {}", previous, cardinality_next);
+
+    Ok(())
+}
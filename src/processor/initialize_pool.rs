@@ -8,17 +8,19 @@ use solana_program::{
     sysvar::Sysvar,
 };
 use crate::error::CLMMError;
-use crate::state::Pool;
+use crate::math::mev_protection::OracleObservation;
+use crate::state::{Oracle, Pool};
 use crate::utils::{
     create_account, assert_signer,
-    write_account_data, token_initialize_account,
+    write_account_data, token_initialize_account, get_current_timestamp,
+    assert_token_program, assert_supported_mint, vault_account_size,
     derive_pool_address, derive_pool_vault_a_address, derive_pool_vault_b_address,
-    derive_pool_authority_address,
+    derive_pool_authority_address, derive_oracle_address, ORACLE_SEED,
 };
 use crate::math::tick_math::U256;
 
 // System program ID
-solana_program::declare_id!("Fw4mNHEDrHAGg41XEcp7DkHpEP12MiUcCrP2Lj5ngth9");
+use solana_program::system_program::ID;
 
 /// Initialize a new concentrated liquidity pool
 ///
@@ -30,9 +32,14 @@ solana_program::declare_id!("Fw4mNHEDrHAGg41XEcp7DkHpEP12MiUcCrP2Lj5ngth9");
 /// 4. `[writable]` Pool vault for token A (PDA)
 /// 5. `[writable]` Pool vault for token B (PDA)
 /// 6. `[]` Pool authority (PDA)
-/// 7. `[]` Token program
+/// 7. `[]` Token program owning token A's mint (SPL Token or Token-2022)
 /// 8. `[]` System program
 /// 9. `[]` Rent sysvar
+/// 10. `[writable]` Oracle account (PDA)
+/// 11. `[]` Token program owning token B's mint (SPL Token or Token-2022)
+///
+/// Token-2022 mints may carry the transfer-fee extension; vaults are sized for the
+/// account extensions it requires.
 pub fn process(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -52,17 +59,24 @@ pub fn process(
     let vault_a = next_account_info(account_info_iter)?;
     let vault_b = next_account_info(account_info_iter)?;
     let pool_authority = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
+    let token_program_a = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
     let rent_sysvar = next_account_info(account_info_iter)?;
+    let oracle_account = next_account_info(account_info_iter)?;
+    let token_program_b = next_account_info(account_info_iter)?;
 
     // Validate payer is signer
     assert_signer(payer)?;
 
-    // Validate token program
-    if token_program.key.to_bytes() != spl_token::id().to_bytes() {
-        msg!("Invalid token program");
-        return Err(ProgramError::IncorrectProgramId);
+    // Each mint must be owned by the token program supplied for it
+    for (mint, token_program) in [(token_a_mint, token_program_a), (token_b_mint, token_program_b)] {
+        assert_token_program(token_program).inspect_err(|_| msg!("Invalid token program"))?;
+        if mint.owner != token_program.key {
+            msg!("Mint {} is not owned by token program {}", mint.key, token_program.key);
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        assert_supported_mint(&mint.try_borrow_data()?)
+            .inspect_err(|_| msg!("Mint {} uses an unsupported token extension", mint.key))?;
     }
 
     // Validate system program
@@ -71,12 +85,13 @@ pub fn process(
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    // Ensure tokens are sorted (token_a < token_b)
-    let (token_0, token_1) = if token_a_mint.key < token_b_mint.key {
-        (token_a_mint.key, token_b_mint.key)
+    // Ensure tokens are sorted (token_a < token_b), keeping each mint with its token program
+    let (mint_0, token_program_0, mint_1, token_program_1) = if token_a_mint.key < token_b_mint.key {
+        (token_a_mint, token_program_a, token_b_mint, token_program_b)
     } else {
-        (token_b_mint.key, token_a_mint.key)
+        (token_b_mint, token_program_b, token_a_mint, token_program_a)
     };
+    let (token_0, token_1) = (mint_0.key, mint_1.key);
 
     // Validate pool PDA
     let (expected_pool_address, pool_bump) = derive_pool_address(
@@ -124,6 +139,17 @@ pub fn process(
         return Err(ProgramError::InvalidSeeds);
     }
 
+    // Validate oracle PDA
+    let (expected_oracle, oracle_bump) = derive_oracle_address(
+        program_id,
+        pool_account.key,
+    );
+
+    if oracle_account.key != &expected_oracle {
+        msg!("Invalid oracle PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
     // Validate fee tier
     if fee > 10000 {
         msg!("Fee must be <= 10000 basis points (100%)");
@@ -175,21 +201,22 @@ pub fn process(
         &[vault_a_bump],
     ];
 
+    let vault_a_size = vault_account_size(&mint_0.try_borrow_data()?)?;
     create_account(
         payer,
         vault_a,
         system_program,
-        program_id,
+        token_program_0.key,
         &rent,
-        165, // spl_token::state::Account::LEN
+        vault_a_size,
         vault_a_seeds,
     )?;
 
     // Initialize vault A as token account
     token_initialize_account(
-        token_program,
+        token_program_0,
         vault_a,
-        token_a_mint,
+        mint_0,
         pool_authority,
         rent_sysvar,
     )?;
@@ -202,27 +229,28 @@ pub fn process(
         &[vault_b_bump],
     ];
 
+    let vault_b_size = vault_account_size(&mint_1.try_borrow_data()?)?;
     create_account(
         payer,
         vault_b,
         system_program,
-        program_id,
+        token_program_1.key,
         &rent,
-        165, // spl_token::state::Account::LEN
+        vault_b_size,
         vault_b_seeds,
     )?;
 
     // Initialize vault B as token account
     token_initialize_account(
-        token_program,
+        token_program_1,
         vault_b,
-        token_b_mint,
+        mint_1,
         pool_authority,
         rent_sysvar,
     )?;
 
     // Create the pool state
-    let pool = Pool::new(
+    let mut pool = Pool::new(
         *token_0,
         *token_1,
         fee,
@@ -233,25 +261,59 @@ pub fn process(
         CLMMError::InvalidPrice
     })?;
 
+    // The creator administers the pool until it hands the role over
+    pool.config_authority = *payer.key;
+    pool.token_program_a = *token_program_0.key;
+    pool.token_program_b = *token_program_1.key;
+
     // Validate pool
     if !pool.is_valid() {
         msg!("Invalid pool configuration");
         return Err(CLMMError::InvalidAccount.into());
     }
 
+    // Create the oracle with a single slot holding the initial price
+    let oracle_seeds = &[
+        ORACLE_SEED,
+        pool_account.key.as_ref(),
+        &[oracle_bump],
+    ];
+
+    create_account(
+        payer,
+        oracle_account,
+        system_program,
+        program_id,
+        &rent,
+        Oracle::space(1),
+        oracle_seeds,
+    )?;
+
+    let mut oracle = Oracle::new(*pool_account.key);
+    oracle.write(OracleObservation {
+        timestamp: get_current_timestamp()? as u32,
+        price: pool.sqrt_price_x96,
+        tick: pool.tick,
+        liquidity: pool.liquidity,
+    });
+    write_account_data(oracle_account, &oracle)?;
+
     // Write pool data to account
     write_account_data(pool_account, &pool)?;
 
     msg!("Pool initialized successfully");
     msg!("  Token A: {}", token_0);
     msg!("  Token B: {}", token_1);
+    msg!("  Token programs: {}, {}", token_program_0.key, token_program_1.key);
     msg!("  Fee: {} bps", fee);
     msg!("  Tick spacing: {}", tick_spacing);
     msg!("  Initial sqrt price: {}", initial_sqrt_price);
     msg!("  Initial tick: {}", pool.tick);
     msg!("  Pool authority: {}", pool_authority.key);
+    msg!("  Config authority: {}", payer.key);
     msg!("  Vault A: {}", vault_a.key);
     msg!("  Vault B: {}", vault_b.key);
+    msg!("  Oracle: {}", oracle_account.key);
 
     Ok(())
 }
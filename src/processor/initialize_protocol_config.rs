@@ -0,0 +1,73 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+use crate::state::{ProtocolConfig, PROTOCOL_CONFIG_ACCOUNT_SIZE};
+use crate::utils::{
+    assert_signer, assert_writable, assert_uninitialized, assert_upgrade_authority,
+    write_account_data, create_account, derive_protocol_config_address,
+};
+
+/// Create the program-wide protocol config
+///
+/// Only the program's upgrade authority may create the config; it names the protocol
+/// authority that controls protocol fees from then on.
+///
+/// Accounts expected:
+/// 0. `[signer, writable]` Program upgrade authority (payer)
+/// 1. `[writable]` Protocol config account (PDA)
+/// 2. `[]` Program data account
+/// 3. `[]` System program
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    authority: Pubkey,
+) -> ProgramResult {
+    msg!("Initializing protocol config...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    // Parse accounts
+    let upgrade_authority = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let program_data = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    assert_signer(upgrade_authority)?;
+    assert_writable(upgrade_authority)?;
+    assert_writable(config_account)?;
+    assert_uninitialized(config_account)?;
+    assert_upgrade_authority(program_id, program_data, upgrade_authority)?;
+
+    let (expected_config, config_bump) = derive_protocol_config_address(program_id);
+    if config_account.key != &expected_config {
+        msg!("Invalid protocol config PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let rent = Rent::get()?;
+    let config_seeds = &[b"protocol_fee".as_ref(), &[config_bump]];
+
+    create_account(
+        upgrade_authority,
+        config_account,
+        system_program,
+        program_id,
+        &rent,
+        PROTOCOL_CONFIG_ACCOUNT_SIZE,
+        config_seeds,
+    )?;
+
+    let config = ProtocolConfig::new(authority);
+    write_account_data(config_account, &config)?;
+
+    msg!("Protocol config initialized");
+    msg!("  Protocol authority: {}", authority);
+
+    Ok(())
+}
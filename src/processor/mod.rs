@@ -5,16 +5,28 @@ use solana_program::{
     program_error::ProgramError,
     pubkey::Pubkey,
 };
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::math::mev_protection::MevConfig;
+use crate::processor::swap_route::RouteHop;
 
 pub mod swap;
 pub mod initialize_pool;
 pub mod add_liquidity;
 pub mod remove_liquidity;
 pub mod collect_fees;
+pub mod increase_observation_cardinality;
+pub mod initialize_protocol_config;
+pub mod set_protocol_fee;
+pub mod collect_protocol_fees;
+pub mod pool_admin;
+pub mod flash;
+pub mod open_position;
+pub mod close_position;
+pub mod settle_range_order;
+pub mod swap_route;
 
 /// Instructions supported by the CLMM program
-#[derive(BorshDeserialize, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
 pub enum CLMMInstruction {
     /// Initialize a new pool
     ///
@@ -26,9 +38,11 @@ pub enum CLMMInstruction {
     /// 4. `[writable]` Pool vault A (PDA)
     /// 5. `[writable]` Pool vault B (PDA)
     /// 6. `[]` Pool authority (PDA)
-    /// 7. `[]` Token program
+    /// 7. `[]` Token program for token A (SPL Token or Token-2022)
     /// 8. `[]` System program
     /// 9. `[]` Rent sysvar
+    /// 10. `[writable]` Oracle account (PDA)
+    /// 11. `[]` Token program for token B (SPL Token or Token-2022)
     ///
     /// Data:
     /// - fee: u32 (in basis points, e.g., 30 = 0.30%)
@@ -40,81 +54,110 @@ pub enum CLMMInstruction {
         initial_sqrt_price_x96: u128,
     },
 
-    /// Add liquidity to a position
+    /// Add liquidity to an open position
     ///
     /// Accounts expected:
-    /// 0. `[signer]` Position owner
+    /// 0. `[signer]` Position token holder
     /// 1. `[writable]` Pool account
     /// 2. `[writable]` Position account (PDA)
-    /// 3. `[writable]` Tick lower account (PDA)
-    /// 4. `[writable]` Tick upper account (PDA)
-    /// 5. `[writable]` User token A account
-    /// 6. `[writable]` User token B account
-    /// 7. `[writable]` Pool vault A
-    /// 8. `[writable]` Pool vault B
-    /// 9. `[]` Pool authority (PDA)
-    /// 10. `[]` Token program
-    /// 11. `[]` System program
-    /// 12. `[]` Rent sysvar
+    /// 3. `[]` Holder's position token account
+    /// 4. `[writable]` Tick lower account (PDA)
+    /// 5. `[writable]` Tick upper account (PDA)
+    /// 6. `[writable]` User token A account
+    /// 7. `[writable]` User token B account
+    /// 8. `[writable]` Pool vault A
+    /// 9. `[writable]` Pool vault B
+    /// 10. `[]` Pool authority (PDA)
+    /// 11. `[]` Token program for token A
+    /// 12. `[]` System program
+    /// 13. `[]` Rent sysvar
+    /// 14. `[writable]` Tick bitmap account for the lower tick's word (PDA)
+    /// 15. `[writable]` Tick bitmap account for the upper tick's word (PDA, may equal 14)
+    /// 16. `[]` Token program for token B
+    /// 17. `[]` Token A mint
+    /// 18. `[]` Token B mint
     ///
     /// Data:
-    /// - tick_lower: i32
-    /// - tick_upper: i32
     /// - liquidity_delta: u128
     /// - amount_0_max: u64
     /// - amount_1_max: u64
+    /// - native_sol: bool (pay or receive the pool's SOL side as lamports)
+    ///
+    /// With `native_sol`, the user token account for the SOL side is the holder's wrapped
+    /// SOL PDA (`derive_wsol_address`), created and closed within the instruction.
+    ///
+    /// Return data: `add_liquidity::AddLiquidityResult`
     AddLiquidity {
-        tick_lower: i32,
-        tick_upper: i32,
         liquidity_delta: u128,
         amount_0_max: u64,
         amount_1_max: u64,
+        native_sol: bool,
     },
 
     /// Remove liquidity from a position
     ///
     /// Accounts expected:
-    /// 0. `[signer]` Position owner
+    /// 0. `[signer]` Position token holder
     /// 1. `[writable]` Pool account
     /// 2. `[writable]` Position account
-    /// 3. `[writable]` Tick lower account
-    /// 4. `[writable]` Tick upper account
-    /// 5. `[writable]` User token A account
-    /// 6. `[writable]` User token B account
-    /// 7. `[writable]` Pool vault A
-    /// 8. `[writable]` Pool vault B
-    /// 9. `[]` Pool authority (PDA)
-    /// 10. `[]` Token program
+    /// 3. `[]` Holder's position token account
+    /// 4. `[writable]` Tick lower account
+    /// 5. `[writable]` Tick upper account
+    /// 6. `[writable]` User token A account
+    /// 7. `[writable]` User token B account
+    /// 8. `[writable]` Pool vault A
+    /// 9. `[writable]` Pool vault B
+    /// 10. `[]` Pool authority (PDA)
+    /// 11. `[]` Token program for token A
+    /// 12. `[writable]` Tick bitmap account for the lower tick's word (PDA)
+    /// 13. `[writable]` Tick bitmap account for the upper tick's word (PDA, may equal 12)
+    /// 14. `[]` Token program for token B
+    /// 15. `[]` Token A mint
+    /// 16. `[]` Token B mint
+    /// 17. `[]` System program (only with `native_sol`)
+    /// 18. `[]` Rent sysvar (only with `native_sol`)
     ///
     /// Data:
     /// - liquidity_delta: u128
     /// - amount_0_min: u64
     /// - amount_1_min: u64
+    /// - native_sol: bool (pay or receive the pool's SOL side as lamports)
     RemoveLiquidity {
         liquidity_delta: u128,
         amount_0_min: u64,
         amount_1_min: u64,
+        native_sol: bool,
     },
 
     /// Collect fees from a position
     ///
     /// Accounts expected:
-    /// 0. `[signer]` Position owner
+    /// 0. `[signer]` Position token holder
     /// 1. `[writable]` Pool account
     /// 2. `[writable]` Position account
-    /// 3. `[writable]` User token A account
-    /// 4. `[writable]` User token B account
-    /// 5. `[writable]` Pool vault A
-    /// 6. `[writable]` Pool vault B
-    /// 7. `[]` Pool authority (PDA)
-    /// 8. `[]` Token program
+    /// 3. `[]` Holder's position token account
+    /// 4. `[]` Tick lower account (PDA)
+    /// 5. `[]` Tick upper account (PDA)
+    /// 6. `[writable]` User token A account
+    /// 7. `[writable]` User token B account
+    /// 8. `[writable]` Pool vault A
+    /// 9. `[writable]` Pool vault B
+    /// 10. `[]` Pool authority (PDA)
+    /// 11. `[]` Token program for token A
+    /// 12. `[]` Token program for token B
+    /// 13. `[]` Token A mint
+    /// 14. `[]` Token B mint
+    /// 15. `[]` System program (only with `native_sol`)
+    /// 16. `[]` Rent sysvar (only with `native_sol`)
     ///
     /// Data:
     /// - amount_0_requested: u64 (0 = collect all)
     /// - amount_1_requested: u64 (0 = collect all)
+    /// - native_sol: bool (pay or receive the pool's SOL side as lamports)
     CollectFees {
         amount_0_requested: u64,
         amount_1_requested: u64,
+        native_sol: bool,
     },
 
     /// Execute a swap
@@ -127,18 +170,314 @@ pub enum CLMMInstruction {
     /// 4. `[writable]` Pool vault A
     /// 5. `[writable]` Pool vault B
     /// 6. `[]` Pool authority (PDA)
-    /// 7. `[]` Token program
+    /// 7. `[]` Token program for token A
+    /// 8. `[writable]` Oracle account (PDA)
+    /// 9. `[]` Token program for token B
+    /// 10. `[]` Token A mint
+    /// 11. `[]` Token B mint
+    /// 12. `[]` System program (only with `native_sol`)
+    /// 13. `[]` Rent sysvar (only with `native_sol`)
+    /// 14. `[]` Tick bitmap accounts (PDA) for every word the price moves through (zero or more)
+    /// 15. `[writable]` Initialized tick accounts the swap may cross, plus the other boundary tick
+    ///     of any range order those crossings fill (zero or more)
     ///
     /// Data:
     /// - amount_in: u64
     /// - minimum_amount_out: u64
-    /// - sqrt_price_limit: u128
-    /// - zero_for_one: bool
+    /// - sqrt_price_limit: u128 (0 = no limit)
+    /// - zero_for_one: bool (true = token A -> token B)
+    /// - max_price_impact_bps: u32
+    /// - native_sol: bool (pay or receive the pool's SOL side as lamports)
     Swap {
         amount_in: u64,
         minimum_amount_out: u64,
         sqrt_price_limit: u128,
         zero_for_one: bool,
+        max_price_impact_bps: u32,
+        native_sol: bool,
+    },
+
+    /// Execute a swap for an exact output amount
+    ///
+    /// Accounts expected: same as `Swap`
+    ///
+    /// Data:
+    /// - amount_out: u64
+    /// - maximum_amount_in: u64 (fee included)
+    /// - sqrt_price_limit: u128 (0 = no limit)
+    /// - zero_for_one: bool (true = token A -> token B)
+    /// - max_price_impact_bps: u32
+    /// - native_sol: bool (pay or receive the pool's SOL side as lamports)
+    SwapExactOut {
+        amount_out: u64,
+        maximum_amount_in: u64,
+        sqrt_price_limit: u128,
+        zero_for_one: bool,
+        max_price_impact_bps: u32,
+        native_sol: bool,
+    },
+
+    /// Grow the pool oracle's observation ring buffer
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer
+    /// 1. `[]` Pool account
+    /// 2. `[writable]` Oracle account (PDA)
+    /// 3. `[]` System program
+    ///
+    /// Data:
+    /// - cardinality_next: u16 (new number of observation slots)
+    IncreaseObservationCardinality {
+        cardinality_next: u16,
+    },
+
+    /// Create the program-wide protocol config
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Program upgrade authority (payer)
+    /// 1. `[writable]` Protocol config account (PDA)
+    /// 2. `[]` Program data account
+    /// 3. `[]` System program
+    ///
+    /// Data:
+    /// - authority: Pubkey (protocol authority)
+    InitializeProtocolConfig {
+        authority: Pubkey,
+    },
+
+    /// Set the share of a pool's swap fees that goes to the protocol
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Protocol authority
+    /// 1. `[]` Protocol config account (PDA)
+    /// 2. `[writable]` Pool account
+    ///
+    /// Data:
+    /// - protocol_fee_percent: u32 (0 = off, at most `MAX_PROTOCOL_FEE_PERCENT`)
+    SetProtocolFee {
+        protocol_fee_percent: u32,
+    },
+
+    /// Withdraw protocol fees accrued by a pool
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Protocol authority
+    /// 1. `[]` Protocol config account (PDA)
+    /// 2. `[writable]` Pool account
+    /// 3. `[writable]` Recipient token A account
+    /// 4. `[writable]` Recipient token B account
+    /// 5. `[writable]` Pool vault A
+    /// 6. `[writable]` Pool vault B
+    /// 7. `[]` Pool authority (PDA)
+    /// 8. `[]` Token program for token A
+    /// 9. `[]` Token program for token B
+    /// 10. `[]` Token A mint
+    /// 11. `[]` Token B mint
+    ///
+    /// Data:
+    /// - amount_0_requested: u64 (0 = collect all)
+    /// - amount_1_requested: u64 (0 = collect all)
+    CollectProtocolFees {
+        amount_0_requested: u64,
+        amount_1_requested: u64,
+    },
+
+    /// Pause or unpause a pool (paused pools reject swaps and new liquidity)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Config authority
+    /// 1. `[writable]` Pool account
+    ///
+    /// Data:
+    /// - paused: bool
+    SetPoolPaused {
+        paused: bool,
+    },
+
+    /// Enable or disable dynamic fee adjustment
+    ///
+    /// Accounts expected: same as `SetPoolPaused`
+    ///
+    /// Data:
+    /// - enabled: bool (disabling resets the fee to the base fee)
+    SetDynamicFee {
+        enabled: bool,
+    },
+
+    /// Update the dynamic fee bounds and adjustment interval
+    ///
+    /// Accounts expected: same as `SetPoolPaused`
+    ///
+    /// Data:
+    /// - min_fee: u32 (in basis points)
+    /// - max_fee: u32 (in basis points)
+    /// - fee_adjustment_interval: u32 (seconds)
+    SetFeeBounds {
+        min_fee: u32,
+        max_fee: u32,
+        fee_adjustment_interval: u32,
+    },
+
+    /// Replace the pool's MEV protection configuration
+    ///
+    /// Accounts expected: same as `SetPoolPaused`
+    ///
+    /// Data:
+    /// - mev_config: MevConfig
+    SetMevConfig {
+        mev_config: MevConfig,
+    },
+
+    /// Propose a new config authority (first step of a transfer)
+    ///
+    /// Accounts expected: same as `SetPoolPaused`
+    ///
+    /// Data:
+    /// - new_authority: Pubkey (default pubkey cancels a pending transfer)
+    ProposeConfigAuthority {
+        new_authority: Pubkey,
+    },
+
+    /// Accept a proposed config authority transfer (second step)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Pending config authority
+    /// 1. `[writable]` Pool account
+    AcceptConfigAuthority,
+
+    /// Borrow tokens from the pool vaults, repaid with a fee within the same instruction
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Caller
+    /// 1. `[writable]` Pool account
+    /// 2. `[writable]` Recipient token A account
+    /// 3. `[writable]` Recipient token B account
+    /// 4. `[writable]` Pool vault A
+    /// 5. `[writable]` Pool vault B
+    /// 6. `[]` Pool authority (PDA)
+    /// 7. `[]` Token program for token A
+    /// 8. `[]` Borrower program (invoked with `flash::FlashCallback`)
+    /// 9. `[]` Token program for token B
+    /// 10. `[]` Token A mint
+    /// 11. `[]` Token B mint
+    /// 12. `[]` Accounts forwarded to the borrower program (zero or more)
+    ///
+    /// Data:
+    /// - amount_0: u64
+    /// - amount_1: u64
+    /// - data: Vec<u8> (forwarded to the borrower program)
+    Flash {
+        amount_0: u64,
+        amount_1: u64,
+        data: Vec<u8>,
+    },
+
+    /// Open an empty position and mint its one-of-one position token
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Owner (payer)
+    /// 1. `[writable]` Pool account
+    /// 2. `[writable]` Position account (PDA)
+    /// 3. `[signer, writable]` Position mint (new keypair)
+    /// 4. `[writable]` Position token account (PDA)
+    /// 5. `[]` Pool authority (PDA)
+    /// 6. `[]` Token program
+    /// 7. `[]` System program
+    ///
+    /// Data:
+    /// - tick_lower: i32
+    /// - tick_upper: i32
+    OpenPosition {
+        tick_lower: i32,
+        tick_upper: i32,
+    },
+
+    /// Close an empty position and burn its position token
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Position token holder (receives the rent)
+    /// 1. `[writable]` Position account
+    /// 2. `[writable]` Holder's position token account
+    /// 3. `[writable]` Position mint
+    /// 4. `[]` Token program
+    ClosePosition,
+
+    /// Add as much liquidity as the desired amounts allow at the current price
+    ///
+    /// Accounts expected: same as `AddLiquidity`
+    ///
+    /// Data:
+    /// - amount_0_desired: u64
+    /// - amount_1_desired: u64
+    /// - amount_0_min: u64
+    /// - amount_1_min: u64
+    /// - native_sol: bool (pay or receive the pool's SOL side as lamports)
+    ///
+    /// Return data: `add_liquidity::AddLiquidityResult`
+    AddLiquidityByAmounts {
+        amount_0_desired: u64,
+        amount_1_desired: u64,
+        amount_0_min: u64,
+        amount_1_min: u64,
+        native_sol: bool,
+    },
+
+    /// Place a range order on an empty position spanning exactly one tick spacing
+    ///
+    /// A position above the current tick sells token0 and one below it sells token1.
+    /// The order is filled when a swap crosses its far tick in that direction.
+    ///
+    /// Accounts expected: same as `AddLiquidity`
+    ///
+    /// Data:
+    /// - liquidity_delta: u128
+    /// - amount_0_max: u64
+    /// - amount_1_max: u64
+    ///
+    /// Return data: `add_liquidity::AddLiquidityResult`
+    PlaceRangeOrder {
+        liquidity_delta: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+    },
+
+    /// Pay out a filled range order to its position token holder (permissionless)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Caller (position token holder or keeper)
+    /// 1. `[]` Pool account
+    /// 2. `[writable]` Position account
+    /// 3. `[]` Holder's position token account
+    /// 4. `[writable]` Tick lower account (PDA)
+    /// 5. `[writable]` Tick upper account (PDA)
+    /// 6. `[writable]` Holder's token A account
+    /// 7. `[writable]` Holder's token B account
+    /// 8. `[writable]` Pool vault A
+    /// 9. `[writable]` Pool vault B
+    /// 10. `[]` Pool authority (PDA)
+    /// 11. `[]` Token program for token A
+    /// 12. `[writable]` Tick bitmap account for the lower tick's word (PDA)
+    /// 13. `[writable]` Tick bitmap account for the upper tick's word (PDA, may equal 12)
+    /// 14. `[]` Token program for token B
+    /// 15. `[]` Token A mint
+    /// 16. `[]` Token B mint
+    SettleRangeOrder,
+
+    /// Swap an exact input through a path of pools, atomically
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` User account
+    /// 1. Per hop: the accounts listed in `swap_route::process`, followed by the hop's
+    ///    tick bitmap and tick accounts
+    ///
+    /// Data:
+    /// - amount_in: u64 (sent into the first pool)
+    /// - minimum_amount_out: u64 (received from the last pool)
+    /// - hops: Vec<RouteHop> (direction, price impact bound and account counts per pool)
+    SwapRoute {
+        amount_in: u64,
+        minimum_amount_out: u64,
+        hops: Vec<RouteHop>,
     },
 }
 
@@ -175,21 +514,19 @@ pub fn process<'a>(
         }
 
         CLMMInstruction::AddLiquidity {
-            tick_lower,
-            tick_upper,
             liquidity_delta,
             amount_0_max,
             amount_1_max,
+            native_sol,
         } => {
             msg!("Instruction: AddLiquidity");
             add_liquidity::process(
                 program_id,
                 accounts,
-                tick_lower,
-                tick_upper,
                 liquidity_delta,
                 amount_0_max,
                 amount_1_max,
+                native_sol,
             )
         }
 
@@ -197,6 +534,7 @@ pub fn process<'a>(
             liquidity_delta,
             amount_0_min,
             amount_1_min,
+            native_sol,
         } => {
             msg!("Instruction: RemoveLiquidity");
             remove_liquidity::process(
@@ -205,12 +543,14 @@ pub fn process<'a>(
                 liquidity_delta,
                 amount_0_min,
                 amount_1_min,
+                native_sol,
             )
         }
 
         CLMMInstruction::CollectFees {
             amount_0_requested,
             amount_1_requested,
+            native_sol,
         } => {
             msg!("Instruction: CollectFees");
             collect_fees::process(
@@ -218,6 +558,7 @@ pub fn process<'a>(
                 accounts,
                 amount_0_requested,
                 amount_1_requested,
+                native_sol,
             )
         }
 
@@ -225,15 +566,181 @@ pub fn process<'a>(
             amount_in,
             minimum_amount_out,
             sqrt_price_limit,
-            zero_for_one: _zero_for_one,
+            zero_for_one,
+            max_price_impact_bps,
+            native_sol,
         } => {
             msg!("Instruction: Swap");
             swap::process(
+                program_id,
                 accounts,
                 amount_in,
                 minimum_amount_out,
-                sqrt_price_limit,
+                swap::SwapOptions {
+                    sqrt_price_limit,
+                    zero_for_one,
+                    max_price_impact_bps,
+                    native_sol,
+                },
+            )
+        }
+
+        CLMMInstruction::SwapExactOut {
+            amount_out,
+            maximum_amount_in,
+            sqrt_price_limit,
+            zero_for_one,
+            max_price_impact_bps,
+            native_sol,
+        } => {
+            msg!("Instruction: SwapExactOut");
+            swap::process_exact_out(
+                program_id,
+                accounts,
+                amount_out,
+                maximum_amount_in,
+                swap::SwapOptions {
+                    sqrt_price_limit,
+                    zero_for_one,
+                    max_price_impact_bps,
+                    native_sol,
+                },
+            )
+        }
+
+        CLMMInstruction::IncreaseObservationCardinality { cardinality_next } => {
+            msg!("Instruction: IncreaseObservationCardinality");
+            increase_observation_cardinality::process(program_id, accounts, cardinality_next)
+        }
+
+        CLMMInstruction::InitializeProtocolConfig { authority } => {
+            msg!("Instruction: InitializeProtocolConfig");
+            initialize_protocol_config::process(program_id, accounts, authority)
+        }
+
+        CLMMInstruction::SetProtocolFee { protocol_fee_percent } => {
+            msg!("Instruction: SetProtocolFee");
+            set_protocol_fee::process(program_id, accounts, protocol_fee_percent)
+        }
+
+        CLMMInstruction::CollectProtocolFees {
+            amount_0_requested,
+            amount_1_requested,
+        } => {
+            msg!("Instruction: CollectProtocolFees");
+            collect_protocol_fees::process(
+                program_id,
+                accounts,
+                amount_0_requested,
+                amount_1_requested,
+            )
+        }
+
+        CLMMInstruction::SetPoolPaused { paused } => {
+            msg!("Instruction: SetPoolPaused");
+            pool_admin::process_set_paused(program_id, accounts, paused)
+        }
+
+        CLMMInstruction::SetDynamicFee { enabled } => {
+            msg!("Instruction: SetDynamicFee");
+            pool_admin::process_set_dynamic_fee(program_id, accounts, enabled)
+        }
+
+        CLMMInstruction::SetFeeBounds {
+            min_fee,
+            max_fee,
+            fee_adjustment_interval,
+        } => {
+            msg!("Instruction: SetFeeBounds");
+            pool_admin::process_set_fee_bounds(
+                program_id,
+                accounts,
+                min_fee,
+                max_fee,
+                fee_adjustment_interval,
+            )
+        }
+
+        CLMMInstruction::SetMevConfig { mev_config } => {
+            msg!("Instruction: SetMevConfig");
+            pool_admin::process_set_mev_config(program_id, accounts, mev_config)
+        }
+
+        CLMMInstruction::ProposeConfigAuthority { new_authority } => {
+            msg!("Instruction: ProposeConfigAuthority");
+            pool_admin::process_propose_config_authority(program_id, accounts, new_authority)
+        }
+
+        CLMMInstruction::AcceptConfigAuthority => {
+            msg!("Instruction: AcceptConfigAuthority");
+            pool_admin::process_accept_config_authority(program_id, accounts)
+        }
+
+        CLMMInstruction::Flash {
+            amount_0,
+            amount_1,
+            data,
+        } => {
+            msg!("Instruction: Flash");
+            flash::process(program_id, accounts, amount_0, amount_1, data)
+        }
+
+        CLMMInstruction::OpenPosition {
+            tick_lower,
+            tick_upper,
+        } => {
+            msg!("Instruction: OpenPosition");
+            open_position::process(program_id, accounts, tick_lower, tick_upper)
+        }
+
+        CLMMInstruction::ClosePosition => {
+            msg!("Instruction: ClosePosition");
+            close_position::process(program_id, accounts)
+        }
+
+        CLMMInstruction::AddLiquidityByAmounts {
+            amount_0_desired,
+            amount_1_desired,
+            amount_0_min,
+            amount_1_min,
+            native_sol,
+        } => {
+            msg!("Instruction: AddLiquidityByAmounts");
+            add_liquidity::process_by_amounts(
+                program_id,
+                accounts,
+                amount_0_desired,
+                amount_1_desired,
+                amount_0_min,
+                amount_1_min,
+                native_sol,
             )
         }
+        CLMMInstruction::PlaceRangeOrder {
+            liquidity_delta,
+            amount_0_max,
+            amount_1_max,
+        } => {
+            msg!("Instruction: PlaceRangeOrder");
+            add_liquidity::process_range_order(
+                program_id,
+                accounts,
+                liquidity_delta,
+                amount_0_max,
+                amount_1_max,
+            )
+        }
+        CLMMInstruction::SettleRangeOrder => {
+            msg!("Instruction: SettleRangeOrder");
+            settle_range_order::process(program_id, accounts)
+        }
+        CLMMInstruction::SwapRoute {
+            amount_in,
+            minimum_amount_out,
+            hops,
+        } => {
+            msg!("Instruction: SwapRoute");
+            swap_route::process(program_id, accounts, amount_in, minimum_amount_out, hops)
+        }
     }
 }
@@ -0,0 +1,195 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+use borsh::BorshDeserialize;
+use crate::error::CLMMError;
+use crate::state::{Pool, Position};
+use crate::utils::{
+    assert_signer, assert_writable, assert_owned_by, assert_initialized,
+    write_account_data, get_current_timestamp, create_account, create_keypair_account,
+    token_initialize_mint, token_initialize_account_for, token_mint_to,
+    token_revoke_mint_authority, derive_position_address, derive_position_token_address,
+    derive_pool_authority_address, pool_authority_seeds, MINT_ACCOUNT_SIZE, TOKEN_ACCOUNT_SIZE,
+};
+
+/// Open an empty position and mint its one-of-one position token to the owner
+///
+/// Accounts expected:
+/// 0. `[signer, writable]` Owner (payer)
+/// 1. `[writable]` Pool account
+/// 2. `[writable]` Position account (PDA)
+/// 3. `[signer, writable]` Position mint (new keypair)
+/// 4. `[writable]` Position token account (PDA)
+/// 5. `[]` Pool authority (PDA)
+/// 6. `[]` Token program
+/// 7. `[]` System program
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tick_lower: i32,
+    tick_upper: i32,
+) -> ProgramResult {
+    msg!("Opening position...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    // Parse accounts
+    let owner = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+    let position_account = next_account_info(account_info_iter)?;
+    let position_mint = next_account_info(account_info_iter)?;
+    let position_token_account = next_account_info(account_info_iter)?;
+    let pool_authority = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    assert_signer(owner)?;
+    assert_signer(position_mint)?;
+    assert_writable(pool_account)?;
+    assert_writable(position_account)?;
+    assert_writable(position_mint)?;
+    assert_writable(position_token_account)?;
+    assert_owned_by(pool_account, program_id)?;
+    assert_initialized(pool_account)?;
+
+    if token_program.key != &spl_token::id() {
+        msg!("Invalid token program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let pool_data = pool_account.try_borrow_data()?;
+    let mut pool = Pool::deserialize(&mut &pool_data[..])?;
+    drop(pool_data);
+
+    pool.validate_tick_range(tick_lower, tick_upper)
+        .map_err(|e| {
+            msg!("Invalid tick range: {}", e);
+            CLMMError::InvalidTickRange
+        })?;
+
+    // Validate PDAs
+    let (expected_authority, authority_bump) = derive_pool_authority_address(
+        program_id,
+        pool_account.key,
+    );
+    if pool_authority.key != &expected_authority {
+        msg!("Invalid pool authority");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (expected_position, position_bump) = derive_position_address(program_id, position_mint.key);
+    if position_account.key != &expected_position {
+        msg!("Invalid position PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (expected_token_account, token_account_bump) = derive_position_token_address(
+        program_id,
+        position_mint.key,
+    );
+    if position_token_account.key != &expected_token_account {
+        msg!("Invalid position token account PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let rent = Rent::get()?;
+    let current_time = get_current_timestamp()? as u32;
+
+    // Create the position mint, with the pool authority as its only minter
+    create_keypair_account(
+        owner,
+        position_mint,
+        system_program,
+        token_program.key,
+        &rent,
+        MINT_ACCOUNT_SIZE,
+    )?;
+    token_initialize_mint(token_program, position_mint, pool_authority.key, 0)?;
+
+    // Create the owner's token account for the position
+    let token_account_seeds = &[
+        b"position_token",
+        position_mint.key.as_ref(),
+        &[token_account_bump],
+    ];
+
+    create_account(
+        owner,
+        position_token_account,
+        system_program,
+        token_program.key,
+        &rent,
+        TOKEN_ACCOUNT_SIZE,
+        token_account_seeds,
+    )?;
+    token_initialize_account_for(token_program, position_token_account, position_mint, owner.key)?;
+
+    // Mint exactly one token, then fix the supply
+    let authority_bump_arr = [authority_bump];
+    let authority_seeds = pool_authority_seeds(
+        pool_account.key,
+        &authority_bump_arr,
+    );
+
+    token_mint_to(
+        token_program,
+        position_mint,
+        position_token_account,
+        pool_authority,
+        1,
+        &authority_seeds,
+    )?;
+    token_revoke_mint_authority(token_program, position_mint, pool_authority, &authority_seeds)?;
+
+    // Create the position account
+    let position_seeds = &[
+        b"position",
+        position_mint.key.as_ref(),
+        &[position_bump],
+    ];
+
+    let position_size = std::mem::size_of::<Position>() + 8;
+
+    create_account(
+        owner,
+        position_account,
+        system_program,
+        program_id,
+        &rent,
+        position_size,
+        position_seeds,
+    )?;
+
+    let position_id = pool.position_count;
+    pool.position_count += 1;
+
+    let mut position = Position::new(
+        *pool_account.key,
+        *owner.key,
+        tick_lower,
+        tick_upper,
+        position_id,
+        current_time,
+    ).map_err(|e| {
+        msg!("Failed to create position: {}", e);
+        CLMMError::InvalidTickRange
+    })?;
+    position.position_mint = *position_mint.key;
+    position.entry_sqrt_price_x96 = pool.sqrt_price_x96;
+
+    write_account_data(position_account, &position)?;
+    write_account_data(pool_account, &pool)?;
+
+    msg!("Position opened successfully");
+    msg!("  Position: {}", position_account.key);
+    msg!("  Position mint: {}", position_mint.key);
+    msg!("  Tick range: [{}, {}]", tick_lower, tick_upper);
+
+    Ok(())
+}
@@ -0,0 +1,214 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use borsh::BorshDeserialize;
+use crate::error::CLMMError;
+use crate::math::mev_protection::MevConfig;
+use crate::state::Pool;
+use crate::utils::{
+    assert_signer, assert_writable, assert_owned_by, assert_initialized,
+    write_account_data,
+};
+
+/// Pause or unpause a pool
+///
+/// A paused pool rejects swaps and new liquidity; positions can still be
+/// withdrawn and fees collected.
+///
+/// Accounts expected:
+/// 0. `[signer]` Config authority
+/// 1. `[writable]` Pool account
+pub fn process_set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    paused: bool,
+) -> ProgramResult {
+    let (pool_account, mut pool) = load_pool_as_config_authority(program_id, accounts)?;
+
+    pool.unlocked = !paused;
+    write_account_data(pool_account, &pool)?;
+
+    msg!("Pool {}: {}", pool_account.key, if paused { "paused" } else { "unpaused" });
+
+    Ok(())
+}
+
+/// Enable or disable dynamic fee adjustment
+///
+/// Disabling resets the pool fee to its base fee.
+///
+/// Accounts expected:
+/// 0. `[signer]` Config authority
+/// 1. `[writable]` Pool account
+pub fn process_set_dynamic_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    enabled: bool,
+) -> ProgramResult {
+    let (pool_account, mut pool) = load_pool_as_config_authority(program_id, accounts)?;
+
+    pool.dynamic_fee_enabled = enabled;
+    if !enabled {
+        pool.fee = pool.base_fee;
+    }
+    write_account_data(pool_account, &pool)?;
+
+    msg!("Dynamic fee {} for pool {}", if enabled { "enabled" } else { "disabled" }, pool_account.key);
+    msg!("  Fee: {} bps", pool.fee);
+
+    Ok(())
+}
+
+/// Update the bounds and interval used by dynamic fee adjustment
+///
+/// Accounts expected:
+/// 0. `[signer]` Config authority
+/// 1. `[writable]` Pool account
+pub fn process_set_fee_bounds(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    min_fee: u32,
+    max_fee: u32,
+    fee_adjustment_interval: u32,
+) -> ProgramResult {
+    let (pool_account, mut pool) = load_pool_as_config_authority(program_id, accounts)?;
+
+    pool.set_fee_bounds(min_fee, max_fee, fee_adjustment_interval)
+        .map_err(|e| {
+            msg!("Invalid fee bounds: {}", e);
+            CLMMError::InvalidFee
+        })?;
+    write_account_data(pool_account, &pool)?;
+
+    msg!("Fee bounds updated for pool {}", pool_account.key);
+    msg!("  Fee range: [{}, {}] bps", pool.min_fee, pool.max_fee);
+    msg!("  Adjustment interval: {}s", pool.fee_adjustment_interval);
+    msg!("  Fee: {} bps", pool.fee);
+
+    Ok(())
+}
+
+/// Replace the pool's MEV protection configuration
+///
+/// Accounts expected:
+/// 0. `[signer]` Config authority
+/// 1. `[writable]` Pool account
+pub fn process_set_mev_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mev_config: MevConfig,
+) -> ProgramResult {
+    let (pool_account, mut pool) = load_pool_as_config_authority(program_id, accounts)?;
+
+    if mev_config.max_slippage_bps > 10000 {
+        msg!("Max slippage {} bps out of range", mev_config.max_slippage_bps);
+        return Err(CLMMError::InvalidInstruction.into());
+    }
+
+    pool.mev_config = mev_config;
+    write_account_data(pool_account, &pool)?;
+
+    msg!("MEV config updated for pool {}", pool_account.key);
+    msg!("  {:?}", pool.mev_config);
+
+    Ok(())
+}
+
+/// Propose a new config authority; it takes over once it accepts
+///
+/// Proposing the default pubkey cancels a pending transfer.
+///
+/// Accounts expected:
+/// 0. `[signer]` Config authority
+/// 1. `[writable]` Pool account
+pub fn process_propose_config_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Pubkey,
+) -> ProgramResult {
+    let (pool_account, mut pool) = load_pool_as_config_authority(program_id, accounts)?;
+
+    pool.pending_config_authority = new_authority;
+    write_account_data(pool_account, &pool)?;
+
+    msg!("Config authority transfer proposed for pool {}", pool_account.key);
+    msg!("  Pending authority: {}", new_authority);
+
+    Ok(())
+}
+
+/// Accept a proposed config authority transfer
+///
+/// Accounts expected:
+/// 0. `[signer]` Pending config authority
+/// 1. `[writable]` Pool account
+pub fn process_accept_config_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let (authority, pool_account, mut pool) = load_pool(program_id, accounts)?;
+
+    if pool.pending_config_authority == Pubkey::default()
+        || &pool.pending_config_authority != authority.key
+    {
+        msg!("Signer is not the pending config authority");
+        return Err(CLMMError::Unauthorized.into());
+    }
+
+    let previous_authority = pool.config_authority;
+    pool.config_authority = pool.pending_config_authority;
+    pool.pending_config_authority = Pubkey::default();
+    write_account_data(pool_account, &pool)?;
+
+    msg!("Config authority transferred for pool {}", pool_account.key);
+    msg!("  {} -> {}", previous_authority, pool.config_authority);
+
+    Ok(())
+}
+
+/// Load the pool and require the signer to be its config authority
+fn load_pool_as_config_authority<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<(&'a AccountInfo<'b>, Pool), ProgramError> {
+    let (authority, pool_account, pool) = load_pool(program_id, accounts)?;
+
+    if !pool.is_config_authority(authority.key) {
+        msg!("Signer is not the config authority");
+        return Err(CLMMError::Unauthorized.into());
+    }
+
+    Ok((pool_account, pool))
+}
+
+/// Parse the signer and pool accounts shared by all admin instructions
+fn load_pool<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<(&'a AccountInfo<'b>, &'a AccountInfo<'b>, Pool), ProgramError> {
+    let account_info_iter = &mut accounts.iter();
+
+    let authority = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+
+    assert_signer(authority)?;
+    assert_writable(pool_account)?;
+    assert_owned_by(pool_account, program_id)?;
+    assert_initialized(pool_account)?;
+
+    let pool_data = pool_account.try_borrow_data()?;
+    let pool = Pool::deserialize(&mut &pool_data[..])?;
+    drop(pool_data);
+
+    // Settings cannot change under a flash loan that is still out
+    if pool.flash_in_progress {
+        msg!("Flash loan in progress");
+        return Err(CLMMError::PoolLocked.into());
+    }
+
+    Ok((authority, pool_account, pool))
+}
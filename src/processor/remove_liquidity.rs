@@ -7,35 +7,47 @@ use solana_program::{
 };
 use borsh::BorshDeserialize;
 use crate::error::CLMMError;
-use crate::state::{Pool, Position, Tick};
-use crate::math::tick_math::{U256, I256, U256_ZERO, I256_ZERO};
+use crate::state::{Pool, Position, RangeOrder, Tick, TickBitmap};
+use crate::math::tick_math::{U256, I256, U256_ZERO};
 use crate::utils::{
     assert_signer, assert_writable, assert_owned_by, assert_initialized,
-    write_account_data, get_current_timestamp, token_transfer_signed,
-    derive_tick_address, derive_pool_authority_address,
-    pool_authority_seeds,
+    write_account_data, get_current_timestamp, token_transfer_signed, assert_position_holder,
+    derive_tick_address, derive_tick_bitmap_address, derive_pool_authority_address,
+    pool_authority_seeds, wrap_native, unwrap_native, NativeSolAccounts,
 };
 
 /// Remove liquidity from a position
 ///
 /// Accounts expected:
-/// 0. `[signer]` Position owner
+/// 0. `[signer]` Position token holder (writable with `native_sol`)
 /// 1. `[writable]` Pool account
 /// 2. `[writable]` Position account
-/// 3. `[writable]` Tick lower account
-/// 4. `[writable]` Tick upper account
-/// 5. `[writable]` User token A account
-/// 6. `[writable]` User token B account
-/// 7. `[writable]` Pool vault A
-/// 8. `[writable]` Pool vault B
-/// 9. `[]` Pool authority (PDA)
-/// 10. `[]` Token program
+/// 3. `[]` Holder's position token account
+/// 4. `[writable]` Tick lower account
+/// 5. `[writable]` Tick upper account
+/// 6. `[writable]` User token A account
+/// 7. `[writable]` User token B account
+/// 8. `[writable]` Pool vault A
+/// 9. `[writable]` Pool vault B
+/// 10. `[]` Pool authority (PDA)
+/// 11. `[]` Token program for token A
+/// 12. `[writable]` Tick bitmap account for the lower tick's word (PDA)
+/// 13. `[writable]` Tick bitmap account for the upper tick's word (PDA, may equal 12)
+/// 14. `[]` Token program for token B
+/// 15. `[]` Token A mint
+/// 16. `[]` Token B mint
+/// 17. `[]` System program (only with `native_sol`)
+/// 18. `[]` Rent sysvar (only with `native_sol`)
+///
+/// With `native_sol`, the user token account for the pool's SOL side is the holder's
+/// wrapped SOL PDA; it is created for the withdrawal and closed so the holder receives SOL.
 pub fn process(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     liquidity_delta: u128,
     amount_0_min: u64,
     amount_1_min: u64,
+    native_sol: bool,
 ) -> ProgramResult {
     msg!("Removing liquidity from position...");
 
@@ -45,6 +57,7 @@ pub fn process(
     let owner = next_account_info(account_info_iter)?;
     let pool_account = next_account_info(account_info_iter)?;
     let position_account = next_account_info(account_info_iter)?;
+    let position_token_account = next_account_info(account_info_iter)?;
     let tick_lower_account = next_account_info(account_info_iter)?;
     let tick_upper_account = next_account_info(account_info_iter)?;
     let user_token_a = next_account_info(account_info_iter)?;
@@ -52,9 +65,19 @@ pub fn process(
     let vault_a = next_account_info(account_info_iter)?;
     let vault_b = next_account_info(account_info_iter)?;
     let pool_authority = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
+    let token_program_a = next_account_info(account_info_iter)?;
+    let bitmap_lower_account = next_account_info(account_info_iter)?;
+    let bitmap_upper_account = next_account_info(account_info_iter)?;
+    let token_program_b = next_account_info(account_info_iter)?;
+    let mint_a = next_account_info(account_info_iter)?;
+    let mint_b = next_account_info(account_info_iter)?;
+    let native_accounts = if native_sol {
+        Some((next_account_info(account_info_iter)?, next_account_info(account_info_iter)?))
+    } else {
+        None
+    };
 
-    // Validate owner is signer
+    // Validate holder is signer
     assert_signer(owner)?;
 
     // Validate writable accounts
@@ -62,6 +85,8 @@ pub fn process(
     assert_writable(position_account)?;
     assert_writable(tick_lower_account)?;
     assert_writable(tick_upper_account)?;
+    assert_writable(bitmap_lower_account)?;
+    assert_writable(bitmap_upper_account)?;
     assert_writable(user_token_a)?;
     assert_writable(user_token_b)?;
     assert_writable(vault_a)?;
@@ -78,17 +103,29 @@ pub fn process(
     let mut pool = Pool::deserialize(&mut &pool_data[..])?;
     drop(pool_data);
 
+    pool.validate_token_accounts(token_program_a.key, token_program_b.key, mint_a.key, mint_b.key)
+        .map_err(|e| {
+            msg!("{}", e);
+            CLMMError::InvalidAccount
+        })?;
+
     // Deserialize position
     let position_data = position_account.try_borrow_data()?;
     let mut position = Position::deserialize(&mut &position_data[..])?;
     drop(position_data);
 
-    // Validate position owner
-    if &position.owner != owner.key {
-        msg!("Position owner mismatch");
-        return Err(CLMMError::Unauthorized.into());
+    if &position.pool_id != pool_account.key {
+        msg!("Position belongs to a different pool");
+        return Err(CLMMError::InvalidAccount.into());
     }
 
+    // Whoever holds the position token controls the position
+    assert_position_holder(position_token_account, &position.position_mint, owner.key)
+        .map_err(|_| {
+            msg!("Signer does not hold the position token");
+            CLMMError::Unauthorized
+        })?;
+
     // Validate liquidity delta
     if liquidity_delta == 0 {
         msg!("Liquidity delta cannot be zero");
@@ -125,8 +162,8 @@ pub fn process(
         liquidity_u256,
     )?;
 
-    let amount_0_u64 = amount_0.low_u64();
-    let amount_1_u64 = amount_1.low_u64();
+    let amount_0_u64 = u64::try_from(amount_0).map_err(|_| CLMMError::MathOverflow)?;
+    let amount_1_u64 = u64::try_from(amount_1).map_err(|_| CLMMError::MathOverflow)?;
 
     // Validate amounts meet minimums
     if amount_0_u64 < amount_0_min {
@@ -139,43 +176,120 @@ pub fn process(
         return Err(CLMMError::InsufficientLiquidity.into());
     }
 
-    // Calculate fees earned
-    let (fees_0, fees_1) = calculate_fees_earned(&pool, &position)?;
-    let total_amount_0 = amount_0_u64.saturating_add(fees_0.low_u64());
-    let total_amount_1 = amount_1_u64.saturating_add(fees_1.low_u64());
-
-    // Update position liquidity
-    position.liquidity = position.liquidity - liquidity_u256;
-    position.updated_at = current_time;
-
-    // Add fees to tokens owed
-    position.add_tokens_owed(fees_0, fees_1);
-
     // Update ticks
-    update_tick_liquidity(
+    let (mut lower_tick, lower_flipped) = update_tick_liquidity(
         program_id,
         pool_account.key,
         tick_lower_account,
         position.tick_lower,
-        I256::from_dec_str(&liquidity_delta.to_string()).unwrap_or(I256_ZERO),
+        I256::from(liquidity_delta),
         false, // lower tick - subtract liquidity
     )?;
 
-    update_tick_liquidity(
+    let (mut upper_tick, upper_flipped) = update_tick_liquidity(
         program_id,
         pool_account.key,
         tick_upper_account,
         position.tick_upper,
-        I256::from_dec_str(&liquidity_delta.to_string()).unwrap_or(I256_ZERO),
+        I256::from(liquidity_delta),
         true, // upper tick - subtract liquidity
     )?;
 
+    // Unfilled range orders can be cancelled, filled ones are paid out by settlement
+    if position.is_range_order_filled(&lower_tick, &upper_tick) {
+        msg!("Range order has been filled, settle it instead");
+        return Err(CLMMError::RangeOrderFilled.into());
+    }
+    match position.range_order {
+        RangeOrder::SellToken0 => {
+            upper_tick.remove_range_order(liquidity_u256);
+            write_account_data(tick_upper_account, &upper_tick)?;
+        }
+        RangeOrder::SellToken1 => {
+            lower_tick.remove_range_order(liquidity_u256);
+            write_account_data(tick_lower_account, &lower_tick)?;
+        }
+        RangeOrder::None => {}
+    }
+
+    // Fees earned by the liquidity held before removal are settled into tokens owed,
+    // and everything owed is paid out with the principal
+    let (fee_growth_inside_0, fee_growth_inside_1) = Tick::fee_growth_inside(
+        &lower_tick,
+        &upper_tick,
+        pool.tick,
+        pool.fee_growth_global0_x128,
+        pool.fee_growth_global1_x128,
+    );
+    let (fees_0, fees_1) = position.fees_earned(fee_growth_inside_0, fee_growth_inside_1)?;
+    position.add_tokens_owed(fees_0, fees_1);
+    position.update_fee_growth(fee_growth_inside_0, fee_growth_inside_1, current_time);
+
+    let (owed_0, owed_1) = position.collect_tokens_owed(U256::MAX, U256::MAX);
+    let owed_0 = u64::try_from(owed_0).map_err(|_| CLMMError::MathOverflow)?;
+    let owed_1 = u64::try_from(owed_1).map_err(|_| CLMMError::MathOverflow)?;
+    let total_amount_0 = amount_0_u64.checked_add(owed_0).ok_or(CLMMError::MathOverflow)?;
+    let total_amount_1 = amount_1_u64.checked_add(owed_1).ok_or(CLMMError::MathOverflow)?;
+    position.record_withdrawal(amount_0_u64, amount_1_u64);
+    position.record_fees_collected(owed_0, owed_1);
+
+    // Update position liquidity
+    position.liquidity -= liquidity_u256;
+
+    // Ticks left without liquidity are cleared from their bitmap words
+    if lower_flipped {
+        flip_tick_bitmap(
+            program_id,
+            pool_account.key,
+            bitmap_lower_account,
+            position.tick_lower,
+            pool.tick_spacing,
+        )?;
+    }
+
+    if upper_flipped {
+        flip_tick_bitmap(
+            program_id,
+            pool_account.key,
+            bitmap_upper_account,
+            position.tick_upper,
+            pool.tick_spacing,
+        )?;
+    }
+
     // Update pool liquidity if position is in range
     if pool.tick >= position.tick_lower && pool.tick < position.tick_upper {
         pool.liquidity = pool.liquidity - liquidity_u256;
         msg!("Updated pool liquidity: {}", pool.liquidity);
     }
 
+    // The SOL side is paid into a temporary wrapped SOL account and unwrapped below
+    let native_side = match native_accounts {
+        Some((system_program, rent_sysvar)) => {
+            assert_writable(owner)?;
+            let native_is_a = pool.native_mint_is_a().map_err(|e| {
+                msg!("{}", e);
+                CLMMError::InvalidAccount
+            })?;
+            let (wsol_account, native_mint, token_program) = if native_is_a {
+                (user_token_a, mint_a, token_program_a)
+            } else {
+                (user_token_b, mint_b, token_program_b)
+            };
+            let native_side = NativeSolAccounts {
+                owner,
+                wsol_account,
+                native_mint,
+                token_program,
+                system_program,
+                rent_sysvar,
+            };
+            wrap_native(program_id, &native_side, 0)?;
+            Some(native_side)
+        }
+        None => None,
+    };
+
     // Transfer tokens from pool vaults to user (principal + fees)
     let authority_bump_arr = [authority_bump];
     let authority_seeds = pool_authority_seeds(
@@ -185,10 +299,11 @@ pub fn process(
 
     if total_amount_0 > 0 {
         msg!("Transferring {} of token A from pool to user (principal: {}, fees: {})",
-            total_amount_0, amount_0_u64, fees_0.low_u64());
+            total_amount_0, amount_0_u64, owed_0);
         token_transfer_signed(
-            token_program,
+            token_program_a,
             vault_a,
+            mint_a,
             user_token_a,
             pool_authority,
             total_amount_0,
@@ -198,10 +313,11 @@ pub fn process(
 
     if total_amount_1 > 0 {
         msg!("Transferring {} of token B from pool to user (principal: {}, fees: {})",
-            total_amount_1, amount_1_u64, fees_1.low_u64());
+            total_amount_1, amount_1_u64, owed_1);
         token_transfer_signed(
-            token_program,
+            token_program_b,
             vault_b,
+            mint_b,
             user_token_b,
             pool_authority,
             total_amount_1,
@@ -209,8 +325,13 @@ pub fn process(
         )?;
     }
 
+    if let Some(native_side) = native_side {
+        unwrap_native(&native_side)?;
+    }
+
     // Deactivate position if liquidity is zero
     if position.is_empty() {
+        position.range_order = RangeOrder::None;
         position.deactivate(current_time);
         msg!("Position deactivated (empty)");
     }
@@ -222,8 +343,8 @@ pub fn process(
     msg!("Liquidity removed successfully");
     msg!("  Position: {}", position_account.key);
     msg!("  Liquidity removed: {}", liquidity_delta);
-    msg!("  Amount 0 returned: {} (principal) + {} (fees)", amount_0_u64, fees_0.low_u64());
-    msg!("  Amount 1 returned: {} (principal) + {} (fees)", amount_1_u64, fees_1.low_u64());
+    msg!("  Amount 0 returned: {} (principal) + {} (fees)", amount_0_u64, owed_0);
+    msg!("  Amount 1 returned: {} (principal) + {} (fees)", amount_1_u64, owed_1);
     msg!("  Remaining liquidity: {}", position.liquidity);
 
     Ok(())
@@ -250,7 +371,7 @@ fn calculate_amounts_for_liquidity(
             sqrt_price_upper,
             liquidity,
             false, // false for removal
-        );
+        )?;
         (amount_0, U256_ZERO)
     } else if current_sqrt_price < sqrt_price_upper {
         // Price in range - both tokens
@@ -259,13 +380,13 @@ fn calculate_amounts_for_liquidity(
             sqrt_price_upper,
             liquidity,
             false,
-        );
+        )?;
         let amount_1 = FixedPointMath::get_amount1_delta(
             sqrt_price_lower,
             current_sqrt_price,
             liquidity,
             false,
-        );
+        )?;
         (amount_0, amount_1)
     } else {
         // Price above range - only token1
@@ -274,34 +395,15 @@ fn calculate_amounts_for_liquidity(
             sqrt_price_upper,
             liquidity,
             false,
-        );
+        )?;
         (U256_ZERO, amount_1)
     };
 
     Ok((amount_0, amount_1))
 }
 
-/// Calculate fees earned by a position
-fn calculate_fees_earned(
-    pool: &Pool,
-    position: &Position,
-) -> Result<(U256, U256), ProgramError> {
-    // Calculate fee growth inside the position's range
-    let fee_growth_inside_0 = pool.fee_growth_global0_x128;
-    let fee_growth_inside_1 = pool.fee_growth_global1_x128;
-
-    // Calculate fees earned since last update
-    let fee_growth_delta_0 = fee_growth_inside_0 - position.fee_growth_inside0_last_x128;
-    let fee_growth_delta_1 = fee_growth_inside_1 - position.fee_growth_inside1_last_x128;
-
-    // Multiply by liquidity to get fee amounts
-    let fees_0 = (position.liquidity * fee_growth_delta_0) / (U256::from(1u128) << 128);
-    let fees_1 = (position.liquidity * fee_growth_delta_1) / (U256::from(1u128) << 128);
-
-    Ok((fees_0, fees_1))
-}
-
-/// Update tick liquidity (for removal, liquidity_delta should be negative)
+/// Update tick liquidity (for removal, liquidity_delta should be negative).
+/// Returns the tick and whether it flipped between initialized and uninitialized.
 fn update_tick_liquidity(
     program_id: &Pubkey,
     pool_key: &Pubkey,
@@ -309,7 +411,7 @@ fn update_tick_liquidity(
     tick_index: i32,
     liquidity_delta: I256,
     upper: bool,
-) -> ProgramResult {
+) -> Result<(Tick, bool), ProgramError> {
     let (expected_tick, _tick_bump) = derive_tick_address(program_id, pool_key, tick_index);
 
     if tick_account.key != &expected_tick {
@@ -325,11 +427,43 @@ fn update_tick_liquidity(
     drop(tick_data);
 
     // Update tick liquidity (negate delta for removal)
-    let negative_delta = I256_ZERO - liquidity_delta;
-    tick.update_liquidity(negative_delta, upper);
+    let negative_delta = liquidity_delta.checked_neg().ok_or(CLMMError::MathOverflow)?;
+    let flipped = tick.update_liquidity(negative_delta, upper)?;
 
     // Save tick
     write_account_data(tick_account, &tick)?;
 
+    Ok((tick, flipped))
+}
+
+/// Flip a tick's bit in its existing bitmap word
+fn flip_tick_bitmap(
+    program_id: &Pubkey,
+    pool_key: &Pubkey,
+    bitmap_account: &AccountInfo,
+    tick_index: i32,
+    tick_spacing: u32,
+) -> ProgramResult {
+    let (word_position, _bit_position) = TickBitmap::position(tick_index, tick_spacing);
+    let (expected_bitmap, _bitmap_bump) = derive_tick_bitmap_address(program_id, pool_key, word_position);
+
+    if bitmap_account.key != &expected_bitmap {
+        msg!("Invalid tick bitmap PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    assert_owned_by(bitmap_account, program_id)?;
+    assert_initialized(bitmap_account)?;
+
+    // Load bitmap word
+    let bitmap_data = bitmap_account.try_borrow_data()?;
+    let mut tick_bitmap = TickBitmap::deserialize(&mut &bitmap_data[..])?;
+    drop(bitmap_data);
+
+    tick_bitmap.flip_tick(tick_index, tick_spacing);
+
+    // Save bitmap word
+    write_account_data(bitmap_account, &tick_bitmap)?;
+
     Ok(())
 }
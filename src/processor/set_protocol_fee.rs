@@ -0,0 +1,89 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use borsh::BorshDeserialize;
+use crate::error::CLMMError;
+use crate::state::{Pool, ProtocolConfig, MAX_PROTOCOL_FEE_PERCENT};
+use crate::utils::{
+    assert_signer, assert_writable, assert_owned_by, assert_initialized,
+    write_account_data, derive_protocol_config_address,
+};
+
+/// Set the share of a pool's swap fees that goes to the protocol
+///
+/// Accounts expected:
+/// 0. `[signer]` Protocol authority
+/// 1. `[]` Protocol config account (PDA)
+/// 2. `[writable]` Pool account
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    protocol_fee_percent: u32,
+) -> ProgramResult {
+    msg!("Setting protocol fee...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    // Parse accounts
+    let authority = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+
+    assert_signer(authority)?;
+    assert_writable(pool_account)?;
+    assert_owned_by(pool_account, program_id)?;
+    assert_initialized(pool_account)?;
+
+    load_protocol_config(program_id, config_account, authority)?;
+
+    if protocol_fee_percent > MAX_PROTOCOL_FEE_PERCENT {
+        msg!("Protocol fee {}% exceeds maximum {}%", protocol_fee_percent, MAX_PROTOCOL_FEE_PERCENT);
+        return Err(CLMMError::InvalidFee.into());
+    }
+
+    let pool_data = pool_account.try_borrow_data()?;
+    let mut pool = Pool::deserialize(&mut &pool_data[..])?;
+    drop(pool_data);
+
+    let previous_fee_percent = pool.protocol_fee_percent;
+    pool.protocol_fee_percent = protocol_fee_percent;
+
+    write_account_data(pool_account, &pool)?;
+
+    msg!("Protocol fee updated");
+    msg!("  Pool: {}", pool_account.key);
+    msg!("  Protocol fee: {}% -> {}%", previous_fee_percent, protocol_fee_percent);
+
+    Ok(())
+}
+
+/// Load the protocol config and check that `authority` controls it
+pub(crate) fn load_protocol_config(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+    authority: &AccountInfo,
+) -> Result<ProtocolConfig, ProgramError> {
+    assert_owned_by(config_account, program_id)?;
+    assert_initialized(config_account)?;
+
+    let (expected_config, _config_bump) = derive_protocol_config_address(program_id);
+    if config_account.key != &expected_config {
+        msg!("Invalid protocol config PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let config_data = config_account.try_borrow_data()?;
+    let config = ProtocolConfig::deserialize(&mut &config_data[..])?;
+    drop(config_data);
+
+    if !config.is_authority(authority.key) {
+        msg!("Signer is not the protocol authority");
+        return Err(CLMMError::Unauthorized.into());
+    }
+
+    Ok(config)
+}
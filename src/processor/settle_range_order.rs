@@ -0,0 +1,329 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use borsh::BorshDeserialize;
+use crate::error::CLMMError;
+use crate::state::{Pool, Position, RangeOrder, Tick, TickBitmap};
+use crate::math::tick_math::{TickMath, U256, U256_ZERO};
+use crate::math::fixed_point::FixedPointMath;
+use crate::utils::{
+    assert_signer, assert_writable, assert_owned_by, assert_initialized,
+    write_account_data, get_current_timestamp, token_transfer_signed, assert_position_holder,
+    assert_token_mint, assert_token_owner, get_token_owner, derive_tick_address,
+    derive_tick_bitmap_address, derive_pool_authority_address, derive_pool_vault_a_address,
+    derive_pool_vault_b_address, pool_authority_seeds,
+};
+
+/// Pay out a filled range order to the holder of its position token
+///
+/// Anyone may settle a filled order, so keepers can close orders as soon as they fill.
+/// The converted token and the fees earned up to the fill always go to token accounts
+/// owned by the position token holder.
+///
+/// Accounts expected:
+/// 0. `[signer]` Caller (position token holder or keeper)
+/// 1. `[]` Pool account
+/// 2. `[writable]` Position account
+/// 3. `[]` Holder's position token account
+/// 4. `[writable]` Tick lower account (PDA)
+/// 5. `[writable]` Tick upper account (PDA)
+/// 6. `[writable]` Holder's token A account (recipient)
+/// 7. `[writable]` Holder's token B account (recipient)
+/// 8. `[writable]` Pool vault A
+/// 9. `[writable]` Pool vault B
+/// 10. `[]` Pool authority (PDA)
+/// 11. `[]` Token program for token A
+/// 12. `[writable]` Tick bitmap account for the lower tick's word (PDA)
+/// 13. `[writable]` Tick bitmap account for the upper tick's word (PDA, may equal 12)
+/// 14. `[]` Token program for token B
+/// 15. `[]` Token A mint
+/// 16. `[]` Token B mint
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    msg!("Settling range order...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    // Parse accounts
+    let caller = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
+    let position_account = next_account_info(account_info_iter)?;
+    let position_token_account = next_account_info(account_info_iter)?;
+    let tick_lower_account = next_account_info(account_info_iter)?;
+    let tick_upper_account = next_account_info(account_info_iter)?;
+    let recipient_token_a = next_account_info(account_info_iter)?;
+    let recipient_token_b = next_account_info(account_info_iter)?;
+    let vault_a = next_account_info(account_info_iter)?;
+    let vault_b = next_account_info(account_info_iter)?;
+    let pool_authority = next_account_info(account_info_iter)?;
+    let token_program_a = next_account_info(account_info_iter)?;
+    let bitmap_lower_account = next_account_info(account_info_iter)?;
+    let bitmap_upper_account = next_account_info(account_info_iter)?;
+    let token_program_b = next_account_info(account_info_iter)?;
+    let mint_a = next_account_info(account_info_iter)?;
+    let mint_b = next_account_info(account_info_iter)?;
+
+    assert_signer(caller)?;
+    assert_writable(position_account)?;
+    assert_writable(tick_lower_account)?;
+    assert_writable(tick_upper_account)?;
+    assert_writable(bitmap_lower_account)?;
+    assert_writable(bitmap_upper_account)?;
+    assert_writable(recipient_token_a)?;
+    assert_writable(recipient_token_b)?;
+    assert_writable(vault_a)?;
+    assert_writable(vault_b)?;
+    assert_owned_by(pool_account, program_id)?;
+    assert_owned_by(position_account, program_id)?;
+    assert_initialized(pool_account)?;
+    assert_initialized(position_account)?;
+
+    let pool_data = pool_account.try_borrow_data()?;
+    let pool = Pool::deserialize(&mut &pool_data[..])?;
+    drop(pool_data);
+
+    let position_data = position_account.try_borrow_data()?;
+    let mut position = Position::deserialize(&mut &position_data[..])?;
+    drop(position_data);
+
+    pool.validate_token_accounts(token_program_a.key, token_program_b.key, mint_a.key, mint_b.key)
+        .map_err(|e| {
+            msg!("{}", e);
+            CLMMError::InvalidAccount
+        })?;
+
+    if &position.pool_id != pool_account.key {
+        msg!("Position belongs to a different pool");
+        return Err(CLMMError::InvalidAccount.into());
+    }
+
+    if !position.is_range_order() {
+        msg!("Position is not a range order");
+        return Err(CLMMError::InvalidAccount.into());
+    }
+
+    // Proceeds go to whoever holds the position token
+    let holder = get_token_owner(position_token_account)?;
+    assert_position_holder(position_token_account, &position.position_mint, &holder)
+        .map_err(|_| {
+            msg!("Account does not hold the position token");
+            CLMMError::Unauthorized
+        })?;
+
+    assert_token_owner(recipient_token_a, &holder)
+        .and(assert_token_owner(recipient_token_b, &holder))
+        .map_err(|_| {
+            msg!("Recipient token accounts must be owned by the position token holder");
+            CLMMError::Unauthorized
+        })?;
+    assert_token_mint(recipient_token_a, &pool.token_a)
+        .and(assert_token_mint(recipient_token_b, &pool.token_b))
+        .map_err(|_| {
+            msg!("Recipient token accounts do not match the pool's mints");
+            CLMMError::InvalidAccount
+        })?;
+
+    // Validate pool authority and vault PDAs
+    let (expected_authority, authority_bump) = derive_pool_authority_address(
+        program_id,
+        pool_account.key,
+    );
+    if pool_authority.key != &expected_authority {
+        msg!("Invalid pool authority");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (expected_vault_a, _) = derive_pool_vault_a_address(program_id, pool_account.key);
+    let (expected_vault_b, _) = derive_pool_vault_b_address(program_id, pool_account.key);
+    if vault_a.key != &expected_vault_a || vault_b.key != &expected_vault_b {
+        msg!("Invalid pool vault");
+        return Err(CLMMError::InvalidAccount.into());
+    }
+
+    let mut tick_lower = load_tick(program_id, pool_account.key, tick_lower_account, position.tick_lower)?;
+    let mut tick_upper = load_tick(program_id, pool_account.key, tick_upper_account, position.tick_upper)?;
+
+    if !position.is_range_order_filled(&tick_lower, &tick_upper) {
+        msg!("Range order has not been filled yet");
+        return Err(CLMMError::RangeOrderNotFilled.into());
+    }
+
+    let current_time = get_current_timestamp()? as u32;
+    let liquidity = position.liquidity;
+
+    // Fees accrued up to the fill, read from the completion tick's snapshot
+    let completion_tick = match position.range_order {
+        RangeOrder::SellToken0 => &tick_upper,
+        _ => &tick_lower,
+    };
+    let fee_growth_inside_0 = completion_tick.range_order_fee_growth_inside0_x128;
+    let fee_growth_inside_1 = completion_tick.range_order_fee_growth_inside1_x128;
+
+    let (fees_0, fees_1) = position.fees_earned(fee_growth_inside_0, fee_growth_inside_1)?;
+    position.add_tokens_owed(fees_0, fees_1);
+    position.update_fee_growth(fee_growth_inside_0, fee_growth_inside_1, current_time);
+
+    // The whole range has been converted into the other token
+    let sqrt_price_lower = TickMath::get_sqrt_ratio_at_tick(position.tick_lower)?;
+    let sqrt_price_upper = TickMath::get_sqrt_ratio_at_tick(position.tick_upper)?;
+    let (principal_0, principal_1) = match position.range_order {
+        RangeOrder::SellToken0 => (
+            U256_ZERO,
+            FixedPointMath::get_amount1_delta(sqrt_price_lower, sqrt_price_upper, liquidity, false)?,
+        ),
+        _ => (
+            FixedPointMath::get_amount0_delta(sqrt_price_lower, sqrt_price_upper, liquidity, false)?,
+            U256_ZERO,
+        ),
+    };
+
+    let (owed_0, owed_1) = position.collect_tokens_owed(U256::MAX, U256::MAX);
+    let principal_0 = u64::try_from(principal_0).map_err(|_| CLMMError::MathOverflow)?;
+    let principal_1 = u64::try_from(principal_1).map_err(|_| CLMMError::MathOverflow)?;
+    let owed_0 = u64::try_from(owed_0).map_err(|_| CLMMError::MathOverflow)?;
+    let owed_1 = u64::try_from(owed_1).map_err(|_| CLMMError::MathOverflow)?;
+    let total_amount_0 = principal_0.checked_add(owed_0).ok_or(CLMMError::MathOverflow)?;
+    let total_amount_1 = principal_1.checked_add(owed_1).ok_or(CLMMError::MathOverflow)?;
+    position.record_withdrawal(principal_0, principal_1);
+    position.record_fees_collected(owed_0, owed_1);
+
+    // Net liquidity was taken out at the fill, only the gross amounts remain
+    match position.range_order {
+        RangeOrder::SellToken0 => tick_upper.settle_range_order(liquidity),
+        _ => tick_lower.settle_range_order(liquidity),
+    }
+    let lower_flipped = tick_lower.remove_gross_liquidity(liquidity);
+    let upper_flipped = tick_upper.remove_gross_liquidity(liquidity);
+
+    write_account_data(tick_lower_account, &tick_lower)?;
+    write_account_data(tick_upper_account, &tick_upper)?;
+
+    // Ticks left without liquidity are cleared from their bitmap words
+    if lower_flipped {
+        flip_tick_bitmap(
+            program_id,
+            pool_account.key,
+            bitmap_lower_account,
+            position.tick_lower,
+            pool.tick_spacing,
+        )?;
+    }
+
+    if upper_flipped {
+        flip_tick_bitmap(
+            program_id,
+            pool_account.key,
+            bitmap_upper_account,
+            position.tick_upper,
+            pool.tick_spacing,
+        )?;
+    }
+
+    // Transfer proceeds from pool vaults to the holder
+    let authority_bump_arr = [authority_bump];
+    let authority_seeds = pool_authority_seeds(
+        pool_account.key,
+        &authority_bump_arr,
+    );
+
+    if total_amount_0 > 0 {
+        msg!("Transferring {} of token A to holder (principal: {}, fees: {})",
+            total_amount_0, principal_0, owed_0);
+        token_transfer_signed(
+            token_program_a,
+            vault_a,
+            mint_a,
+            recipient_token_a,
+            pool_authority,
+            total_amount_0,
+            &authority_seeds,
+        )?;
+    }
+
+    if total_amount_1 > 0 {
+        msg!("Transferring {} of token B to holder (principal: {}, fees: {})",
+            total_amount_1, principal_1, owed_1);
+        token_transfer_signed(
+            token_program_b,
+            vault_b,
+            mint_b,
+            recipient_token_b,
+            pool_authority,
+            total_amount_1,
+            &authority_seeds,
+        )?;
+    }
+
+    position.liquidity = U256_ZERO;
+    position.range_order = RangeOrder::None;
+    position.deactivate(current_time);
+
+    write_account_data(position_account, &position)?;
+
+    msg!("Range order settled");
+    msg!("  Position: {}", position_account.key);
+    msg!("  Holder: {}", holder);
+    msg!("  Liquidity: {}", liquidity);
+
+    Ok(())
+}
+
+/// Load one of the position's boundary ticks, checking its PDA
+fn load_tick(
+    program_id: &Pubkey,
+    pool_key: &Pubkey,
+    tick_account: &AccountInfo,
+    tick_index: i32,
+) -> Result<Tick, ProgramError> {
+    let (expected_tick, _tick_bump) = derive_tick_address(program_id, pool_key, tick_index);
+
+    if tick_account.key != &expected_tick {
+        msg!("Invalid tick PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    assert_owned_by(tick_account, program_id)?;
+    assert_initialized(tick_account)?;
+
+    let tick_data = tick_account.try_borrow_data()?;
+    let tick = Tick::deserialize(&mut &tick_data[..])?;
+    drop(tick_data);
+
+    Ok(tick)
+}
+
+/// Flip a tick's bit in its existing bitmap word
+fn flip_tick_bitmap(
+    program_id: &Pubkey,
+    pool_key: &Pubkey,
+    bitmap_account: &AccountInfo,
+    tick_index: i32,
+    tick_spacing: u32,
+) -> ProgramResult {
+    let (word_position, _bit_position) = TickBitmap::position(tick_index, tick_spacing);
+    let (expected_bitmap, _bitmap_bump) = derive_tick_bitmap_address(program_id, pool_key, word_position);
+
+    if bitmap_account.key != &expected_bitmap {
+        msg!("Invalid tick bitmap PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    assert_owned_by(bitmap_account, program_id)?;
+    assert_initialized(bitmap_account)?;
+
+    let bitmap_data = bitmap_account.try_borrow_data()?;
+    let mut tick_bitmap = TickBitmap::deserialize(&mut &bitmap_data[..])?;
+    drop(bitmap_data);
+
+    tick_bitmap.flip_tick(tick_index, tick_spacing);
+
+    write_account_data(bitmap_account, &tick_bitmap)?;
+
+    Ok(())
+}
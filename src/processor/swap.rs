@@ -1,254 +1,488 @@
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
-    instruction::{AccountMeta, Instruction},
-    program::invoke,
+    msg,
+    program_error::ProgramError,
     pubkey::Pubkey,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use crate::error::CLMMError;
-use crate::math::SwapEngine;
-use crate::state::Pool;
-use std::collections::VecDeque;
+use crate::math::{SwapContext, SwapEngine};
+use crate::math::tick_math::{U256, U256_ONE, MIN_SQRT_RATIO, MAX_SQRT_RATIO};
+use crate::state::{Oracle, Pool, Tick, TickBitmap, TICK_BITMAP_ACCOUNT_SIZE};
+use crate::utils::{
+    assert_owned_by, assert_signer, assert_writable, assert_token_mint,
+    derive_pool_authority_address, derive_pool_vault_a_address, derive_pool_vault_b_address,
+    derive_oracle_address, derive_tick_address, derive_tick_bitmap_address, get_current_timestamp, pool_authority_seeds,
+    token_transfer_received, token_transfer_signed, write_account_data, get_transfer_fee,
+    wrap_native, unwrap_native, NativeSolAccounts,
+};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 /// Swap processor for handling swap instructions
 pub struct SwapProcessor;
 
 /// Process swap instruction
+///
+/// Accounts expected:
+/// 0. `[signer]` User account (writable with `native_sol`)
+/// 1. `[writable]` Pool account
+/// 2. `[writable]` User token A account
+/// 3. `[writable]` User token B account
+/// 4. `[writable]` Pool vault A
+/// 5. `[writable]` Pool vault B
+/// 6. `[]` Pool authority (PDA)
+/// 7. `[]` Token program for token A
+/// 8. `[writable]` Oracle account (PDA)
+/// 9. `[]` Token program for token B
+/// 10. `[]` Token A mint
+/// 11. `[]` Token B mint
+/// 12. `[]` System program (only with `native_sol`)
+/// 13. `[]` Rent sysvar (only with `native_sol`)
+/// 14. `[]` Tick bitmap accounts (PDA) for every word the price moves through, including
+///     words never created (zero or more)
+/// 15. `[writable]` Initialized tick accounts the swap may cross, plus the other boundary tick
+///     of any range order those crossings fill (zero or more)
+///
+/// With `native_sol`, the user token account for the pool's SOL side is the user's
+/// wrapped SOL PDA. It is created and funded from the user's lamports for the input
+/// bound, and closed after the swap so the output or any unspent input returns as SOL.
+///
+/// Bitmap and tick accounts are told apart by size. The oracle feeds the
+/// engine's TWAP checks and records the post-swap price. A `sqrt_price_limit` of zero
+/// means no limit in the requested direction.
+///
+/// Amounts and thresholds are what the user sends and receives. Token-2022 transfer
+/// fees are taken off before the input reaches the pool and after the output leaves it,
+/// and the input vault is credited with its measured balance change.
 pub fn process<'a>(
+    program_id: &Pubkey,
     accounts: &'a [AccountInfo<'a>],
     amount_in: u64,
     minimum_amount_out: u64,
-    sqrt_price_limit: u128,
+    options: SwapOptions,
+) -> ProgramResult {
+    process_swap(program_id, accounts, amount_in, minimum_amount_out, options, true)
+}
+
+/// Process exact-output swap instruction
+///
+/// Takes the same accounts as `process`. Fails unless exactly `amount_out` is
+/// delivered for at most `maximum_amount_in` of input, fee included.
+pub fn process_exact_out<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    amount_out: u64,
+    maximum_amount_in: u64,
+    options: SwapOptions,
+) -> ProgramResult {
+    process_swap(program_id, accounts, amount_out, maximum_amount_in, options, false)
+}
+
+/// Direction and bounds shared by the exact-input and exact-output swap instructions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapOptions {
+    pub sqrt_price_limit: u128,
+    pub zero_for_one: bool,
+    pub max_price_impact_bps: u32,
+    pub native_sol: bool,
+}
+
+/// Shared swap handler. With `exact_input`, `amount` is the input and `other_amount_threshold`
+/// the minimum output; otherwise `amount` is the output and the threshold the maximum input.
+fn process_swap<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    amount: u64,
+    other_amount_threshold: u64,
+    options: SwapOptions,
+    exact_input: bool,
 ) -> ProgramResult {
+    let SwapOptions { sqrt_price_limit, zero_for_one, max_price_impact_bps, native_sol } = options;
     let account_info_iter = &mut accounts.iter();
-    let pool_account = next_account_info(account_info_iter)?;
     let user_account = next_account_info(account_info_iter)?;
+    let pool_account = next_account_info(account_info_iter)?;
     let user_token_a_account = next_account_info(account_info_iter)?;
     let user_token_b_account = next_account_info(account_info_iter)?;
     let pool_token_a_vault = next_account_info(account_info_iter)?;
     let pool_token_b_vault = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
-
-    // Deserialize pool state
-    let pool_data = &pool_account.data.borrow();
-    let mut pool_slice = pool_data.as_ref();
-    let mut pool = Pool::deserialize(&mut pool_slice)?;
+    let pool_authority = next_account_info(account_info_iter)?;
+    let token_program_a = next_account_info(account_info_iter)?;
+    let oracle_account = next_account_info(account_info_iter)?;
+    let token_program_b = next_account_info(account_info_iter)?;
+    let mint_a = next_account_info(account_info_iter)?;
+    let mint_b = next_account_info(account_info_iter)?;
+    let native_accounts = if native_sol {
+        Some((next_account_info(account_info_iter)?, next_account_info(account_info_iter)?))
+    } else {
+        None
+    };
+    let (bitmap_accounts, tick_accounts): (Vec<&AccountInfo>, Vec<&AccountInfo>) = account_info_iter
+        .partition(|account| account.data_is_empty() || account.data_len() == TICK_BITMAP_ACCOUNT_SIZE);
 
     // Validate accounts
-    if !pool_account.is_writable {
-        return Err(CLMMError::InvalidAccount.into());
-    }
-    if !user_token_a_account.is_writable {
-        return Err(CLMMError::InvalidAccount.into());
-    }
-    if !user_token_b_account.is_writable {
-        return Err(CLMMError::InvalidAccount.into());
-    }
-    if !pool_token_a_vault.is_writable {
-        return Err(CLMMError::InvalidAccount.into());
-    }
-    if !pool_token_b_vault.is_writable {
-        return Err(CLMMError::InvalidAccount.into());
+    assert_signer(user_account)?;
+    assert_writable(pool_account)?;
+    assert_writable(user_token_a_account)?;
+    assert_writable(user_token_b_account)?;
+    assert_writable(pool_token_a_vault)?;
+    assert_writable(pool_token_b_vault)?;
+    assert_writable(oracle_account)?;
+    assert_owned_by(pool_account, program_id)?;
+    assert_owned_by(oracle_account, program_id)?;
+
+    let (expected_oracle, _oracle_bump) = derive_oracle_address(program_id, pool_account.key);
+    if oracle_account.key != &expected_oracle {
+        msg!("Invalid oracle account");
+        return Err(ProgramError::InvalidSeeds);
     }
 
-    // Add proper token account validation
-    SwapProcessor::validate_token_accounts(&pool, pool_account, user_token_a_account, user_token_b_account, pool_token_a_vault, pool_token_b_vault)?;
+    // Deserialize pool state
+    let pool_data = pool_account.try_borrow_data()?;
+    let mut pool = Pool::deserialize(&mut &pool_data[..])?;
+    drop(pool_data);
+
+    pool.validate_token_accounts(token_program_a.key, token_program_b.key, mint_a.key, mint_b.key)
+        .map_err(|e| {
+            msg!("{}", e);
+            CLMMError::InvalidAccount
+        })?;
+
+    // Wrap the user's SOL up to the input bound; whatever is left is unwrapped afterwards
+    let native_side = match native_accounts {
+        Some((system_program, rent_sysvar)) => {
+            assert_writable(user_account)?;
+            let native_is_a = pool.native_mint_is_a().map_err(|e| {
+                msg!("{}", e);
+                CLMMError::InvalidAccount
+            })?;
+            let lamports = if native_is_a != zero_for_one {
+                0
+            } else if exact_input {
+                amount
+            } else {
+                other_amount_threshold
+            };
+            let (wsol_account, native_mint, token_program) = if native_is_a {
+                (user_token_a_account, mint_a, token_program_a)
+            } else {
+                (user_token_b_account, mint_b, token_program_b)
+            };
+            let native_side = NativeSolAccounts {
+                owner: user_account,
+                wsol_account,
+                native_mint,
+                token_program,
+                system_program,
+                rent_sysvar,
+            };
+            wrap_native(program_id, &native_side, lamports)?;
+            Some(native_side)
+        }
+        None => None,
+    };
 
-    // Add signer validation
-    if !user_account.is_signer {
-        return Err(CLMMError::Unauthorized.into());
+    SwapProcessor::validate_token_accounts(
+        program_id,
+        &pool,
+        pool_account,
+        user_token_a_account,
+        user_token_b_account,
+        pool_token_a_vault,
+        pool_token_b_vault,
+    )?;
+
+    let (expected_authority, authority_bump) = derive_pool_authority_address(program_id, pool_account.key);
+    if pool_authority.key != &expected_authority {
+        msg!("Invalid pool authority");
+        return Err(ProgramError::InvalidSeeds);
     }
 
-    let amount_in_u256 = crate::math::tick_math::U256::from(amount_in);
-    let sqrt_price_limit_u256 = crate::math::tick_math::U256::from(sqrt_price_limit);
-    let minimum_amount_out_u256 = crate::math::tick_math::U256::from(minimum_amount_out);
+    // Input flows from the user into the input vault, output from the other vault back out
+    let (user_source, vault_in, vault_out, user_destination) = if zero_for_one {
+        (user_token_a_account, pool_token_a_vault, pool_token_b_vault, user_token_b_account)
+    } else {
+        (user_token_b_account, pool_token_b_vault, pool_token_a_vault, user_token_a_account)
+    };
+    let (token_program_in, mint_in, token_program_out, mint_out) = if zero_for_one {
+        (token_program_a, mint_a, token_program_b, mint_b)
+    } else {
+        (token_program_b, mint_b, token_program_a, mint_a)
+    };
+
+    // The pool only ever sees amounts net of transfer fees
+    let input_fee = get_transfer_fee(mint_in)?;
+    let output_fee = get_transfer_fee(mint_out)?;
+    let pool_amount = if exact_input {
+        amount - input_fee.calculate_fee(amount)
+    } else {
+        output_fee.calculate_pre_fee_amount(amount).ok_or(CLMMError::MathOverflow)?
+    };
 
-    // Determine swap direction (simplified - in real implementation would check token accounts)
-    let zero_for_one = true; // Assume token0 -> token1 for now
+    let amount_u256 = U256::from(pool_amount);
+    let sqrt_price_limit_u256 = if sqrt_price_limit == 0 {
+        // No limit: allow the price to run to the edge of the tick range
+        if zero_for_one {
+            MIN_SQRT_RATIO + U256_ONE
+        } else {
+            MAX_SQRT_RATIO - U256_ONE
+        }
+    } else {
+        U256::from(sqrt_price_limit)
+    };
 
     // Execute the swap with dynamic fee adjustment
     let mut price_history = VecDeque::new();
     let mut volume_history = VecDeque::new();
     let mut impact_history = VecDeque::new();
-    let current_timestamp = 1000; // TODO: Get actual timestamp from instruction context
+    let current_timestamp = get_current_timestamp()? as u32;
+    let sequence_number = pool.last_sequence_number + 1;
 
-    let mut oracle_observations = VecDeque::new();
-    let swap_result = SwapEngine::execute_swap(
+    let oracle_data = oracle_account.try_borrow_data()?;
+    let mut oracle = Oracle::deserialize(&mut &oracle_data[..])?;
+    drop(oracle_data);
+    let mut oracle_observations = oracle.chronological();
+    let start_tick = pool.tick;
+    let tick_bitmaps = SwapProcessor::load_tick_bitmaps(program_id, pool_account.key, &bitmap_accounts)?;
+    let mut ticks = SwapProcessor::load_ticks(program_id, pool_account.key, &tick_accounts)?;
+
+    let execute = if exact_input {
+        SwapEngine::execute_swap
+    } else {
+        SwapEngine::execute_swap_exact_out
+    };
+    let mut ctx = SwapContext {
+        tick_bitmaps: &tick_bitmaps,
+        ticks: &mut ticks,
+        price_history: &mut price_history,
+        volume_history: &mut volume_history,
+        impact_history: &mut impact_history,
+        oracle_observations: &mut oracle_observations,
+        current_timestamp,
+        sequence_number,
+    };
+    let swap_result = execute(
         &mut pool,
-        amount_in_u256,
+        &mut ctx,
+        amount_u256,
         zero_for_one,
         sqrt_price_limit_u256,
-        &user_account.key,
-        &mut price_history,
-        &mut volume_history,
-        &mut impact_history,
-        &mut oracle_observations,
-        current_timestamp,
-        1, // TODO: Get actual sequence number from instruction context
+        user_account.key,
     )?;
 
-    // Validate minimum output
-    if swap_result.amount_out < minimum_amount_out_u256 {
-        return Err(CLMMError::InsufficientLiquidity.into());
+    let pool_amount_in = u64::try_from(swap_result.amount_in).map_err(|_| CLMMError::MathOverflow)?;
+    let pool_amount_out = u64::try_from(swap_result.amount_out).map_err(|_| CLMMError::MathOverflow)?;
+
+    // What the user sends for the input the pool consumed
+    let user_amount_in = if exact_input && pool_amount_in == pool_amount {
+        amount
+    } else {
+        input_fee.calculate_pre_fee_amount(pool_amount_in).ok_or(CLMMError::MathOverflow)?
+    };
+
+    if exact_input {
+        // Validate minimum output, as received by the user
+        let user_amount_out = swap_result.amount_out - U256::from(output_fee.calculate_fee(pool_amount_out));
+        if user_amount_out < U256::from(other_amount_threshold) {
+            return Err(CLMMError::InsufficientLiquidity.into());
+        }
+    } else {
+        // The full output must be delivered within the input bound
+        if swap_result.amount_out < amount_u256 {
+            return Err(CLMMError::InsufficientLiquidity.into());
+        }
+        if user_amount_in > other_amount_threshold {
+            return Err(CLMMError::SlippageExceeded.into());
+        }
     }
 
-    // Validate price impact (optional - could be a parameter)
-    let max_price_impact = 100; // 1% max impact
-    if swap_result.price_impact > max_price_impact {
+    // Words the caller left out were treated as empty, so they must really be empty
+    SwapProcessor::assert_bitmap_coverage(
+        program_id,
+        pool_account.key,
+        pool.tick_spacing,
+        start_tick,
+        pool.tick,
+        &bitmap_accounts,
+    )?;
+
+    // Validate price impact against the caller's bound
+    if swap_result.price_impact > max_price_impact_bps {
+        msg!("Price impact {} bps exceeds maximum {} bps", swap_result.price_impact, max_price_impact_bps);
         return Err(CLMMError::InvalidPrice.into());
     }
 
+    // Record the post-swap observation in the oracle ring buffer
+    if let Some(observation) = oracle_observations.back() {
+        oracle.write(observation.clone());
+    }
+    pool.last_oracle_update = current_timestamp;
+    pool.oracle_observation_count = oracle.observation_count as u32;
+    write_account_data(oracle_account, &oracle)?;
+
     // Update pool account data
     pool.serialize(&mut &mut pool_account.data.borrow_mut()[..])?;
 
-    // Transfer tokens between accounts
-    SwapProcessor::transfer_tokens(
-        token_program,
+    // Persist crossed ticks
+    for tick_account in &tick_accounts {
+        let tick_data = tick_account.try_borrow_data()?;
+        let tick_index = Tick::deserialize(&mut &tick_data[..])?.tick;
+        drop(tick_data);
+
+        if let Some(tick) = ticks.get(&tick_index) {
+            write_account_data(tick_account, tick)?;
+        }
+    }
+
+    let amount_received = token_transfer_received(
+        token_program_in,
+        user_source,
+        mint_in,
+        vault_in,
         user_account,
-        user_token_a_account,
-        pool_token_a_vault,
-        user_token_b_account,
-        pool_token_b_vault,
-        swap_result.amount_in.low_u128() as u64,
-        swap_result.amount_out.low_u128() as u64,
-        zero_for_one,
+        user_amount_in,
+    )?;
+    if amount_received < pool_amount_in {
+        msg!("Input vault received {}, swap requires {}", amount_received, pool_amount_in);
+        return Err(CLMMError::InsufficientAmountReceived.into());
+    }
+
+    let authority_bump_arr = [authority_bump];
+    let authority_seeds = pool_authority_seeds(pool_account.key, &authority_bump_arr);
+    token_transfer_signed(
+        token_program_out,
+        vault_out,
+        mint_out,
+        user_destination,
+        pool_authority,
+        pool_amount_out,
+        &authority_seeds,
     )?;
 
+    if let Some(native_side) = native_side {
+        unwrap_native(&native_side)?;
+    }
+
+    msg!("Swap completed");
+    msg!("  Direction: {}", if zero_for_one { "A -> B" } else { "B -> A" });
+    msg!("  Amount in: {} (sent {}, received {})", swap_result.amount_in, user_amount_in, amount_received);
+    msg!("  Amount out: {}", swap_result.amount_out);
+
     Ok(())
 }
 
 impl SwapProcessor {
-    /// Validate token accounts for swap operation
-    fn validate_token_accounts(
-        _pool: &Pool,
-        _pool_account: &AccountInfo,
-        user_token_a_account: &AccountInfo,
-        user_token_b_account: &AccountInfo,
-        pool_token_a_vault: &AccountInfo,
-        pool_token_b_vault: &AccountInfo,
-    ) -> ProgramResult {
-        // Validate that user token accounts are owned by the token program
-        if user_token_a_account.owner.to_bytes() != spl_token::id().to_bytes() {
-            return Err(CLMMError::InvalidAccount.into());
-        }
-        if user_token_b_account.owner.to_bytes() != spl_token::id().to_bytes() {
-            return Err(CLMMError::InvalidAccount.into());
-        }
-        if pool_token_a_vault.owner.to_bytes() != spl_token::id().to_bytes() {
-            return Err(CLMMError::InvalidAccount.into());
-        }
-        if pool_token_b_vault.owner.to_bytes() != spl_token::id().to_bytes() {
-            return Err(CLMMError::InvalidAccount.into());
-        }
+    /// Load the tick bitmap words supplied to a swap, keyed by word position.
+    /// Empty accounts stand for words that were never created and are skipped.
+    pub(crate) fn load_tick_bitmaps(
+        program_id: &Pubkey,
+        pool_key: &Pubkey,
+        bitmap_accounts: &[&AccountInfo],
+    ) -> Result<BTreeMap<i16, TickBitmap>, ProgramError> {
+        let mut tick_bitmaps = BTreeMap::new();
 
-        // Basic validation - in a real implementation, you'd validate token accounts properly
-        // For now, just ensure the accounts are different and properly sized
-        if user_token_a_account.key == user_token_b_account.key {
-            return Err(CLMMError::InvalidAccount.into());
-        }
-        if pool_token_a_vault.key == pool_token_b_vault.key {
-            return Err(CLMMError::InvalidAccount.into());
+        for bitmap_account in bitmap_accounts {
+            if bitmap_account.data_is_empty() {
+                continue;
+            }
+            assert_owned_by(bitmap_account, program_id)?;
+
+            let bitmap_data = bitmap_account.try_borrow_data()?;
+            let tick_bitmap = TickBitmap::deserialize(&mut &bitmap_data[..])?;
+            drop(bitmap_data);
+
+            let (expected_bitmap, _bitmap_bump) =
+                derive_tick_bitmap_address(program_id, pool_key, tick_bitmap.word_position);
+            if bitmap_account.key != &expected_bitmap {
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            tick_bitmaps.insert(tick_bitmap.word_position, tick_bitmap);
         }
 
-        // Additional validation would require proper token program integration
-        // This is a simplified version for compilation purposes
+        Ok(tick_bitmaps)
+    }
 
-        Ok(())
+    /// Load the tick accounts supplied to a swap, keyed by tick index
+    pub(crate) fn load_ticks(
+        program_id: &Pubkey,
+        pool_key: &Pubkey,
+        tick_accounts: &[&AccountInfo],
+    ) -> Result<HashMap<i32, Tick>, ProgramError> {
+        let mut ticks = HashMap::new();
+
+        for tick_account in tick_accounts {
+            assert_owned_by(tick_account, program_id)?;
+
+            let tick_data = tick_account.try_borrow_data()?;
+            let tick = Tick::deserialize(&mut &tick_data[..])?;
+            drop(tick_data);
+
+            let (expected_tick, _tick_bump) = derive_tick_address(program_id, pool_key, tick.tick);
+            if tick_account.key != &expected_tick {
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            ticks.insert(tick.tick, tick);
+        }
+
+        Ok(ticks)
     }
 
-    /// Transfer tokens between accounts for swap operation
-    fn transfer_tokens<'a>(
-        token_program: &'a AccountInfo<'a>,
-        authority: &'a AccountInfo<'a>,
-        user_token_a_account: &'a AccountInfo<'a>,
-        pool_token_a_vault: &'a AccountInfo<'a>,
-        user_token_b_account: &'a AccountInfo<'a>,
-        pool_token_b_vault: &'a AccountInfo<'a>,
-        amount_in: u64,
-        amount_out: u64,
-        zero_for_one: bool,
+    /// Ensure a bitmap account was supplied for every word between the start and end tick
+    pub(crate) fn assert_bitmap_coverage(
+        program_id: &Pubkey,
+        pool_key: &Pubkey,
+        tick_spacing: u32,
+        start_tick: i32,
+        end_tick: i32,
+        bitmap_accounts: &[&AccountInfo],
     ) -> ProgramResult {
-        if zero_for_one {
-            // Transfer token0 from user to pool vault
-            SwapProcessor::token_transfer_cpi(
-                token_program,
-                user_token_a_account,
-                pool_token_a_vault,
-                authority,
-                amount_in,
-            )?;
-
-            // Transfer token1 from pool vault to user
-            SwapProcessor::token_transfer_cpi(
-                token_program,
-                pool_token_b_vault,
-                user_token_b_account,
-                authority,
-                amount_out,
-            )?;
-        } else {
-            // Transfer token1 from user to pool vault
-            SwapProcessor::token_transfer_cpi(
-                token_program,
-                user_token_b_account,
-                pool_token_b_vault,
-                authority,
-                amount_in,
-            )?;
-
-            // Transfer token0 from pool vault to user
-            SwapProcessor::token_transfer_cpi(
-                token_program,
-                pool_token_a_vault,
-                user_token_a_account,
-                authority,
-                amount_out,
-            )?;
+        let (start_word, _) = TickBitmap::position(start_tick, tick_spacing);
+        let (end_word, _) = TickBitmap::position(end_tick, tick_spacing);
+
+        for word_position in start_word.min(end_word)..=start_word.max(end_word) {
+            let (expected_bitmap, _bitmap_bump) =
+                derive_tick_bitmap_address(program_id, pool_key, word_position);
+            if !bitmap_accounts.iter().any(|account| account.key == &expected_bitmap) {
+                msg!("Missing tick bitmap account for word {}", word_position);
+                return Err(CLMMError::InvalidAccount.into());
+            }
         }
 
         Ok(())
     }
 
-    /// Execute token transfer CPI call
-    fn token_transfer_cpi<'a>(
-        token_program: &'a AccountInfo<'a>,
-        from: &'a AccountInfo<'a>,
-        to: &'a AccountInfo<'a>,
-        authority: &'a AccountInfo<'a>,
-        amount: u64,
+    /// Validate token accounts for swap operation
+    ///
+    /// The vaults must be the pool's PDA vaults and each user account must hold the
+    /// mint of the vault it is paired with, so either direction maps user A to vault A
+    /// and user B to vault B.
+    pub(crate) fn validate_token_accounts(
+        program_id: &Pubkey,
+        pool: &Pool,
+        pool_account: &AccountInfo,
+        user_token_a_account: &AccountInfo,
+        user_token_b_account: &AccountInfo,
+        pool_token_a_vault: &AccountInfo,
+        pool_token_b_vault: &AccountInfo,
     ) -> ProgramResult {
-        // SPL Token transfer instruction discriminator
-        const TOKEN_IX_TRANSFER: u8 = 3;
-
-        // Helper function to get the SPL Token program ID as our Pubkey type
-        fn token_program_id() -> Pubkey {
-            Pubkey::new_from_array(spl_token::id().to_bytes())
+        let (expected_vault_a, _) = derive_pool_vault_a_address(program_id, pool_account.key);
+        if pool_token_a_vault.key != &expected_vault_a {
+            msg!("Invalid vault A");
+            return Err(CLMMError::InvalidAccount.into());
+        }
+        let (expected_vault_b, _) = derive_pool_vault_b_address(program_id, pool_account.key);
+        if pool_token_b_vault.key != &expected_vault_b {
+            msg!("Invalid vault B");
+            return Err(CLMMError::InvalidAccount.into());
         }
 
-        let mut data = Vec::with_capacity(9);
-        data.push(TOKEN_IX_TRANSFER);
-        data.extend_from_slice(&amount.to_le_bytes());
-
-        let ix = Instruction {
-            program_id: token_program_id(),
-            accounts: vec![
-                AccountMeta::new(*from.key, false),
-                AccountMeta::new(*to.key, false),
-                AccountMeta::new_readonly(*authority.key, true),
-            ],
-            data,
-        };
-
-        invoke(
-            &ix,
-            &[
-                from.clone(),
-                to.clone(),
-                authority.clone(),
-                token_program.clone(),
-            ],
-        )
+        assert_token_mint(user_token_a_account, &pool.token_a)?;
+        assert_token_mint(user_token_b_account, &pool.token_b)?;
+        assert_token_mint(pool_token_a_vault, &pool.token_a)?;
+        assert_token_mint(pool_token_b_vault, &pool.token_b)?;
+
+        Ok(())
     }
 }
-
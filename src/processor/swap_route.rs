@@ -0,0 +1,349 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::error::CLMMError;
+use crate::math::{SwapContext, SwapEngine};
+use crate::math::tick_math::{U256, U256_ONE, MIN_SQRT_RATIO, MAX_SQRT_RATIO};
+use crate::processor::swap::SwapProcessor;
+use crate::state::{Oracle, Pool, Tick};
+use crate::utils::{
+    assert_owned_by, assert_signer, assert_writable, derive_oracle_address,
+    derive_pool_authority_address, get_current_timestamp, get_token_balance, get_transfer_fee,
+    pool_authority_seeds, token_transfer_received, token_transfer_signed, write_account_data,
+};
+use std::collections::VecDeque;
+
+/// Maximum number of pools a single route may pass through
+pub const MAX_ROUTE_HOPS: usize = 4;
+
+/// Number of accounts every hop of a route starts with
+pub const ROUTE_HOP_ACCOUNTS: usize = 11;
+
+/// One pool of a `SwapRoute`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct RouteHop {
+    /// Direction through this pool (true = token A -> token B)
+    pub zero_for_one: bool,
+    /// Price impact bound for this pool, in basis points
+    pub max_price_impact_bps: u32,
+    /// Number of tick bitmap accounts following the hop's fixed accounts
+    pub bitmap_count: u8,
+    /// Number of tick accounts following the bitmap accounts
+    pub tick_count: u8,
+}
+
+/// Swap through a path of pools, feeding each hop's output into the next
+///
+/// Accounts expected:
+/// 0. `[signer]` User account
+/// 1. Per hop, in route order:
+///    0. `[writable]` Pool account
+///    1. `[writable]` User token A account
+///    2. `[writable]` User token B account
+///    3. `[writable]` Pool vault A
+///    4. `[writable]` Pool vault B
+///    5. `[]` Pool authority (PDA)
+///    6. `[writable]` Oracle account (PDA)
+///    7. `[]` Token program for token A
+///    8. `[]` Token program for token B
+///    9. `[]` Token A mint
+///    10. `[]` Token B mint
+///    11. `[]` `bitmap_count` tick bitmap accounts (PDA), as for `Swap`
+///    12. `[writable]` `tick_count` tick accounts, as for `Swap`
+///
+/// The account a hop pays out to must be the account the next hop pays from, and the
+/// next hop spends exactly what that account received. The whole route fails unless
+/// the last hop delivers at least `minimum_amount_out`; intermediate hops have no
+/// price limit beyond their `max_price_impact_bps`.
+pub fn process<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    amount_in: u64,
+    minimum_amount_out: u64,
+    hops: Vec<RouteHop>,
+) -> ProgramResult {
+    msg!("Processing routed swap...");
+
+    if hops.is_empty() || hops.len() > MAX_ROUTE_HOPS {
+        msg!("Route must have between 1 and {} hops, got {}", MAX_ROUTE_HOPS, hops.len());
+        return Err(CLMMError::InvalidInstruction.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+    let user_account = next_account_info(account_info_iter)?;
+    assert_signer(user_account)?;
+
+    let mut amount = amount_in;
+    let mut previous_destination: Option<&Pubkey> = None;
+
+    for (index, hop) in hops.iter().enumerate() {
+        let hop_accounts = HopAccounts {
+            pool_account: next_account_info(account_info_iter)?,
+            user_token_a_account: next_account_info(account_info_iter)?,
+            user_token_b_account: next_account_info(account_info_iter)?,
+            pool_token_a_vault: next_account_info(account_info_iter)?,
+            pool_token_b_vault: next_account_info(account_info_iter)?,
+            pool_authority: next_account_info(account_info_iter)?,
+            oracle_account: next_account_info(account_info_iter)?,
+            token_program_a: next_account_info(account_info_iter)?,
+            token_program_b: next_account_info(account_info_iter)?,
+            mint_a: next_account_info(account_info_iter)?,
+            mint_b: next_account_info(account_info_iter)?,
+            bitmap_accounts: (0..hop.bitmap_count)
+                .map(|_| next_account_info(account_info_iter))
+                .collect::<Result<Vec<_>, _>>()?,
+            tick_accounts: (0..hop.tick_count)
+                .map(|_| next_account_info(account_info_iter))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
+        let (user_source, user_destination) = if hop.zero_for_one {
+            (hop_accounts.user_token_a_account, hop_accounts.user_token_b_account)
+        } else {
+            (hop_accounts.user_token_b_account, hop_accounts.user_token_a_account)
+        };
+
+        // Each hop must spend the output of the one before it
+        if let Some(previous) = previous_destination {
+            if user_source.key != previous {
+                msg!("Hop {} does not pay from the previous hop's output account", index);
+                return Err(CLMMError::InvalidAccount.into());
+            }
+        }
+
+        amount = execute_hop(program_id, user_account, &hop_accounts, hop, amount)?;
+        previous_destination = Some(user_destination.key);
+
+        msg!("  Hop {}: pool {} -> {} out", index, hop_accounts.pool_account.key, amount);
+    }
+
+    if amount < minimum_amount_out {
+        msg!("Route delivered {}, minimum is {}", amount, minimum_amount_out);
+        return Err(CLMMError::SlippageExceeded.into());
+    }
+
+    msg!("Routed swap completed");
+    msg!("  Hops: {}", hops.len());
+    msg!("  Amount in: {}", amount_in);
+    msg!("  Amount out: {}", amount);
+
+    Ok(())
+}
+
+/// Accounts of one hop, in the order they follow the user account
+struct HopAccounts<'a, 'b> {
+    pool_account: &'b AccountInfo<'a>,
+    user_token_a_account: &'b AccountInfo<'a>,
+    user_token_b_account: &'b AccountInfo<'a>,
+    pool_token_a_vault: &'b AccountInfo<'a>,
+    pool_token_b_vault: &'b AccountInfo<'a>,
+    pool_authority: &'b AccountInfo<'a>,
+    oracle_account: &'b AccountInfo<'a>,
+    token_program_a: &'b AccountInfo<'a>,
+    token_program_b: &'b AccountInfo<'a>,
+    mint_a: &'b AccountInfo<'a>,
+    mint_b: &'b AccountInfo<'a>,
+    bitmap_accounts: Vec<&'b AccountInfo<'a>>,
+    tick_accounts: Vec<&'b AccountInfo<'a>>,
+}
+
+/// Swap `amount` through one pool of the route and return what the user's output
+/// account received
+fn execute_hop<'a>(
+    program_id: &Pubkey,
+    user_account: &AccountInfo<'a>,
+    accounts: &HopAccounts<'a, '_>,
+    hop: &RouteHop,
+    amount: u64,
+) -> Result<u64, ProgramError> {
+    let HopAccounts {
+        pool_account,
+        user_token_a_account,
+        user_token_b_account,
+        pool_token_a_vault,
+        pool_token_b_vault,
+        pool_authority,
+        oracle_account,
+        token_program_a,
+        token_program_b,
+        mint_a,
+        mint_b,
+        ref bitmap_accounts,
+        ref tick_accounts,
+    } = *accounts;
+    assert_writable(pool_account)?;
+    assert_writable(user_token_a_account)?;
+    assert_writable(user_token_b_account)?;
+    assert_writable(pool_token_a_vault)?;
+    assert_writable(pool_token_b_vault)?;
+    assert_writable(oracle_account)?;
+    assert_owned_by(pool_account, program_id)?;
+    assert_owned_by(oracle_account, program_id)?;
+
+    let (expected_oracle, _oracle_bump) = derive_oracle_address(program_id, pool_account.key);
+    if oracle_account.key != &expected_oracle {
+        msg!("Invalid oracle account");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let pool_data = pool_account.try_borrow_data()?;
+    let mut pool = Pool::deserialize(&mut &pool_data[..])?;
+    drop(pool_data);
+
+    pool.validate_token_accounts(token_program_a.key, token_program_b.key, mint_a.key, mint_b.key)
+        .map_err(|e| {
+            msg!("{}", e);
+            CLMMError::InvalidAccount
+        })?;
+
+    SwapProcessor::validate_token_accounts(
+        program_id,
+        &pool,
+        pool_account,
+        user_token_a_account,
+        user_token_b_account,
+        pool_token_a_vault,
+        pool_token_b_vault,
+    )?;
+
+    let (expected_authority, authority_bump) = derive_pool_authority_address(program_id, pool_account.key);
+    if pool_authority.key != &expected_authority {
+        msg!("Invalid pool authority");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let zero_for_one = hop.zero_for_one;
+    let (user_source, vault_in, vault_out, user_destination) = if zero_for_one {
+        (user_token_a_account, pool_token_a_vault, pool_token_b_vault, user_token_b_account)
+    } else {
+        (user_token_b_account, pool_token_b_vault, pool_token_a_vault, user_token_a_account)
+    };
+    let (token_program_in, mint_in, token_program_out, mint_out) = if zero_for_one {
+        (token_program_a, mint_a, token_program_b, mint_b)
+    } else {
+        (token_program_b, mint_b, token_program_a, mint_a)
+    };
+
+    // The pool only ever sees amounts net of transfer fees
+    let input_fee = get_transfer_fee(mint_in)?;
+    let pool_amount = amount - input_fee.calculate_fee(amount);
+
+    // Intermediate hops take whatever price the pool offers; the route minimum guards the result
+    let sqrt_price_limit = if zero_for_one {
+        MIN_SQRT_RATIO + U256_ONE
+    } else {
+        MAX_SQRT_RATIO - U256_ONE
+    };
+
+    let mut price_history = VecDeque::new();
+    let mut volume_history = VecDeque::new();
+    let mut impact_history = VecDeque::new();
+    let current_timestamp = get_current_timestamp()? as u32;
+    let sequence_number = pool.last_sequence_number + 1;
+
+    let oracle_data = oracle_account.try_borrow_data()?;
+    let mut oracle = Oracle::deserialize(&mut &oracle_data[..])?;
+    drop(oracle_data);
+    let mut oracle_observations = oracle.chronological();
+    let start_tick = pool.tick;
+    let tick_bitmaps = SwapProcessor::load_tick_bitmaps(program_id, pool_account.key, bitmap_accounts)?;
+    let mut ticks = SwapProcessor::load_ticks(program_id, pool_account.key, tick_accounts)?;
+
+    let mut ctx = SwapContext {
+        tick_bitmaps: &tick_bitmaps,
+        ticks: &mut ticks,
+        price_history: &mut price_history,
+        volume_history: &mut volume_history,
+        impact_history: &mut impact_history,
+        oracle_observations: &mut oracle_observations,
+        current_timestamp,
+        sequence_number,
+    };
+    let swap_result = SwapEngine::execute_swap(
+        &mut pool,
+        &mut ctx,
+        U256::from(pool_amount),
+        zero_for_one,
+        sqrt_price_limit,
+        user_account.key,
+    )?;
+
+    let pool_amount_in = u64::try_from(swap_result.amount_in).map_err(|_| CLMMError::MathOverflow)?;
+    let pool_amount_out = u64::try_from(swap_result.amount_out).map_err(|_| CLMMError::MathOverflow)?;
+
+    let user_amount_in = if pool_amount_in == pool_amount {
+        amount
+    } else {
+        input_fee.calculate_pre_fee_amount(pool_amount_in).ok_or(CLMMError::MathOverflow)?
+    };
+
+    SwapProcessor::assert_bitmap_coverage(
+        program_id,
+        pool_account.key,
+        pool.tick_spacing,
+        start_tick,
+        pool.tick,
+        bitmap_accounts,
+    )?;
+
+    if swap_result.price_impact > hop.max_price_impact_bps {
+        msg!("Price impact {} bps exceeds maximum {} bps", swap_result.price_impact, hop.max_price_impact_bps);
+        return Err(CLMMError::InvalidPrice.into());
+    }
+
+    if let Some(observation) = oracle_observations.back() {
+        oracle.write(observation.clone());
+    }
+    pool.last_oracle_update = current_timestamp;
+    pool.oracle_observation_count = oracle.observation_count as u32;
+    write_account_data(oracle_account, &oracle)?;
+
+    pool.serialize(&mut &mut pool_account.data.borrow_mut()[..])?;
+
+    for tick_account in tick_accounts {
+        let tick_data = tick_account.try_borrow_data()?;
+        let tick_index = Tick::deserialize(&mut &tick_data[..])?.tick;
+        drop(tick_data);
+
+        if let Some(tick) = ticks.get(&tick_index) {
+            write_account_data(tick_account, tick)?;
+        }
+    }
+
+    let amount_received = token_transfer_received(
+        token_program_in,
+        user_source,
+        mint_in,
+        vault_in,
+        user_account,
+        user_amount_in,
+    )?;
+    if amount_received < pool_amount_in {
+        msg!("Input vault received {}, swap requires {}", amount_received, pool_amount_in);
+        return Err(CLMMError::InsufficientAmountReceived.into());
+    }
+
+    // The next hop spends what actually arrived, after any transfer fee on the output
+    let balance_before = get_token_balance(user_destination)?;
+    let authority_bump_arr = [authority_bump];
+    let authority_seeds = pool_authority_seeds(pool_account.key, &authority_bump_arr);
+    token_transfer_signed(
+        token_program_out,
+        vault_out,
+        mint_out,
+        user_destination,
+        pool_authority,
+        pool_amount_out,
+        &authority_seeds,
+    )?;
+    let balance_after = get_token_balance(user_destination)?;
+
+    balance_after
+        .checked_sub(balance_before)
+        .ok_or(CLMMError::MathOverflow.into())
+}
@@ -4,6 +4,7 @@ use solana_program::pubkey::Pubkey;
 pub const MINIMUM_LIQUIDITY: u64 = 1000;
 pub const MAX_FEE: u32 = 10000;
 pub const PROTOCOL_FEE_PERCENT: u32 = 0;
+pub const MAX_PROTOCOL_FEE_PERCENT: u32 = 25;
 
 /// Common fee tiers (in basis points)
 pub const FEE_TIER_0_01: u32 = 1;
@@ -24,16 +25,26 @@ pub const MAX_TICK_RANGE_WIDTH: u32 = 887272 * 2;
 /// PDA seeds
 pub const POOL_SEED: &[u8] = b"pool";
 pub const POSITION_SEED: &[u8] = b"position";
+pub const POSITION_TOKEN_SEED: &[u8] = b"position_token";
 pub const TICK_SEED: &[u8] = b"tick";
-pub const BITMAP_SEED: &[u8] = b"bitmap";
 pub const PROTOCOL_FEE_SEED: &[u8] = b"protocol_fee";
 
 /// Account sizes (in bytes)
 pub const POOL_ACCOUNT_SIZE: usize = 8 + 32 + 32 + 4 + 4 + 4 + 16 + 4 + 16 + 16 + 16 + 16 + 16 + 8 + 4 + 1 + 4 + 4 + 4 + 4 + 4 + 1 + 4 + 4 + 8 + 4 + 4 + 200;
 
-pub const POSITION_ACCOUNT_SIZE: usize = 8 + 32 + 32 + 4 + 4 + 16 + 16 + 16 + 16 + 16 + 8 + 4 + 4 + 1 + 256;
+pub const POSITION_ACCOUNT_SIZE: usize = 8 + 32 + 32 + 4 + 4 + 16 + 16 + 16 + 16 + 16 + 8 + 4 + 4 + 1 + 32 + 1 + 4 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 32 + 75;
 
-pub const TICK_ACCOUNT_SIZE: usize = 8 + 4 + 16 + 16 + 16 + 16 + 16 + 16 + 4 + 1 + 256;
+pub const TICK_ACCOUNT_SIZE: usize = 8 + 4 + 16 + 16 + 16 + 16 + 16 + 16 + 4 + 1 + 1 + 16 + 16 + 4 + 16 + 16 + 123;
+
+/// Protocol config account: authority plus reserved space
+pub const PROTOCOL_CONFIG_ACCOUNT_SIZE: usize = 32 + 64;
+
+/// Tick bitmap account: 256-bit (32-byte) bitmap plus word position
+pub const TICK_BITMAP_ACCOUNT_SIZE: usize = 32 + 2;
+
+/// Oracle account: fixed header followed by `cardinality_next` observation slots
+pub const ORACLE_HEADER_SIZE: usize = 32 + 2 + 2 + 2 + 2;
+pub const ORACLE_OBSERVATION_SIZE: usize = 4 + 32 + 4 + 32;
 
 /// Helper function to get pool PDA
 pub fn get_pool_pda(token_a: &Pubkey, token_b: &Pubkey, fee: u32, program_id: &Pubkey) -> (Pubkey, u8) {
@@ -71,13 +82,3 @@ pub fn get_tick_pda(pool_id: &Pubkey, tick: i32, program_id: &Pubkey) -> (Pubkey
     Pubkey::find_program_address(seeds, program_id)
 }
 
-/// Helper function to get bitmap PDA
-pub fn get_bitmap_pda(pool_id: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
-    let seeds = &[
-        BITMAP_SEED,
-        &pool_id.to_bytes(),
-        &[0],
-    ];
-    Pubkey::find_program_address(seeds, program_id)
-}
-
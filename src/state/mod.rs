@@ -1,9 +1,13 @@
 pub mod pool;
 pub mod position;
 pub mod tick;
+pub mod oracle;
+pub mod protocol_config;
 pub mod constants;
 
 pub use pool::*;
 pub use position::*;
 pub use tick::*;
+pub use oracle::*;
+pub use protocol_config::*;
 pub use constants::*;
@@ -0,0 +1,159 @@
+use solana_program::pubkey::Pubkey;
+use crate::math::mev_protection::OracleObservation;
+use crate::state::constants::{ORACLE_HEADER_SIZE, ORACLE_OBSERVATION_SIZE};
+use std::collections::VecDeque;
+
+/// Per-pool price oracle holding a fixed-capacity ring buffer of observations
+///
+/// The account stores `cardinality_next` slots. Only the first `cardinality` of them
+/// form the active ring; a requested growth takes effect once the ring wraps back to its
+/// last slot, so the buffer stays in chronological order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Oracle {
+    /// Pool this oracle belongs to
+    pub pool: Pubkey,
+    /// Slot of the most recent observation
+    pub index: u16,
+    /// Number of slots in the active ring
+    pub cardinality: u16,
+    /// Number of allocated slots the ring grows into
+    pub cardinality_next: u16,
+    /// Number of slots written so far, capped at `cardinality`
+    pub observation_count: u16,
+    /// Observation slots, `cardinality_next` of them
+    pub observations: Vec<OracleObservation>,
+}
+
+impl Oracle {
+    /// Create an oracle with a single slot
+    pub fn new(pool: Pubkey) -> Self {
+        Oracle {
+            pool,
+            index: 0,
+            cardinality: 1,
+            cardinality_next: 1,
+            observation_count: 0,
+            observations: vec![OracleObservation::default()],
+        }
+    }
+
+    /// Account size needed to hold `cardinality` observation slots
+    pub fn space(cardinality: u16) -> usize {
+        ORACLE_HEADER_SIZE + cardinality as usize * ORACLE_OBSERVATION_SIZE
+    }
+
+    /// Record an observation, overwriting the oldest slot once the ring is full.
+    /// A second observation in the same second replaces the latest one.
+    pub fn write(&mut self, observation: OracleObservation) {
+        if self.observation_count > 0 {
+            if self.observations[self.index as usize].timestamp == observation.timestamp {
+                self.observations[self.index as usize] = observation;
+                return;
+            }
+
+            // Pending growth only kicks in at the end of the ring
+            if self.index == self.cardinality - 1 && self.cardinality_next > self.cardinality {
+                self.cardinality = self.cardinality_next;
+            }
+            self.index = (self.index + 1) % self.cardinality;
+        }
+
+        self.observations[self.index as usize] = observation;
+        self.observation_count = (self.observation_count + 1).min(self.cardinality);
+    }
+
+    /// Allocate room for `cardinality_next` observations. Never shrinks.
+    pub fn grow(&mut self, cardinality_next: u16) {
+        if cardinality_next <= self.cardinality_next {
+            return;
+        }
+        self.observations
+            .resize(cardinality_next as usize, OracleObservation::default());
+        self.cardinality_next = cardinality_next;
+    }
+
+    /// The most recent observation, if any
+    pub fn latest(&self) -> Option<&OracleObservation> {
+        if self.observation_count == 0 {
+            None
+        } else {
+            Some(&self.observations[self.index as usize])
+        }
+    }
+
+    /// Observations ordered from oldest to newest
+    pub fn chronological(&self) -> VecDeque<OracleObservation> {
+        let count = self.observation_count as usize;
+        let cardinality = self.cardinality as usize;
+
+        // Until the ring has filled up, the oldest observation sits in slot 0
+        let oldest = if count < cardinality {
+            0
+        } else {
+            (self.index as usize + 1) % cardinality
+        };
+
+        (0..count)
+            .map(|offset| self.observations[(oldest + offset) % cardinality].clone())
+            .collect()
+    }
+}
+
+impl borsh::BorshSerialize for Oracle {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.pool.serialize(writer)?;
+        self.index.serialize(writer)?;
+        self.cardinality.serialize(writer)?;
+        self.cardinality_next.serialize(writer)?;
+        self.observation_count.serialize(writer)?;
+        // The slot count is implied by cardinality_next, so no length prefix
+        for observation in &self.observations {
+            observation.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl borsh::BorshDeserialize for Oracle {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let pool = Pubkey::deserialize(buf)?;
+        let index = u16::deserialize(buf)?;
+        let cardinality = u16::deserialize(buf)?;
+        let cardinality_next = u16::deserialize(buf)?;
+        let observation_count = u16::deserialize(buf)?;
+        let mut observations = Vec::with_capacity(cardinality_next as usize);
+        for _ in 0..cardinality_next {
+            observations.push(OracleObservation::deserialize(buf)?);
+        }
+
+        Ok(Oracle {
+            pool,
+            index,
+            cardinality,
+            cardinality_next,
+            observation_count,
+            observations,
+        })
+    }
+
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let pool = Pubkey::deserialize_reader(reader)?;
+        let index = u16::deserialize_reader(reader)?;
+        let cardinality = u16::deserialize_reader(reader)?;
+        let cardinality_next = u16::deserialize_reader(reader)?;
+        let observation_count = u16::deserialize_reader(reader)?;
+        let mut observations = Vec::with_capacity(cardinality_next as usize);
+        for _ in 0..cardinality_next {
+            observations.push(OracleObservation::deserialize_reader(reader)?);
+        }
+
+        Ok(Oracle {
+            pool,
+            index,
+            cardinality,
+            cardinality_next,
+            observation_count,
+            observations,
+        })
+    }
+}
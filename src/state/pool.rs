@@ -99,8 +99,25 @@ pub struct Pool {
     /// MEV protection configuration
     pub mev_config: crate::math::mev_protection::MevConfig,
 
+    /// Share of each swap fee diverted to the protocol (in percent)
+    pub protocol_fee_percent: u32,
+
+    /// Administration fields
+    /// Authority allowed to pause the pool and change its fee and MEV settings
+    pub config_authority: Pubkey,
+    /// Authority proposed by `config_authority`, pending acceptance
+    pub pending_config_authority: Pubkey,
+
+    /// Token program that owns `token_a` (SPL Token or Token-2022)
+    pub token_program_a: Pubkey,
+    /// Token program that owns `token_b` (SPL Token or Token-2022)
+    pub token_program_b: Pubkey,
+
+    /// Whether a flash loan is out (reentrancy guard, separate from the pause state in `unlocked`)
+    pub flash_in_progress: bool,
+
     /// Reserve space for future fields
-    pub reserved: [u8; 200],
+    pub reserved: [u8; 67],
 }
 
 impl Pool {
@@ -148,7 +165,13 @@ impl Pool {
             last_sequence_number: 0,
             last_position_update: 0,
             mev_config: crate::math::mev_protection::MevProtectionEngine::default_config(),
-            reserved: [0; 200],
+            protocol_fee_percent: crate::state::constants::PROTOCOL_FEE_PERCENT,
+            config_authority: Pubkey::default(),
+            pending_config_authority: Pubkey::default(),
+            token_program_a: spl_token::id(),
+            token_program_b: spl_token::id(),
+            flash_in_progress: false,
+            reserved: [0; 67],
         })
     }
 
@@ -167,6 +190,77 @@ impl Pool {
         self.last_update_timestamp = timestamp;
     }
 
+    /// Split a swap fee into the liquidity provider and protocol shares
+    pub fn split_protocol_fee(&self, fee_amount: U256) -> (U256, U256) {
+        let protocol_fee = fee_amount * U256::from(self.protocol_fee_percent) / U256::from(100u32);
+        (fee_amount - protocol_fee, protocol_fee)
+    }
+
+    /// Fee owed on a flash loan of `amount`, charged at the pool fee and rounded up
+    pub fn flash_fee(&self, amount: u64) -> u64 {
+        let fee = (amount as u128 * self.fee as u128).div_ceil(10000);
+        fee.min(u64::MAX as u128) as u64
+    }
+
+    /// Check that the supplied token programs and mints are this pool's
+    pub fn validate_token_accounts(
+        &self,
+        token_program_a: &Pubkey,
+        token_program_b: &Pubkey,
+        mint_a: &Pubkey,
+        mint_b: &Pubkey,
+    ) -> Result<(), &'static str> {
+        if token_program_a != &self.token_program_a || token_program_b != &self.token_program_b {
+            return Err("Token program does not match the pool");
+        }
+        if mint_a != &self.token_a || mint_b != &self.token_b {
+            return Err("Mint does not match the pool");
+        }
+        Ok(())
+    }
+
+    /// Whether native SOL is this pool's token A (`true`) or token B (`false`)
+    pub fn native_mint_is_a(&self) -> Result<bool, &'static str> {
+        let native_mint = spl_token::native_mint::id();
+        if self.token_a == native_mint {
+            Ok(true)
+        } else if self.token_b == native_mint {
+            Ok(false)
+        } else {
+            Err("Pool has no wrapped SOL side")
+        }
+    }
+
+    /// Check whether `key` may administer this pool
+    pub fn is_config_authority(&self, key: &Pubkey) -> bool {
+        self.config_authority != Pubkey::default() && &self.config_authority == key
+    }
+
+    /// Update the fee bounds, pulling the current and base fee back inside them
+    pub fn set_fee_bounds(
+        &mut self,
+        min_fee: u32,
+        max_fee: u32,
+        fee_adjustment_interval: u32,
+    ) -> Result<(), &'static str> {
+        if min_fee > max_fee {
+            return Err("Minimum fee above maximum fee");
+        }
+        if max_fee > crate::state::constants::MAX_FEE {
+            return Err("Maximum fee out of range");
+        }
+        if fee_adjustment_interval == 0 {
+            return Err("Fee adjustment interval must be positive");
+        }
+
+        self.min_fee = min_fee;
+        self.max_fee = max_fee;
+        self.fee_adjustment_interval = fee_adjustment_interval;
+        self.base_fee = self.base_fee.clamp(min_fee, max_fee);
+        self.fee = self.fee.clamp(min_fee, max_fee);
+        Ok(())
+    }
+
     /// Check if a tick is properly spaced for this pool
     pub fn is_tick_spacing_valid(&self, tick: i32) -> bool {
         tick % self.tick_spacing as i32 == 0
@@ -231,6 +325,12 @@ impl borsh::BorshSerialize for Pool {
         self.last_sequence_number.serialize(writer)?;
         self.last_position_update.serialize(writer)?;
         self.mev_config.serialize(writer)?;
+        self.protocol_fee_percent.serialize(writer)?;
+        self.config_authority.serialize(writer)?;
+        self.pending_config_authority.serialize(writer)?;
+        self.token_program_a.serialize(writer)?;
+        self.token_program_b.serialize(writer)?;
+        self.flash_in_progress.serialize(writer)?;
         self.reserved.serialize(writer)?;
         Ok(())
     }
@@ -264,8 +364,14 @@ impl borsh::BorshDeserialize for Pool {
         let last_sequence_number_val = u64::deserialize(buf)?;
         let last_position_update_val = u32::deserialize(buf)?;
         let mev_config_val = crate::math::mev_protection::MevConfig::deserialize(buf)?;
-        let mut reserved = [0u8; 200];
-        for i in 0..200 {
+        let protocol_fee_percent = u32::deserialize(buf)?;
+        let config_authority = Pubkey::deserialize(buf)?;
+        let pending_config_authority = Pubkey::deserialize(buf)?;
+        let token_program_a = Pubkey::deserialize(buf)?;
+        let token_program_b = Pubkey::deserialize(buf)?;
+        let flash_in_progress = bool::deserialize(buf)?;
+        let mut reserved = [0u8; 67];
+        for i in 0..67 {
             reserved[i] = u8::deserialize(buf)?;
         }
 
@@ -296,6 +402,12 @@ impl borsh::BorshDeserialize for Pool {
             last_sequence_number: last_sequence_number_val,
             last_position_update: last_position_update_val,
             mev_config: mev_config_val,
+            protocol_fee_percent,
+            config_authority,
+            pending_config_authority,
+            token_program_a,
+            token_program_b,
+            flash_in_progress,
             reserved: reserved,
         })
     }
@@ -327,7 +439,13 @@ impl borsh::BorshDeserialize for Pool {
         let last_sequence_number_val = u64::deserialize_reader(reader)?;
         let last_position_update_val = u32::deserialize_reader(reader)?;
         let mev_config_val = crate::math::mev_protection::MevConfig::deserialize_reader(reader)?;
-        let mut reserved = [0u8; 200];
+        let protocol_fee_percent = u32::deserialize_reader(reader)?;
+        let config_authority = Pubkey::deserialize_reader(reader)?;
+        let pending_config_authority = Pubkey::deserialize_reader(reader)?;
+        let token_program_a = Pubkey::deserialize_reader(reader)?;
+        let token_program_b = Pubkey::deserialize_reader(reader)?;
+        let flash_in_progress = bool::deserialize_reader(reader)?;
+        let mut reserved = [0u8; 67];
         reader.read_exact(&mut reserved)?;
 
         Ok(Pool {
@@ -357,6 +475,12 @@ impl borsh::BorshDeserialize for Pool {
             last_sequence_number: last_sequence_number_val,
             last_position_update: last_position_update_val,
             mev_config: mev_config_val,
+            protocol_fee_percent,
+            config_authority,
+            pending_config_authority,
+            token_program_a,
+            token_program_b,
+            flash_in_progress,
             reserved: reserved,
         })
     }
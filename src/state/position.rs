@@ -1,13 +1,28 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
-use crate::math::tick_math::{U256, U256_ZERO};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use crate::error::CLMMError;
+use crate::math::tick_math::{U256, U256_ZERO, Q96};
+use crate::math::fixed_point::FixedPointMath;
+use crate::state::Tick;
+
+/// Side of a single-spacing range order
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RangeOrder {
+    /// Ordinary liquidity position
+    #[default]
+    None,
+    /// Sells token0 for token1, filled once the price crosses the upper tick upward
+    SellToken0,
+    /// Sells token1 for token0, filled once the price crosses the lower tick downward
+    SellToken1,
+}
 
 /// Represents a liquidity position in a pool
 #[derive(Debug, Clone, PartialEq)]
 pub struct Position {
     /// Pool this position belongs to
     pub pool_id: Pubkey,
-    /// Owner of this position
+    /// Wallet that opened this position (the holder of `position_mint` controls it)
     pub owner: Pubkey,
     /// Lower tick of the position range
     pub tick_lower: i32,
@@ -38,8 +53,35 @@ pub struct Position {
     /// Whether this position is active
     pub is_active: bool,
 
+    /// One-of-one SPL token representing ownership of this position
+    pub position_mint: Pubkey,
+
+    /// Whether this position is a range order, and which token it sells
+    pub range_order: RangeOrder,
+    /// Epoch of the completion tick when the range order was placed
+    pub range_order_epoch: u32,
+
+    /// Pool sqrt price when the position was opened, or when liquidity was first added
+    pub entry_sqrt_price_x96: U256,
+    /// Total liquidity ever added to this position
+    pub liquidity_added: U256,
+    /// Token0 paid into the pool for the position's liquidity
+    pub amount0_deposited: u64,
+    /// Token1 paid into the pool for the position's liquidity
+    pub amount1_deposited: u64,
+    /// Token0 principal paid back out of the pool
+    pub amount0_withdrawn: u64,
+    /// Token1 principal paid back out of the pool
+    pub amount1_withdrawn: u64,
+    /// Token0 fees paid out to the holder
+    pub fees0_collected: u64,
+    /// Token1 fees paid out to the holder
+    pub fees1_collected: u64,
+    /// Deposits valued in token1 at the pool price of each deposit
+    pub deposit_value1: U256,
+
     /// Reserve space for future fields
-    pub reserved: [u8; 256],
+    pub reserved: [u8; 75],
 }
 
 impl Position {
@@ -70,7 +112,19 @@ impl Position {
             created_at,
             updated_at: created_at,
             is_active: true,
-            reserved: [0; 256],
+            position_mint: Pubkey::default(),
+            range_order: RangeOrder::None,
+            range_order_epoch: 0,
+            entry_sqrt_price_x96: U256_ZERO,
+            liquidity_added: U256_ZERO,
+            amount0_deposited: 0,
+            amount1_deposited: 0,
+            amount0_withdrawn: 0,
+            amount1_withdrawn: 0,
+            fees0_collected: 0,
+            fees1_collected: 0,
+            deposit_value1: U256_ZERO,
+            reserved: [0; 75],
         })
     }
 
@@ -102,6 +156,24 @@ impl Position {
         (collected0, collected1)
     }
 
+    /// Fees earned since the last checkpoint, given the current fee growth inside the range
+    ///
+    /// Fails with `MathOverflow` rather than crediting a saturated amount.
+    pub fn fees_earned(
+        &self,
+        fee_growth_inside0: U256,
+        fee_growth_inside1: U256,
+    ) -> Result<(U256, U256), ProgramError> {
+        let q128 = U256::one() << 128;
+        let delta0 = fee_growth_inside0.overflowing_sub(self.fee_growth_inside0_last_x128).0;
+        let delta1 = fee_growth_inside1.overflowing_sub(self.fee_growth_inside1_last_x128).0;
+
+        Ok((
+            FixedPointMath::mul_div(delta0, self.liquidity, q128)?,
+            FixedPointMath::mul_div(delta1, self.liquidity, q128)?,
+        ))
+    }
+
     /// Update fee growth tracking
     pub fn update_fee_growth(
         &mut self,
@@ -134,6 +206,72 @@ impl Position {
         self.liquidity == U256_ZERO
     }
 
+    /// Check if this position is a range order
+    pub fn is_range_order(&self) -> bool {
+        self.range_order != RangeOrder::None
+    }
+
+    /// Tick whose crossing fills this range order, if it is one
+    pub fn range_order_completion_tick(&self) -> Option<i32> {
+        match self.range_order {
+            RangeOrder::None => None,
+            RangeOrder::SellToken0 => Some(self.tick_upper),
+            RangeOrder::SellToken1 => Some(self.tick_lower),
+        }
+    }
+
+    /// Whether this is a range order that a swap has filled since it was placed
+    pub fn is_range_order_filled(&self, tick_lower: &Tick, tick_upper: &Tick) -> bool {
+        match self.range_order {
+            RangeOrder::None => false,
+            RangeOrder::SellToken0 => tick_upper.is_range_order_filled(self.range_order_epoch),
+            RangeOrder::SellToken1 => tick_lower.is_range_order_filled(self.range_order_epoch),
+        }
+    }
+
+    /// Record liquidity added at `sqrt_price_x96` and the amounts paid for it
+    ///
+    /// The first deposit also resets the entry price, in case the position sat empty
+    /// after it was opened. Each deposit adds its token1 value at `sqrt_price_x96` to
+    /// the position's cost basis.
+    pub fn record_deposit(
+        &mut self,
+        liquidity: U256,
+        amount0: u64,
+        amount1: u64,
+        sqrt_price_x96: U256,
+    ) -> Result<(), ProgramError> {
+        let amount0_in_token1 = FixedPointMath::mul_div(
+            FixedPointMath::mul_div(U256::from(amount0), sqrt_price_x96, Q96)?,
+            sqrt_price_x96,
+            Q96,
+        )?;
+        self.deposit_value1 = self
+            .deposit_value1
+            .checked_add(amount0_in_token1 + U256::from(amount1))
+            .ok_or(CLMMError::MathOverflow)?;
+
+        if self.liquidity_added == U256_ZERO {
+            self.entry_sqrt_price_x96 = sqrt_price_x96;
+        }
+        self.liquidity_added = self.liquidity_added.saturating_add(liquidity);
+        self.amount0_deposited = self.amount0_deposited.saturating_add(amount0);
+        self.amount1_deposited = self.amount1_deposited.saturating_add(amount1);
+        Ok(())
+    }
+
+    /// Record principal paid back to the holder
+    pub fn record_withdrawal(&mut self, amount0: u64, amount1: u64) {
+        self.amount0_withdrawn = self.amount0_withdrawn.saturating_add(amount0);
+        self.amount1_withdrawn = self.amount1_withdrawn.saturating_add(amount1);
+    }
+
+    /// Record fees paid out to the holder
+    pub fn record_fees_collected(&mut self, amount0: u64, amount1: u64) {
+        self.fees0_collected = self.fees0_collected.saturating_add(amount0);
+        self.fees1_collected = self.fees1_collected.saturating_add(amount1);
+    }
+
     /// Deactivate the position
     pub fn deactivate(&mut self, timestamp: u32) {
         self.is_active = false;
@@ -156,6 +294,18 @@ impl borsh::BorshSerialize for Position {
         self.created_at.serialize(writer)?;
         self.updated_at.serialize(writer)?;
         self.is_active.serialize(writer)?;
+        self.position_mint.serialize(writer)?;
+        self.range_order.serialize(writer)?;
+        self.range_order_epoch.serialize(writer)?;
+        self.entry_sqrt_price_x96.serialize(writer)?;
+        self.liquidity_added.serialize(writer)?;
+        self.amount0_deposited.serialize(writer)?;
+        self.amount1_deposited.serialize(writer)?;
+        self.amount0_withdrawn.serialize(writer)?;
+        self.amount1_withdrawn.serialize(writer)?;
+        self.fees0_collected.serialize(writer)?;
+        self.fees1_collected.serialize(writer)?;
+        self.deposit_value1.serialize(writer)?;
         self.reserved.serialize(writer)?;
         Ok(())
     }
@@ -176,8 +326,20 @@ impl borsh::BorshDeserialize for Position {
         let created_at = u32::deserialize(buf)?;
         let updated_at = u32::deserialize(buf)?;
         let is_active = bool::deserialize(buf)?;
-        let mut reserved = [0u8; 256];
-        for i in 0..256 {
+        let position_mint = Pubkey::deserialize(buf)?;
+        let range_order = RangeOrder::deserialize(buf)?;
+        let range_order_epoch = u32::deserialize(buf)?;
+        let entry_sqrt_price_x96 = U256::deserialize(buf)?;
+        let liquidity_added = U256::deserialize(buf)?;
+        let amount0_deposited = u64::deserialize(buf)?;
+        let amount1_deposited = u64::deserialize(buf)?;
+        let amount0_withdrawn = u64::deserialize(buf)?;
+        let amount1_withdrawn = u64::deserialize(buf)?;
+        let fees0_collected = u64::deserialize(buf)?;
+        let fees1_collected = u64::deserialize(buf)?;
+        let deposit_value1 = U256::deserialize(buf)?;
+        let mut reserved = [0u8; 75];
+        for i in 0..75 {
             reserved[i] = u8::deserialize(buf)?;
         }
 
@@ -195,6 +357,18 @@ impl borsh::BorshDeserialize for Position {
             created_at,
             updated_at,
             is_active,
+            position_mint,
+            range_order,
+            range_order_epoch,
+            entry_sqrt_price_x96,
+            liquidity_added,
+            amount0_deposited,
+            amount1_deposited,
+            amount0_withdrawn,
+            amount1_withdrawn,
+            fees0_collected,
+            fees1_collected,
+            deposit_value1,
             reserved,
         })
     }
@@ -213,7 +387,19 @@ impl borsh::BorshDeserialize for Position {
         let created_at = u32::deserialize_reader(reader)?;
         let updated_at = u32::deserialize_reader(reader)?;
         let is_active = bool::deserialize_reader(reader)?;
-        let mut reserved = [0u8; 256];
+        let position_mint = Pubkey::deserialize_reader(reader)?;
+        let range_order = RangeOrder::deserialize_reader(reader)?;
+        let range_order_epoch = u32::deserialize_reader(reader)?;
+        let entry_sqrt_price_x96 = U256::deserialize_reader(reader)?;
+        let liquidity_added = U256::deserialize_reader(reader)?;
+        let amount0_deposited = u64::deserialize_reader(reader)?;
+        let amount1_deposited = u64::deserialize_reader(reader)?;
+        let amount0_withdrawn = u64::deserialize_reader(reader)?;
+        let amount1_withdrawn = u64::deserialize_reader(reader)?;
+        let fees0_collected = u64::deserialize_reader(reader)?;
+        let fees1_collected = u64::deserialize_reader(reader)?;
+        let deposit_value1 = U256::deserialize_reader(reader)?;
+        let mut reserved = [0u8; 75];
         reader.read_exact(&mut reserved)?;
 
         Ok(Position {
@@ -230,6 +416,18 @@ impl borsh::BorshDeserialize for Position {
             created_at,
             updated_at,
             is_active,
+            position_mint,
+            range_order,
+            range_order_epoch,
+            entry_sqrt_price_x96,
+            liquidity_added,
+            amount0_deposited,
+            amount1_deposited,
+            amount0_withdrawn,
+            amount1_withdrawn,
+            fees0_collected,
+            fees1_collected,
+            deposit_value1,
             reserved,
         })
     }
@@ -247,6 +445,8 @@ pub struct PositionInfo {
     pub tokens_owed0: U256,
     pub tokens_owed1: U256,
     pub is_active: bool,
+    pub position_mint: Pubkey,
+    pub range_order: RangeOrder,
     pub created_at: u32,
     pub updated_at: u32,
 }
@@ -263,6 +463,8 @@ impl From<&Position> for PositionInfo {
             tokens_owed0: position.tokens_owed0,
             tokens_owed1: position.tokens_owed1,
             is_active: position.is_active,
+            position_mint: position.position_mint,
+            range_order: position.range_order,
             created_at: position.created_at,
             updated_at: position.updated_at,
         }
@@ -0,0 +1,26 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Program-wide configuration, a single PDA holding the protocol authority
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq)]
+pub struct ProtocolConfig {
+    /// Authority allowed to set and collect protocol fees
+    pub authority: Pubkey,
+    /// Reserve space for future fields
+    pub reserved: [u8; 64],
+}
+
+impl ProtocolConfig {
+    /// Create a config owned by `authority`
+    pub fn new(authority: Pubkey) -> Self {
+        ProtocolConfig {
+            authority,
+            reserved: [0; 64],
+        }
+    }
+
+    /// Check whether `key` is the protocol authority
+    pub fn is_authority(&self, key: &Pubkey) -> bool {
+        &self.authority == key
+    }
+}
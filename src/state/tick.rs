@@ -1,5 +1,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use crate::math::tick_math::{U256, I256, U256_ZERO, I256_ZERO, Uint256};
+use crate::error::CLMMError;
+use crate::math::tick_math::{U256, I256, U256_ZERO, I256_ZERO};
+use std::collections::BTreeMap;
 
 /// Represents a tick in the concentrated liquidity system
 #[derive(Debug, Clone, PartialEq)]
@@ -27,8 +29,21 @@ pub struct Tick {
     /// Whether this tick has been initialized
     pub initialized: bool,
 
+    /// Whether the range orders completing at this tick fill when the price crosses it upward
+    pub range_order_upward: bool,
+    /// Liquidity of open range orders that complete at this tick
+    pub range_order_liquidity: U256,
+    /// Liquidity of filled range orders that have not been settled yet
+    pub range_order_filled_liquidity: U256,
+    /// Incremented every time the open range orders at this tick are filled
+    pub range_order_epoch: u32,
+    /// Fee growth inside the filled orders' range at the moment they were filled (token0)
+    pub range_order_fee_growth_inside0_x128: U256,
+    /// Fee growth inside the filled orders' range at the moment they were filled (token1)
+    pub range_order_fee_growth_inside1_x128: U256,
+
     /// Reserve space for future fields
-    pub reserved: [u8; 256],
+    pub reserved: [u8; 123],
 }
 
 impl Tick {
@@ -44,7 +59,13 @@ impl Tick {
             seconds_per_liquidity_outside_x128: U256_ZERO,
             seconds_outside: 0,
             initialized: false,
-            reserved: [0; 256],
+            range_order_upward: false,
+            range_order_liquidity: U256_ZERO,
+            range_order_filled_liquidity: U256_ZERO,
+            range_order_epoch: 0,
+            range_order_fee_growth_inside0_x128: U256_ZERO,
+            range_order_fee_growth_inside1_x128: U256_ZERO,
+            reserved: [0; 123],
         }
     }
 
@@ -60,7 +81,13 @@ impl Tick {
             seconds_per_liquidity_outside_x128: U256_ZERO,
             seconds_outside: 0,
             initialized: true,
-            reserved: [0; 256],
+            range_order_upward: false,
+            range_order_liquidity: U256_ZERO,
+            range_order_filled_liquidity: U256_ZERO,
+            range_order_epoch: 0,
+            range_order_fee_growth_inside0_x128: U256_ZERO,
+            range_order_fee_growth_inside1_x128: U256_ZERO,
+            reserved: [0; 123],
         }
     }
 
@@ -70,32 +97,33 @@ impl Tick {
     }
 
     /// Update liquidity at this tick
-    pub fn update_liquidity(&mut self, liquidity_delta: I256, upper: bool) {
-        if !self.initialized {
-            self.initialize();
+    ///
+    /// Returns true if the tick flipped between initialized and uninitialized, in which
+    /// case its bit in the tick bitmap must be flipped too. Fails without changing the
+    /// tick if either liquidity total would overflow.
+    pub fn update_liquidity(&mut self, liquidity_delta: I256, upper: bool) -> Result<bool, CLMMError> {
+        let was_initialized = !self.liquidity_gross.is_zero();
+
+        // Adding liquidity grows the gross amount, removing (a negative delta) shrinks it
+        let abs_delta = liquidity_delta.unsigned_abs();
+        let liquidity_gross = if liquidity_delta.is_negative() {
+            self.liquidity_gross.checked_sub(abs_delta)
+        } else {
+            self.liquidity_gross.checked_add(abs_delta)
         }
+        .ok_or(CLMMError::MathOverflow)?;
 
-        let abs_delta = if liquidity_delta < I256_ZERO {
-            let neg_delta = I256_ZERO - liquidity_delta;
-            let mut bytes = [0u8; 32];
-            for (i, chunk) in neg_delta.0.iter().enumerate() {
-                bytes[i * 8..(i + 1) * 8].copy_from_slice(&chunk.to_be_bytes());
-            }
-            Uint256::from_big_endian(&bytes)
-        } else {
-            let mut bytes = [0u8; 32];
-            for (i, chunk) in liquidity_delta.0.iter().enumerate() {
-                bytes[i * 8..(i + 1) * 8].copy_from_slice(&chunk.to_be_bytes());
-            }
-            Uint256::from_big_endian(&bytes)
-        };
-        if upper {
-            self.liquidity_net = self.liquidity_net + liquidity_delta;
-            self.liquidity_gross = self.liquidity_gross + abs_delta;
+        let liquidity_net = if upper {
+            self.liquidity_net.checked_add(liquidity_delta)
         } else {
-            self.liquidity_net = self.liquidity_net - liquidity_delta;
-            self.liquidity_gross = self.liquidity_gross + abs_delta;
+            self.liquidity_net.checked_sub(liquidity_delta)
         }
+        .ok_or(CLMMError::MathOverflow)?;
+
+        self.liquidity_gross = liquidity_gross;
+        self.liquidity_net = liquidity_net;
+        self.initialized = !self.liquidity_gross.is_zero();
+        Ok(was_initialized != self.initialized)
     }
 
     /// Update fee growth outside this tick
@@ -128,16 +156,129 @@ impl Tick {
         self.seconds_outside = seconds_outside;
     }
 
+    /// Fee growth per unit of liquidity inside `[lower, upper]` given the current tick.
+    ///
+    /// Growth outside each tick is read relative to the current tick, as in Uniswap v3.
+    /// The accumulators wrap, so all subtraction is modular and only differences between
+    /// two readings are meaningful.
+    pub fn fee_growth_inside(
+        lower: &Tick,
+        upper: &Tick,
+        tick_current: i32,
+        fee_growth_global0_x128: U256,
+        fee_growth_global1_x128: U256,
+    ) -> (U256, U256) {
+        let (below0, below1) = if tick_current >= lower.tick {
+            (lower.fee_growth_outside0_x128, lower.fee_growth_outside1_x128)
+        } else {
+            (
+                fee_growth_global0_x128.overflowing_sub(lower.fee_growth_outside0_x128).0,
+                fee_growth_global1_x128.overflowing_sub(lower.fee_growth_outside1_x128).0,
+            )
+        };
+
+        let (above0, above1) = if tick_current < upper.tick {
+            (upper.fee_growth_outside0_x128, upper.fee_growth_outside1_x128)
+        } else {
+            (
+                fee_growth_global0_x128.overflowing_sub(upper.fee_growth_outside0_x128).0,
+                fee_growth_global1_x128.overflowing_sub(upper.fee_growth_outside1_x128).0,
+            )
+        };
+
+        (
+            fee_growth_global0_x128.overflowing_sub(below0).0.overflowing_sub(above0).0,
+            fee_growth_global1_x128.overflowing_sub(below1).0.overflowing_sub(above1).0,
+        )
+    }
+
     /// Check if this tick has liquidity
     pub fn has_liquidity(&self) -> bool {
         !self.liquidity_gross.is_zero()
     }
 
-    /// Get the net liquidity change when crossing this tick
-    pub fn cross(&self) -> I256 {
+    /// Cross this tick during a swap, flipping the "outside" accumulators to the other side.
+    ///
+    /// Returns the net liquidity of the tick. Lower ticks carry `-L` and upper ticks `+L`,
+    /// so a swap moving the price down adds it to the active liquidity and a swap moving
+    /// the price up subtracts it.
+    pub fn cross(
+        &mut self,
+        fee_growth_global0_x128: U256,
+        fee_growth_global1_x128: U256,
+        timestamp: u32,
+    ) -> I256 {
+        self.fee_growth_outside0_x128 = fee_growth_global0_x128
+            .overflowing_sub(self.fee_growth_outside0_x128)
+            .0;
+        self.fee_growth_outside1_x128 = fee_growth_global1_x128
+            .overflowing_sub(self.fee_growth_outside1_x128)
+            .0;
+        self.seconds_outside = timestamp.wrapping_sub(self.seconds_outside);
+
         self.liquidity_net
     }
 
+    /// Register an open range order that completes at this tick.
+    ///
+    /// Orders filling in the opposite direction cannot be open at the same time, and new
+    /// orders wait until every filled order has been settled so that a single fee snapshot
+    /// covers all of them. Returns the epoch the order was placed in.
+    pub fn add_range_order(&mut self, liquidity: U256, upward: bool) -> Result<u32, &'static str> {
+        if !self.range_order_filled_liquidity.is_zero() {
+            return Err("Filled range orders at this tick must be settled first");
+        }
+        if !self.range_order_liquidity.is_zero() && self.range_order_upward != upward {
+            return Err("Range orders in the opposite direction are open at this tick");
+        }
+
+        self.range_order_upward = upward;
+        self.range_order_liquidity = self.range_order_liquidity.saturating_add(liquidity);
+        Ok(self.range_order_epoch)
+    }
+
+    /// Withdraw liquidity of an unfilled range order
+    pub fn remove_range_order(&mut self, liquidity: U256) {
+        self.range_order_liquidity = self.range_order_liquidity.saturating_sub(liquidity);
+    }
+
+    /// Whether range orders placed at `epoch` have been filled
+    pub fn is_range_order_filled(&self, epoch: u32) -> bool {
+        self.range_order_epoch != epoch
+    }
+
+    /// Mark every open range order at this tick as filled, recording the fee growth
+    /// inside their range so fees stop accruing to them. Returns the filled liquidity.
+    pub fn fill_range_orders(
+        &mut self,
+        fee_growth_inside0_x128: U256,
+        fee_growth_inside1_x128: U256,
+    ) -> U256 {
+        let filled = self.range_order_liquidity;
+        self.range_order_filled_liquidity = self.range_order_filled_liquidity.saturating_add(filled);
+        self.range_order_liquidity = U256_ZERO;
+        self.range_order_fee_growth_inside0_x128 = fee_growth_inside0_x128;
+        self.range_order_fee_growth_inside1_x128 = fee_growth_inside1_x128;
+        self.range_order_epoch = self.range_order_epoch.wrapping_add(1);
+        filled
+    }
+
+    /// Release a settled range order's liquidity from the filled total
+    pub fn settle_range_order(&mut self, liquidity: U256) {
+        self.range_order_filled_liquidity = self.range_order_filled_liquidity.saturating_sub(liquidity);
+    }
+
+    /// Remove liquidity whose net contribution was already taken out when its range order
+    /// was filled.
+    ///
+    /// Returns true if the tick flipped to uninitialized.
+    pub fn remove_gross_liquidity(&mut self, liquidity: U256) -> bool {
+        let was_initialized = !self.liquidity_gross.is_zero();
+        self.liquidity_gross = self.liquidity_gross.saturating_sub(liquidity);
+        self.initialized = !self.liquidity_gross.is_zero();
+        was_initialized != self.initialized
+    }
+
     /// Check if the tick is valid (within bounds)
     pub fn is_valid(&self) -> bool {
         self.tick >= crate::math::tick_math::MIN_TICK && self.tick <= crate::math::tick_math::MAX_TICK
@@ -155,6 +296,12 @@ impl borsh::BorshSerialize for Tick {
         self.seconds_per_liquidity_outside_x128.serialize(writer)?;
         self.seconds_outside.serialize(writer)?;
         self.initialized.serialize(writer)?;
+        self.range_order_upward.serialize(writer)?;
+        self.range_order_liquidity.serialize(writer)?;
+        self.range_order_filled_liquidity.serialize(writer)?;
+        self.range_order_epoch.serialize(writer)?;
+        self.range_order_fee_growth_inside0_x128.serialize(writer)?;
+        self.range_order_fee_growth_inside1_x128.serialize(writer)?;
         self.reserved.serialize(writer)?;
         Ok(())
     }
@@ -171,8 +318,14 @@ impl borsh::BorshDeserialize for Tick {
         let seconds_per_liquidity_outside_x128 = U256::deserialize(buf)?;
         let seconds_outside = u32::deserialize(buf)?;
         let initialized = bool::deserialize(buf)?;
-        let mut reserved = [0u8; 256];
-        for i in 0..256 {
+        let range_order_upward = bool::deserialize(buf)?;
+        let range_order_liquidity = U256::deserialize(buf)?;
+        let range_order_filled_liquidity = U256::deserialize(buf)?;
+        let range_order_epoch = u32::deserialize(buf)?;
+        let range_order_fee_growth_inside0_x128 = U256::deserialize(buf)?;
+        let range_order_fee_growth_inside1_x128 = U256::deserialize(buf)?;
+        let mut reserved = [0u8; 123];
+        for i in 0..123 {
             reserved[i] = u8::deserialize(buf)?;
         }
 
@@ -186,6 +339,12 @@ impl borsh::BorshDeserialize for Tick {
             seconds_per_liquidity_outside_x128,
             seconds_outside,
             initialized,
+            range_order_upward,
+            range_order_liquidity,
+            range_order_filled_liquidity,
+            range_order_epoch,
+            range_order_fee_growth_inside0_x128,
+            range_order_fee_growth_inside1_x128,
             reserved,
         })
     }
@@ -200,7 +359,13 @@ impl borsh::BorshDeserialize for Tick {
         let seconds_per_liquidity_outside_x128 = U256::deserialize_reader(reader)?;
         let seconds_outside = u32::deserialize_reader(reader)?;
         let initialized = bool::deserialize_reader(reader)?;
-        let mut reserved = [0u8; 256];
+        let range_order_upward = bool::deserialize_reader(reader)?;
+        let range_order_liquidity = U256::deserialize_reader(reader)?;
+        let range_order_filled_liquidity = U256::deserialize_reader(reader)?;
+        let range_order_epoch = u32::deserialize_reader(reader)?;
+        let range_order_fee_growth_inside0_x128 = U256::deserialize_reader(reader)?;
+        let range_order_fee_growth_inside1_x128 = U256::deserialize_reader(reader)?;
+        let mut reserved = [0u8; 123];
         reader.read_exact(&mut reserved)?;
 
         Ok(Tick {
@@ -213,6 +378,12 @@ impl borsh::BorshDeserialize for Tick {
             seconds_per_liquidity_outside_x128,
             seconds_outside,
             initialized,
+            range_order_upward,
+            range_order_liquidity,
+            range_order_filled_liquidity,
+            range_order_epoch,
+            range_order_fee_growth_inside0_x128,
+            range_order_fee_growth_inside1_x128,
             reserved,
         })
     }
@@ -251,8 +422,8 @@ impl From<&Tick> for TickInfo {
 /// Tick bitmap for efficient tick tracking
 #[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
 pub struct TickBitmap {
-    /// The bitmap data (each bit represents a tick)
-    pub bitmap: [u8; 256],
+    /// The bitmap data (each bit represents one of the word's 256 compressed ticks)
+    pub bitmap: [u8; 32],
     /// The word position (for larger bitmaps)
     pub word_position: i16,
 }
@@ -261,7 +432,7 @@ impl TickBitmap {
     /// Create a new tick bitmap
     pub fn new(word_position: i16) -> Self {
         TickBitmap {
-            bitmap: [0; 256],
+            bitmap: [0; 32],
             word_position,
         }
     }
@@ -269,51 +440,105 @@ impl TickBitmap {
     /// Set a bit at the given position
     pub fn set_bit(&mut self, bit_position: u8) {
         let byte_index = (bit_position / 8) as usize;
-        let bit_index = (bit_position % 8) as u8;
-        if byte_index < self.bitmap.len() {
-            self.bitmap[byte_index] |= 1 << bit_index;
-        }
+        let bit_index = bit_position % 8;
+        self.bitmap[byte_index] |= 1 << bit_index;
     }
 
     /// Clear a bit at the given position
     pub fn clear_bit(&mut self, bit_position: u8) {
         let byte_index = (bit_position / 8) as usize;
-        let bit_index = (bit_position % 8) as u8;
-        if byte_index < self.bitmap.len() {
-            self.bitmap[byte_index] &= !(1 << bit_index);
-        }
+        let bit_index = bit_position % 8;
+        self.bitmap[byte_index] &= !(1 << bit_index);
     }
 
     /// Check if a bit is set at the given position
     pub fn is_bit_set(&self, bit_position: u8) -> bool {
         let byte_index = (bit_position / 8) as usize;
-        let bit_index = (bit_position % 8) as u8;
-        if byte_index < self.bitmap.len() {
-            (self.bitmap[byte_index] & (1 << bit_index)) != 0
+        let bit_index = bit_position % 8;
+        (self.bitmap[byte_index] & (1 << bit_index)) != 0
+    }
+
+    /// Word position and bit position of `tick` in compressed form (`tick / tick_spacing`)
+    pub fn position(tick: i32, tick_spacing: u32) -> (i16, u8) {
+        let compressed = tick.div_euclid(tick_spacing as i32);
+        ((compressed >> 8) as i16, (compressed & 0xff) as u8)
+    }
+
+    /// Flip the bit for `tick`, which must fall in this word
+    pub fn flip_tick(&mut self, tick: i32, tick_spacing: u32) {
+        let (word_position, bit_position) = Self::position(tick, tick_spacing);
+        debug_assert_eq!(word_position, self.word_position);
+        if self.is_bit_set(bit_position) {
+            self.clear_bit(bit_position);
         } else {
-            false
+            self.set_bit(bit_position);
         }
     }
 
-    /// Find the next initialized tick
+    /// Check if no tick in this word is initialized
+    pub fn is_empty(&self) -> bool {
+        self.bitmap.iter().all(|&byte| byte == 0)
+    }
+
+    /// Find the next initialized tick across a set of bitmap words.
+    ///
+    /// With `lte` the search covers `tick` and everything below it, otherwise everything
+    /// strictly above it. Words missing from `words` are treated as empty.
+    pub fn next_initialized_tick_in_words(
+        words: &BTreeMap<i16, TickBitmap>,
+        tick: i32,
+        tick_spacing: u32,
+        lte: bool,
+    ) -> Option<i32> {
+        let mut compressed = tick.div_euclid(tick_spacing as i32);
+        if !lte {
+            compressed += 1;
+        }
+        let word_position = (compressed >> 8).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+
+        if lte {
+            words
+                .range(..=word_position)
+                .rev()
+                .find_map(|(_, word)| word.next_initialized_tick(tick, tick_spacing, true))
+        } else {
+            words
+                .range(word_position..)
+                .find_map(|(_, word)| word.next_initialized_tick(tick, tick_spacing, false))
+        }
+    }
+
+    /// Find the next initialized tick within this bitmap word.
+    ///
+    /// Ticks are tracked in compressed form (`tick / tick_spacing`), one bit per
+    /// compressed tick and 256 compressed ticks per word. With `lte` the search
+    /// includes `tick` itself and moves down; otherwise it starts strictly above
+    /// `tick` and moves up.
     pub fn next_initialized_tick(&self, tick: i32, tick_spacing: u32, lte: bool) -> Option<i32> {
-        let mut compressed = tick / tick_spacing as i32;
-        if !lte && tick % tick_spacing as i32 != 0 {
+        let tick_spacing = tick_spacing as i32;
+        let mut compressed = tick.div_euclid(tick_spacing);
+        if !lte {
             compressed += 1;
         }
 
-        while compressed >= 0 {
-            if self.is_bit_set(compressed as u8) {
-                return Some(compressed * tick_spacing as i32);
+        let word_start = self.word_position as i32 * 256;
+        let position = compressed - word_start;
+
+        if lte {
+            if position < 0 {
+                return None;
             }
-            if lte {
-                compressed -= 1;
-            } else {
-                compressed += 1;
+            (0..=position.min(255))
+                .rev()
+                .find(|&bit| self.is_bit_set(bit as u8))
+                .map(|bit| (word_start + bit) * tick_spacing)
+        } else {
+            if position > 255 {
+                return None;
             }
+            (position.max(0)..=255)
+                .find(|&bit| self.is_bit_set(bit as u8))
+                .map(|bit| (word_start + bit) * tick_spacing)
         }
-
-        None
     }
 }
-
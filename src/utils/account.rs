@@ -1,10 +1,12 @@
 use solana_program::{
     account_info::AccountInfo,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
     entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
     msg,
-    program::invoke_signed,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_utils::limited_deserialize,
     pubkey::Pubkey,
     rent::Rent,
     sysvar::Sysvar,
@@ -12,7 +14,7 @@ use solana_program::{
 use borsh::BorshSerialize;
 
 // System program ID
-solana_program::declare_id!("Fw4mNHEDrHAGg41XEcp7DkHpEP12MiUcCrP2Lj5ngth9");
+use solana_program::system_program::ID;
 
 // System instruction discriminators
 const SYSTEM_IX_CREATE_ACCOUNT: u32 = 0;
@@ -127,6 +129,34 @@ pub fn create_account<'a>(
     Ok(())
 }
 
+/// Transfer lamports from a signing system account
+pub fn transfer_lamports<'a>(
+    from: &AccountInfo<'a>,
+    to: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    lamports: u64,
+) -> ProgramResult {
+    invoke(
+        &transfer_ix(from.key, to.key, lamports),
+        &[from.clone(), to.clone(), system_program.clone()],
+    )
+}
+
+/// Create a new account whose address is a signing keypair rather than a PDA
+pub fn create_keypair_account<'a>(
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    owner: &Pubkey,
+    rent: &Rent,
+    space: usize,
+) -> ProgramResult {
+    invoke(
+        &create_account_ix(payer.key, new_account.key, rent.minimum_balance(space), space as u64, owner),
+        &[payer.clone(), new_account.clone(), system_program.clone()],
+    )
+}
+
 /// Assert that an account is writable
 pub fn assert_writable(account: &AccountInfo) -> ProgramResult {
     if !account.is_writable {
@@ -192,6 +222,36 @@ pub fn assert_account_key(account: &AccountInfo, expected: &Pubkey) -> ProgramRe
     Ok(())
 }
 
+/// Assert that `authority` is the upgrade authority recorded in the program's ProgramData account
+pub fn assert_upgrade_authority(
+    program_id: &Pubkey,
+    program_data: &AccountInfo,
+    authority: &AccountInfo,
+) -> ProgramResult {
+    let (expected_program_data, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if program_data.key != &expected_program_data {
+        msg!("Invalid program data account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let data = program_data.try_borrow_data()?;
+    let metadata_size = UpgradeableLoaderState::size_of_programdata_metadata();
+    let state: UpgradeableLoaderState = limited_deserialize(&data[..data.len().min(metadata_size)], metadata_size as u64)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    match state {
+        UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address: Some(upgrade_authority),
+            ..
+        } if &upgrade_authority == authority.key => Ok(()),
+        _ => {
+            msg!("Signer is not the program upgrade authority");
+            Err(ProgramError::MissingRequiredSignature)
+        }
+    }
+}
+
 /// Get the current unix timestamp from the Clock sysvar
 pub fn get_current_timestamp() -> Result<i64, ProgramError> {
     let clock = solana_program::clock::Clock::get()?;
@@ -244,6 +304,7 @@ pub fn realloc_account<'a>(
     account: &AccountInfo<'a>,
     new_size: usize,
     payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
     rent: &Rent,
 ) -> ProgramResult {
     let current_size = account.data_len();
@@ -261,7 +322,7 @@ pub fn realloc_account<'a>(
         if additional_lamports > 0 {
             invoke_signed(
                 &transfer_ix(payer.key, account.key, additional_lamports),
-                &[payer.clone(), account.clone()],
+                &[payer.clone(), account.clone(), system_program.clone()],
                 &[],
             )?;
         }
@@ -277,7 +338,6 @@ pub fn realloc_account<'a>(
         }
     }
 
-    // Note: realloc is not available in all Solana versions
-    // Account resizing would need to be done through reallocation
+    account.realloc(new_size, true)?;
     Ok(())
 }
@@ -6,45 +6,85 @@ use solana_program::{
     program_error::ProgramError,
     pubkey::Pubkey,
 };
+use crate::error::CLMMError;
 
-// SPL Token instruction discriminators
-const TOKEN_IX_TRANSFER: u8 = 3;
+// SPL Token instruction discriminators, shared by Token-2022
+const TOKEN_IX_TRANSFER_CHECKED: u8 = 12;
 const TOKEN_IX_MINT_TO: u8 = 7;
 const TOKEN_IX_BURN: u8 = 8;
 const TOKEN_IX_CLOSE_ACCOUNT: u8 = 9;
 const TOKEN_IX_INITIALIZE_ACCOUNT: u8 = 1;
+const TOKEN_IX_SET_AUTHORITY: u8 = 6;
+const TOKEN_IX_INITIALIZE_ACCOUNT3: u8 = 18;
+const TOKEN_IX_INITIALIZE_MINT2: u8 = 20;
 
-// Helper function to get the SPL Token program ID as our Pubkey type
-fn token_program_id() -> Pubkey {
-    Pubkey::new_from_array(spl_token::id().to_bytes())
+// SPL Token `AuthorityType::MintTokens`
+const TOKEN_AUTHORITY_MINT_TOKENS: u8 = 0;
+
+/// Size of an SPL Token mint account
+pub const MINT_ACCOUNT_SIZE: usize = 82;
+/// Size of an SPL Token account (Token-2022 accounts with extensions are larger)
+pub const TOKEN_ACCOUNT_SIZE: usize = 165;
+
+/// Token-2022 program ID
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = solana_program::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Check whether `key` is the SPL Token or Token-2022 program
+pub fn is_token_program(key: &Pubkey) -> bool {
+    key.to_bytes() == spl_token::id().to_bytes() || key == &TOKEN_2022_PROGRAM_ID
 }
 
-/// Transfer SPL tokens from one account to another
-pub fn token_transfer<'a>(
-    token_program: &AccountInfo<'a>,
-    source: &AccountInfo<'a>,
-    destination: &AccountInfo<'a>,
-    authority: &AccountInfo<'a>,
+/// Verify that an account is the SPL Token or Token-2022 program
+pub fn assert_token_program(token_program: &AccountInfo) -> ProgramResult {
+    if !is_token_program(token_program.key) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Build a `TransferChecked` instruction, which both token programs accept and
+/// Token-2022 requires for mints with a transfer fee
+fn transfer_checked_ix(
+    token_program: &AccountInfo,
+    source: &AccountInfo,
+    mint: &AccountInfo,
+    destination: &AccountInfo,
+    authority: &AccountInfo,
     amount: u64,
-) -> ProgramResult {
-    let mut data = Vec::with_capacity(9);
-    data.push(TOKEN_IX_TRANSFER);
+) -> Result<Instruction, ProgramError> {
+    let mut data = Vec::with_capacity(10);
+    data.push(TOKEN_IX_TRANSFER_CHECKED);
     data.extend_from_slice(&amount.to_le_bytes());
+    data.push(get_mint_decimals(mint)?);
 
-    let ix = Instruction {
-        program_id: token_program_id(),
+    Ok(Instruction {
+        program_id: *token_program.key,
         accounts: vec![
             AccountMeta::new(*source.key, false),
+            AccountMeta::new_readonly(*mint.key, false),
             AccountMeta::new(*destination.key, false),
             AccountMeta::new_readonly(*authority.key, true),
         ],
         data,
-    };
+    })
+}
+
+/// Transfer SPL tokens from one account to another
+pub fn token_transfer<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let ix = transfer_checked_ix(token_program, source, mint, destination, authority, amount)?;
 
     invoke(
         &ix,
         &[
             source.clone(),
+            mint.clone(),
             destination.clone(),
             authority.clone(),
             token_program.clone(),
@@ -56,29 +96,19 @@ pub fn token_transfer<'a>(
 pub fn token_transfer_signed<'a>(
     token_program: &AccountInfo<'a>,
     source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
     destination: &AccountInfo<'a>,
     authority: &AccountInfo<'a>,
     amount: u64,
     signer_seeds: &[&[u8]],
 ) -> ProgramResult {
-    let mut data = Vec::with_capacity(9);
-    data.push(TOKEN_IX_TRANSFER);
-    data.extend_from_slice(&amount.to_le_bytes());
-
-    let ix = Instruction {
-        program_id: token_program_id(),
-        accounts: vec![
-            AccountMeta::new(*source.key, false),
-            AccountMeta::new(*destination.key, false),
-            AccountMeta::new_readonly(*authority.key, true),
-        ],
-        data,
-    };
+    let ix = transfer_checked_ix(token_program, source, mint, destination, authority, amount)?;
 
     invoke_signed(
         &ix,
         &[
             source.clone(),
+            mint.clone(),
             destination.clone(),
             authority.clone(),
             token_program.clone(),
@@ -87,6 +117,27 @@ pub fn token_transfer_signed<'a>(
     )
 }
 
+/// Transfer SPL tokens and return the amount `destination` actually received
+///
+/// A Token-2022 transfer fee is withheld from the amount sent, so vault accounting
+/// credits the destination's balance change rather than the requested amount.
+pub fn token_transfer_received<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+) -> Result<u64, ProgramError> {
+    let balance_before = get_token_balance(destination)?;
+    token_transfer(token_program, source, mint, destination, authority, amount)?;
+    let balance_after = get_token_balance(destination)?;
+
+    balance_after
+        .checked_sub(balance_before)
+        .ok_or(CLMMError::MathOverflow.into())
+}
+
 /// Mint SPL tokens to a destination account
 pub fn token_mint_to<'a>(
     token_program: &AccountInfo<'a>,
@@ -101,7 +152,7 @@ pub fn token_mint_to<'a>(
     data.extend_from_slice(&amount.to_le_bytes());
 
     let ix = Instruction {
-        program_id: token_program_id(),
+        program_id: *token_program.key,
         accounts: vec![
             AccountMeta::new(*mint.key, false),
             AccountMeta::new(*destination.key, false),
@@ -135,7 +186,7 @@ pub fn token_burn<'a>(
     data.extend_from_slice(&amount.to_le_bytes());
 
     let ix = Instruction {
-        program_id: token_program_id(),
+        program_id: *token_program.key,
         accounts: vec![
             AccountMeta::new(*account.key, false),
             AccountMeta::new(*mint.key, false),
@@ -164,7 +215,7 @@ pub fn token_initialize_account<'a>(
     rent: &AccountInfo<'a>,
 ) -> ProgramResult {
     let ix = Instruction {
-        program_id: token_program_id(),
+        program_id: *token_program.key,
         accounts: vec![
             AccountMeta::new(*account.key, false),
             AccountMeta::new_readonly(*mint.key, false),
@@ -186,7 +237,7 @@ pub fn token_initialize_account<'a>(
     )
 }
 
-/// Close an SPL token account
+/// Close an SPL token account; pass empty `signer_seeds` when the authority signed the transaction
 pub fn token_close_account<'a>(
     token_program: &AccountInfo<'a>,
     account: &AccountInfo<'a>,
@@ -195,7 +246,7 @@ pub fn token_close_account<'a>(
     signer_seeds: &[&[u8]],
 ) -> ProgramResult {
     let ix = Instruction {
-        program_id: token_program_id(),
+        program_id: *token_program.key,
         accounts: vec![
             AccountMeta::new(*account.key, false),
             AccountMeta::new(*destination.key, false),
@@ -204,6 +255,7 @@ pub fn token_close_account<'a>(
         data: vec![TOKEN_IX_CLOSE_ACCOUNT],
     };
 
+    let signers: &[&[&[u8]]] = if signer_seeds.is_empty() { &[] } else { &[signer_seeds] };
     invoke_signed(
         &ix,
         &[
@@ -212,6 +264,78 @@ pub fn token_close_account<'a>(
             authority.clone(),
             token_program.clone(),
         ],
+        signers,
+    )
+}
+
+/// Initialize a new SPL token mint with no freeze authority
+pub fn token_initialize_mint<'a>(
+    token_program: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    mint_authority: &Pubkey,
+    decimals: u8,
+) -> ProgramResult {
+    let mut data = Vec::with_capacity(35);
+    data.push(TOKEN_IX_INITIALIZE_MINT2);
+    data.push(decimals);
+    data.extend_from_slice(mint_authority.as_ref());
+    data.push(0); // COption::None freeze authority
+
+    let ix = Instruction {
+        program_id: *token_program.key,
+        accounts: vec![AccountMeta::new(*mint.key, false)],
+        data,
+    };
+
+    invoke(&ix, &[mint.clone(), token_program.clone()])
+}
+
+/// Initialize a new SPL token account owned by `owner` (no rent sysvar needed)
+pub fn token_initialize_account_for<'a>(
+    token_program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    owner: &Pubkey,
+) -> ProgramResult {
+    let mut data = Vec::with_capacity(33);
+    data.push(TOKEN_IX_INITIALIZE_ACCOUNT3);
+    data.extend_from_slice(owner.as_ref());
+
+    let ix = Instruction {
+        program_id: *token_program.key,
+        accounts: vec![
+            AccountMeta::new(*account.key, false),
+            AccountMeta::new_readonly(*mint.key, false),
+        ],
+        data,
+    };
+
+    invoke(&ix, &[account.clone(), mint.clone(), token_program.clone()])
+}
+
+/// Permanently remove a mint's mint authority, fixing its supply
+pub fn token_revoke_mint_authority<'a>(
+    token_program: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let ix = Instruction {
+        program_id: *token_program.key,
+        accounts: vec![
+            AccountMeta::new(*mint.key, false),
+            AccountMeta::new_readonly(*authority.key, true),
+        ],
+        data: vec![TOKEN_IX_SET_AUTHORITY, TOKEN_AUTHORITY_MINT_TOKENS, 0],
+    };
+
+    invoke_signed(
+        &ix,
+        &[
+            mint.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
         &[signer_seeds],
     )
 }
@@ -219,7 +343,7 @@ pub fn token_close_account<'a>(
 /// Get the balance of an SPL token account
 pub fn get_token_balance(account: &AccountInfo) -> Result<u64, ProgramError> {
     let data = account.try_borrow_data()?;
-    if data.len() != 165 {
+    if data.len() < TOKEN_ACCOUNT_SIZE {
         return Err(ProgramError::InvalidAccountData);
     }
 
@@ -228,9 +352,36 @@ pub fn get_token_balance(account: &AccountInfo) -> Result<u64, ProgramError> {
     Ok(amount)
 }
 
-/// Verify that an account is owned by the SPL Token program
+/// Get the owner of an SPL token account
+pub fn get_token_owner(account: &AccountInfo) -> Result<Pubkey, ProgramError> {
+    assert_is_token_account(account)?;
+
+    let data = account.try_borrow_data()?;
+    if data.len() < TOKEN_ACCOUNT_SIZE {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let owner_bytes: [u8; 32] = data[32..64].try_into().map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok(Pubkey::new_from_array(owner_bytes))
+}
+
+/// Get the decimals of an SPL Token or Token-2022 mint
+pub fn get_mint_decimals(mint: &AccountInfo) -> Result<u8, ProgramError> {
+    if !is_token_program(mint.owner) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let data = mint.try_borrow_data()?;
+    if data.len() < MINT_ACCOUNT_SIZE {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(data[44])
+}
+
+/// Verify that an account is owned by the SPL Token or Token-2022 program
 pub fn assert_is_token_account(account: &AccountInfo) -> ProgramResult {
-    if account.owner.to_bytes() != spl_token::id().to_bytes() {
+    if !is_token_program(account.owner) {
         return Err(ProgramError::IllegalOwner);
     }
     Ok(())
@@ -275,3 +426,19 @@ pub fn assert_token_owner(
 
     Ok(())
 }
+
+/// Verify that `holder` holds the position NFT `position_mint` in `token_account`
+pub fn assert_position_holder(
+    token_account: &AccountInfo,
+    position_mint: &Pubkey,
+    holder: &Pubkey,
+) -> ProgramResult {
+    assert_token_mint(token_account, position_mint)?;
+    assert_token_owner(token_account, holder)?;
+
+    if get_token_balance(token_account)? != 1 {
+        return Err(CLMMError::Unauthorized.into());
+    }
+
+    Ok(())
+}
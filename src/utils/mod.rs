@@ -1,7 +1,11 @@
 pub mod account;
 pub mod cpi;
+pub mod native_sol;
 pub mod pda;
+pub mod token_extensions;
 
 pub use account::*;
 pub use cpi::*;
+pub use native_sol::*;
 pub use pda::*;
+pub use token_extensions::*;
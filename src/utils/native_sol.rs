@@ -0,0 +1,80 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::{self, Sysvar},
+};
+use crate::utils::account::{create_account, transfer_lamports};
+use crate::utils::cpi::{token_close_account, token_initialize_account, TOKEN_ACCOUNT_SIZE};
+use crate::utils::pda::{derive_wsol_address, WSOL_SEED};
+
+/// Check whether `mint` is the wrapped SOL mint
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    mint == &spl_token::native_mint::id()
+}
+
+/// Accounts for wrapping lamports into, and unwrapping them out of, an owner's
+/// temporary wrapped SOL account
+pub struct NativeSolAccounts<'a, 'b> {
+    pub owner: &'b AccountInfo<'a>,
+    /// The owner's wrapped SOL PDA
+    pub wsol_account: &'b AccountInfo<'a>,
+    pub native_mint: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub rent_sysvar: &'b AccountInfo<'a>,
+}
+
+/// Create `owner`'s temporary wrapped SOL account and wrap `lamports` into it
+///
+/// The account lives at the owner's WSOL PDA and is owned by `owner`, so it can stand
+/// in for the owner's token account until `unwrap_native` closes it.
+pub fn wrap_native(program_id: &Pubkey, accounts: &NativeSolAccounts, lamports: u64) -> ProgramResult {
+    let NativeSolAccounts { owner, wsol_account, native_mint, token_program, system_program, rent_sysvar } =
+        *accounts;
+    if !is_native_mint(native_mint.key) || token_program.key != &spl_token::id() {
+        msg!("Native SOL requires the SPL Token wrapped SOL mint");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if rent_sysvar.key != &sysvar::rent::id() {
+        msg!("Invalid rent sysvar");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (expected_wsol, wsol_bump) = derive_wsol_address(program_id, owner.key);
+    if wsol_account.key != &expected_wsol {
+        msg!("Invalid wrapped SOL account PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let wsol_seeds = &[
+        WSOL_SEED,
+        owner.key.as_ref(),
+        &[wsol_bump],
+    ];
+
+    create_account(
+        owner,
+        wsol_account,
+        system_program,
+        token_program.key,
+        &Rent::get()?,
+        TOKEN_ACCOUNT_SIZE,
+        wsol_seeds,
+    )?;
+
+    if lamports > 0 {
+        transfer_lamports(owner, wsol_account, system_program, lamports)?;
+    }
+
+    // Every lamport above the rent reserve becomes the wrapped balance
+    token_initialize_account(token_program, wsol_account, native_mint, owner, rent_sysvar)
+}
+
+/// Close `owner`'s temporary wrapped SOL account, returning its balance and rent as lamports
+pub fn unwrap_native(accounts: &NativeSolAccounts) -> ProgramResult {
+    token_close_account(accounts.token_program, accounts.wsol_account, accounts.owner, accounts.owner, &[])
+}
@@ -10,6 +10,7 @@ pub const POOL_AUTHORITY_SEED: &[u8] = b"pool_authority";
 
 /// Position PDA seeds
 pub const POSITION_SEED: &[u8] = b"position";
+pub const POSITION_TOKEN_SEED: &[u8] = b"position_token";
 
 /// Tick PDA seeds
 pub const TICK_SEED: &[u8] = b"tick";
@@ -17,6 +18,15 @@ pub const TICK_SEED: &[u8] = b"tick";
 /// Oracle PDA seeds
 pub const ORACLE_SEED: &[u8] = b"oracle";
 
+/// Tick bitmap PDA seeds
+pub const BITMAP_SEED: &[u8] = b"bitmap";
+
+/// Protocol config PDA seeds
+pub const PROTOCOL_FEE_SEED: &[u8] = b"protocol_fee";
+
+/// Temporary wrapped SOL account PDA seeds
+pub const WSOL_SEED: &[u8] = b"wsol";
+
 /// Derive the pool PDA address
 pub fn derive_pool_address(
     program_id: &Pubkey,
@@ -80,24 +90,29 @@ pub fn derive_pool_authority_address(
     )
 }
 
-/// Derive the position PDA address
+/// Derive the position PDA address from the position's NFT mint
 pub fn derive_position_address(
     program_id: &Pubkey,
-    pool: &Pubkey,
-    owner: &Pubkey,
-    tick_lower: i32,
-    tick_upper: i32,
+    position_mint: &Pubkey,
 ) -> (Pubkey, u8) {
-    let tick_lower_bytes = tick_lower.to_le_bytes();
-    let tick_upper_bytes = tick_upper.to_le_bytes();
-
     Pubkey::find_program_address(
         &[
             POSITION_SEED,
-            pool.as_ref(),
-            owner.as_ref(),
-            &tick_lower_bytes,
-            &tick_upper_bytes,
+            position_mint.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Derive the token account PDA the position NFT is minted into at open
+pub fn derive_position_token_address(
+    program_id: &Pubkey,
+    position_mint: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            POSITION_TOKEN_SEED,
+            position_mint.as_ref(),
         ],
         program_id,
     )
@@ -135,6 +150,43 @@ pub fn derive_oracle_address(
     )
 }
 
+/// Derive the tick bitmap PDA address for one 256-tick word
+pub fn derive_tick_bitmap_address(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    word_position: i16,
+) -> (Pubkey, u8) {
+    let word_bytes = word_position.to_le_bytes();
+
+    Pubkey::find_program_address(
+        &[
+            BITMAP_SEED,
+            pool.as_ref(),
+            &word_bytes,
+        ],
+        program_id,
+    )
+}
+
+/// Derive the program-wide protocol config PDA address
+pub fn derive_protocol_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROTOCOL_FEE_SEED], program_id)
+}
+
+/// Derive the temporary wrapped SOL account PDA for `owner`
+pub fn derive_wsol_address(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            WSOL_SEED,
+            owner.as_ref(),
+        ],
+        program_id,
+    )
+}
+
 /// Verify that a derived address matches the expected PDA
 pub fn verify_pda(
     expected: &Pubkey,
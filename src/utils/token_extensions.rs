@@ -0,0 +1,174 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    program_error::ProgramError,
+    sysvar::Sysvar,
+};
+use crate::utils::cpi::{is_token_program, TOKEN_ACCOUNT_SIZE};
+
+/// Offset of the account type byte that precedes Token-2022 extensions
+const ACCOUNT_TYPE_OFFSET: usize = TOKEN_ACCOUNT_SIZE;
+/// Token-2022 `AccountType::Mint`
+const ACCOUNT_TYPE_MINT: u8 = 1;
+
+// Token-2022 `ExtensionType` values
+const EXTENSION_UNINITIALIZED: u16 = 0;
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+const EXTENSION_MINT_CLOSE_AUTHORITY: u16 = 3;
+const EXTENSION_INTEREST_BEARING_CONFIG: u16 = 10;
+const EXTENSION_METADATA_POINTER: u16 = 18;
+const EXTENSION_TOKEN_METADATA: u16 = 19;
+const EXTENSION_GROUP_POINTER: u16 = 20;
+const EXTENSION_TOKEN_GROUP: u16 = 21;
+const EXTENSION_GROUP_MEMBER_POINTER: u16 = 22;
+const EXTENSION_TOKEN_GROUP_MEMBER: u16 = 23;
+
+/// Mint extensions a pool can hold safely. Anything else (transfer hooks, permanent
+/// delegates, default-frozen accounts, non-transferable tokens, ...) could block or
+/// drain the vaults.
+const SUPPORTED_MINT_EXTENSIONS: [u16; 9] = [
+    EXTENSION_TRANSFER_FEE_CONFIG,
+    EXTENSION_MINT_CLOSE_AUTHORITY,
+    EXTENSION_INTEREST_BEARING_CONFIG,
+    EXTENSION_METADATA_POINTER,
+    EXTENSION_TOKEN_METADATA,
+    EXTENSION_GROUP_POINTER,
+    EXTENSION_TOKEN_GROUP,
+    EXTENSION_GROUP_MEMBER_POINTER,
+    EXTENSION_TOKEN_GROUP_MEMBER,
+];
+
+/// Size of a token account holding the `TransferFeeAmount` extension
+const TRANSFER_FEE_ACCOUNT_SIZE: usize = TOKEN_ACCOUNT_SIZE + 1 + 4 + 8;
+
+/// Transfer fee charged by a Token-2022 mint with the transfer-fee extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TransferFee {
+    /// First epoch this fee applies to
+    pub epoch: u64,
+    /// Largest fee charged on a single transfer
+    pub maximum_fee: u64,
+    /// Fee rate in basis points
+    pub basis_points: u16,
+}
+
+impl TransferFee {
+    /// Fee withheld from a transfer of `amount`, rounded up as Token-2022 does
+    pub fn calculate_fee(&self, amount: u64) -> u64 {
+        if self.basis_points == 0 || amount == 0 {
+            return 0;
+        }
+
+        let fee = (amount as u128 * self.basis_points as u128).div_ceil(10000);
+        fee.min(self.maximum_fee as u128) as u64
+    }
+
+    /// Smallest amount to send so that at least `post_fee_amount` arrives
+    pub fn calculate_pre_fee_amount(&self, post_fee_amount: u64) -> Option<u64> {
+        if self.basis_points == 0 || post_fee_amount == 0 {
+            return Some(post_fee_amount);
+        }
+        if self.basis_points >= 10000 {
+            return post_fee_amount.checked_add(self.maximum_fee);
+        }
+
+        let numerator = post_fee_amount as u128 * 10000;
+        let denominator = 10000 - self.basis_points as u128;
+        let raw_pre_fee = numerator.div_ceil(denominator);
+
+        if raw_pre_fee - post_fee_amount as u128 >= self.maximum_fee as u128 {
+            post_fee_amount.checked_add(self.maximum_fee)
+        } else {
+            u64::try_from(raw_pre_fee).ok()
+        }
+    }
+}
+
+/// Iterate the extension types and values stored after a Token-2022 mint
+fn mint_extensions(data: &[u8]) -> Result<Vec<(u16, &[u8])>, ProgramError> {
+    // Mints without extensions keep the classic SPL Token layout
+    if data.len() <= ACCOUNT_TYPE_OFFSET {
+        return Ok(Vec::new());
+    }
+    if data[ACCOUNT_TYPE_OFFSET] != ACCOUNT_TYPE_MINT {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut extensions = Vec::new();
+    let mut offset = ACCOUNT_TYPE_OFFSET + 1;
+    while offset + 4 <= data.len() {
+        let extension_type = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let length = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        if extension_type == EXTENSION_UNINITIALIZED {
+            break;
+        }
+
+        let value = data
+            .get(offset + 4..offset + 4 + length)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        extensions.push((extension_type, value));
+        offset += 4 + length;
+    }
+
+    Ok(extensions)
+}
+
+/// Read the transfer fee in force at `epoch` from raw mint data, if the mint has one
+pub fn parse_transfer_fee(mint_data: &[u8], epoch: u64) -> Result<Option<TransferFee>, ProgramError> {
+    let config = match mint_extensions(mint_data)?
+        .into_iter()
+        .find(|(extension_type, _)| *extension_type == EXTENSION_TRANSFER_FEE_CONFIG)
+    {
+        Some((_, value)) => value,
+        None => return Ok(None),
+    };
+
+    // Two authorities and the withheld amount precede the older and newer fees
+    if config.len() < 108 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let read_fee = |offset: usize| TransferFee {
+        epoch: u64::from_le_bytes(config[offset..offset + 8].try_into().unwrap()),
+        maximum_fee: u64::from_le_bytes(config[offset + 8..offset + 16].try_into().unwrap()),
+        basis_points: u16::from_le_bytes(config[offset + 16..offset + 18].try_into().unwrap()),
+    };
+    let older = read_fee(72);
+    let newer = read_fee(90);
+
+    Ok(Some(if epoch >= newer.epoch { newer } else { older }))
+}
+
+/// Transfer fee currently charged by `mint` (zero for mints without the extension)
+pub fn get_transfer_fee(mint: &AccountInfo) -> Result<TransferFee, ProgramError> {
+    if !is_token_program(mint.owner) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let data = mint.try_borrow_data()?;
+    if data.len() <= ACCOUNT_TYPE_OFFSET {
+        return Ok(TransferFee::default());
+    }
+
+    let epoch = Clock::get()?.epoch;
+    Ok(parse_transfer_fee(&data, epoch)?.unwrap_or_default())
+}
+
+/// Verify that a pool can hold `mint`, rejecting Token-2022 extensions it cannot support
+pub fn assert_supported_mint(mint_data: &[u8]) -> Result<(), ProgramError> {
+    for (extension_type, _) in mint_extensions(mint_data)? {
+        if !SUPPORTED_MINT_EXTENSIONS.contains(&extension_type) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
+    Ok(())
+}
+
+/// Size of a vault token account for `mint`, including the account extensions
+/// Token-2022 requires for it
+pub fn vault_account_size(mint_data: &[u8]) -> Result<usize, ProgramError> {
+    let has_transfer_fee = mint_extensions(mint_data)?
+        .iter()
+        .any(|(extension_type, _)| *extension_type == EXTENSION_TRANSFER_FEE_CONFIG);
+
+    Ok(if has_transfer_fee { TRANSFER_FEE_ACCOUNT_SIZE } else { TOKEN_ACCOUNT_SIZE })
+}
@@ -1,5 +1,6 @@
-use clmm_rust::math::fixed_point::{FixedPointMath, U256_ZERO};
-use clmm_rust::math::tick_math::U256;
+use clmm_rust::error::CLMMError;
+use clmm_rust::math::fixed_point::FixedPointMath;
+use clmm_rust::math::tick_math::{TickMath, U256, U256_ZERO};
 
 #[test]
 fn test_mul_div() {
@@ -39,8 +40,72 @@ fn test_get_liquidity_for_amounts() {
     let amount0 = U256::from(1000u64);
     let amount1 = U256::from(2000u64);
 
-    let liquidity =
-        FixedPointMath::get_liquidity_for_amounts(sqrt_price_a, sqrt_price_b, amount0, amount1);
+    // At these tiny prices only token1 funds meaningful liquidity, so price the pool above the range
+    let liquidity = FixedPointMath::get_liquidity_for_amounts(
+        sqrt_price_b,
+        sqrt_price_a,
+        sqrt_price_b,
+        amount0,
+        amount1,
+    );
 
     assert!(liquidity > U256_ZERO);
 }
+
+#[test]
+fn test_get_liquidity_for_amounts_against_current_price() {
+    let sqrt_price_lower = TickMath::get_sqrt_ratio_at_tick(-600).unwrap();
+    let sqrt_price_upper = TickMath::get_sqrt_ratio_at_tick(600).unwrap();
+    let amount0 = U256::from(1_000_000u64);
+    let amount1 = U256::from(1_000_000u64);
+
+    // Below the range only token0 funds liquidity, above it only token1
+    let below = FixedPointMath::get_liquidity_for_amounts(
+        TickMath::get_sqrt_ratio_at_tick(-1200).unwrap(),
+        sqrt_price_lower,
+        sqrt_price_upper,
+        amount0,
+        U256_ZERO,
+    );
+    assert_eq!(below, FixedPointMath::get_liquidity_for_amount0(sqrt_price_lower, sqrt_price_upper, amount0));
+
+    let above = FixedPointMath::get_liquidity_for_amounts(
+        TickMath::get_sqrt_ratio_at_tick(1200).unwrap(),
+        sqrt_price_lower,
+        sqrt_price_upper,
+        U256_ZERO,
+        amount1,
+    );
+    assert_eq!(above, FixedPointMath::get_liquidity_for_amount1(sqrt_price_lower, sqrt_price_upper, amount1));
+
+    // In range the scarcer token limits liquidity, and that liquidity never costs more than supplied
+    let sqrt_price_current = TickMath::get_sqrt_ratio_at_tick(0).unwrap();
+    let liquidity = FixedPointMath::get_liquidity_for_amounts(
+        sqrt_price_current,
+        sqrt_price_lower,
+        sqrt_price_upper,
+        amount0,
+        amount1 / U256::from(2u32),
+    );
+    let needed0 = FixedPointMath::get_amount0_delta(sqrt_price_current, sqrt_price_upper, liquidity, true).unwrap();
+    let needed1 = FixedPointMath::get_amount1_delta(sqrt_price_lower, sqrt_price_current, liquidity, true).unwrap();
+    assert!(needed0 <= amount0);
+    assert!(needed1 <= amount1 / U256::from(2u32));
+    assert!(needed1 + U256::from(2u32) >= amount1 / U256::from(2u32));
+}
+
+#[test]
+fn test_amount_deltas_report_overflow() {
+    let sqrt_price_lower = TickMath::get_sqrt_ratio_at_tick(-887_000).unwrap();
+    let sqrt_price_upper = TickMath::get_sqrt_ratio_at_tick(887_000).unwrap();
+    let liquidity = U256::MAX >> 1;
+
+    assert_eq!(
+        FixedPointMath::get_amount0_delta(sqrt_price_lower, sqrt_price_upper, liquidity, true),
+        Err(CLMMError::MathOverflow.into())
+    );
+    assert_eq!(
+        FixedPointMath::get_amount1_delta(sqrt_price_lower, sqrt_price_upper, liquidity, false),
+        Err(CLMMError::MathOverflow.into())
+    );
+}
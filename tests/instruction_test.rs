@@ -0,0 +1,432 @@
+use borsh::BorshDeserialize;
+use clmm_rust::instruction::{
+    self, AddLiquidityArgs, AddLiquidityByAmountsArgs, CollectFeesArgs, InitializePoolArgs, PlaceRangeOrderArgs,
+    PoolTokens, PositionAccounts, RangeOrderAccounts, SwapAccounts, SwapArgs, SwapRouteHop,
+};
+use clmm_rust::processor::CLMMInstruction;
+use clmm_rust::processor::swap_route::{RouteHop, ROUTE_HOP_ACCOUNTS};
+use clmm_rust::utils::{
+    TOKEN_2022_PROGRAM_ID, derive_pool_address, derive_position_address, derive_position_token_address,
+    derive_tick_bitmap_address, derive_wsol_address,
+};
+use solana_program::{pubkey::Pubkey, system_program, sysvar};
+
+#[test]
+fn test_initialize_pool_builder_sorts_mints() {
+    let program_id = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+    let mint_a = Pubkey::new_unique();
+    let mint_b = Pubkey::new_unique();
+
+    // Mint B is a Token-2022 mint; its token program must follow it through the sort
+    let tokens = PoolTokens {
+        mint_a: mint_b,
+        mint_b: mint_a,
+        token_program_a: TOKEN_2022_PROGRAM_ID,
+        token_program_b: spl_token::id(),
+    };
+    let ix = instruction::initialize_pool(
+        &program_id,
+        &payer,
+        &tokens,
+        InitializePoolArgs { fee: 3000, tick_spacing: 60, initial_sqrt_price_x96: 1u128 << 96 },
+    );
+    let (token_0, program_0, token_1, program_1) = if mint_a < mint_b {
+        (mint_a, spl_token::id(), mint_b, TOKEN_2022_PROGRAM_ID)
+    } else {
+        (mint_b, TOKEN_2022_PROGRAM_ID, mint_a, spl_token::id())
+    };
+    let (pool, _) = derive_pool_address(&program_id, &token_0, &token_1, 3000);
+
+    assert_eq!(ix.accounts.len(), 12);
+    assert!(ix.accounts[0].is_signer);
+    assert_eq!(ix.accounts[1].pubkey, pool);
+    assert_eq!(ix.accounts[2].pubkey, token_0);
+    assert_eq!(ix.accounts[3].pubkey, token_1);
+    assert_eq!(ix.accounts[7].pubkey, program_0);
+    assert_eq!(ix.accounts[11].pubkey, program_1);
+    assert_eq!(
+        CLMMInstruction::try_from_slice(&ix.data).unwrap(),
+        CLMMInstruction::InitializePool { fee: 3000, tick_spacing: 60, initial_sqrt_price_x96: 1u128 << 96 }
+    );
+}
+
+#[test]
+fn test_open_position_builder_accounts() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let position_mint = Pubkey::new_unique();
+
+    let ix = instruction::open_position(&program_id, &owner, &pool, &position_mint, -120, 15360);
+
+    assert_eq!(ix.accounts.len(), 8);
+    assert_eq!(ix.accounts[2].pubkey, derive_position_address(&program_id, &position_mint).0);
+    assert!(ix.accounts[3].is_signer);
+    assert_eq!(ix.accounts[4].pubkey, derive_position_token_address(&program_id, &position_mint).0);
+    assert_eq!(
+        CLMMInstruction::try_from_slice(&ix.data).unwrap(),
+        CLMMInstruction::OpenPosition { tick_lower: -120, tick_upper: 15360 }
+    );
+}
+
+#[test]
+fn test_add_liquidity_builder_accounts() {
+    let program_id = Pubkey::new_unique();
+    let holder = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let position_mint = Pubkey::new_unique();
+    let holder_token_account = Pubkey::new_unique();
+    let tokens = PoolTokens::new(Pubkey::new_unique(), Pubkey::new_unique());
+
+    let position = PositionAccounts {
+        pool,
+        tokens,
+        tick_spacing: 60,
+        position_mint,
+        holder_token_account,
+        user_token_a: Pubkey::new_unique(),
+        user_token_b: Pubkey::new_unique(),
+        tick_lower: -120,
+        tick_upper: 15360,
+    };
+
+    let ix = instruction::add_liquidity(
+        &program_id,
+        &holder,
+        &position,
+        AddLiquidityArgs { liquidity_delta: 1_000_000, amount_0_max: 500, amount_1_max: 600, native_sol: false },
+    );
+
+    assert_eq!(ix.accounts.len(), 19);
+    assert_eq!(ix.accounts[2].pubkey, derive_position_address(&program_id, &position_mint).0);
+    assert_eq!(ix.accounts[3].pubkey, holder_token_account);
+    assert!(!ix.accounts[3].is_writable);
+    assert_eq!(ix.accounts[11].pubkey, tokens.token_program_a);
+    assert_eq!(ix.accounts[14].pubkey, derive_tick_bitmap_address(&program_id, &pool, -1).0);
+    assert_eq!(ix.accounts[15].pubkey, derive_tick_bitmap_address(&program_id, &pool, 1).0);
+    assert_eq!(ix.accounts[16].pubkey, tokens.token_program_b);
+    assert_eq!(ix.accounts[17].pubkey, tokens.mint_a);
+    assert_eq!(ix.accounts[18].pubkey, tokens.mint_b);
+    assert_eq!(
+        CLMMInstruction::try_from_slice(&ix.data).unwrap(),
+        CLMMInstruction::AddLiquidity {
+            liquidity_delta: 1_000_000,
+            amount_0_max: 500,
+            amount_1_max: 600,
+            native_sol: false,
+        }
+    );
+}
+
+#[test]
+fn test_add_liquidity_by_amounts_builder_matches_add_liquidity_accounts() {
+    let program_id = Pubkey::new_unique();
+    let holder = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let position_mint = Pubkey::new_unique();
+    let holder_token_account = Pubkey::new_unique();
+    let tokens = PoolTokens::new(Pubkey::new_unique(), Pubkey::new_unique());
+    let user_token_a = Pubkey::new_unique();
+    let user_token_b = Pubkey::new_unique();
+
+    let position = PositionAccounts {
+        pool,
+        tokens,
+        tick_spacing: 60,
+        position_mint,
+        holder_token_account,
+        user_token_a,
+        user_token_b,
+        tick_lower: -120,
+        tick_upper: 120,
+    };
+
+    let by_liquidity = instruction::add_liquidity(
+        &program_id,
+        &holder,
+        &position,
+        AddLiquidityArgs { liquidity_delta: 1, amount_0_max: 0, amount_1_max: 0, native_sol: false },
+    );
+    let by_amounts = instruction::add_liquidity_by_amounts(
+        &program_id,
+        &holder,
+        &position,
+        AddLiquidityByAmountsArgs {
+            amount_0_desired: 1_000,
+            amount_1_desired: 2_000,
+            amount_0_min: 900,
+            amount_1_min: 1_800,
+            native_sol: false,
+        },
+    );
+
+    assert_eq!(by_amounts.accounts, by_liquidity.accounts);
+    assert_eq!(
+        CLMMInstruction::try_from_slice(&by_amounts.data).unwrap(),
+        CLMMInstruction::AddLiquidityByAmounts {
+            amount_0_desired: 1_000,
+            amount_1_desired: 2_000,
+            amount_0_min: 900,
+            amount_1_min: 1_800,
+            native_sol: false,
+        }
+    );
+}
+
+#[test]
+fn test_range_order_builders() {
+    let program_id = Pubkey::new_unique();
+    let holder = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let position_mint = Pubkey::new_unique();
+    let holder_token_account = Pubkey::new_unique();
+    let tokens = PoolTokens::new(Pubkey::new_unique(), Pubkey::new_unique());
+    let user_token_a = Pubkey::new_unique();
+    let user_token_b = Pubkey::new_unique();
+
+    let order = RangeOrderAccounts {
+        pool,
+        tokens,
+        tick_spacing: 60,
+        position_mint,
+        holder_token_account,
+        token_account_a: user_token_a,
+        token_account_b: user_token_b,
+        tick_lower: 120,
+    };
+
+    // A range order spans a single tick spacing
+    let add = instruction::add_liquidity(
+        &program_id,
+        &holder,
+        &PositionAccounts {
+            pool,
+            tokens,
+            tick_spacing: 60,
+            position_mint,
+            holder_token_account,
+            user_token_a,
+            user_token_b,
+            tick_lower: 120,
+            tick_upper: 180,
+        },
+        AddLiquidityArgs { liquidity_delta: 1, amount_0_max: 0, amount_1_max: 0, native_sol: false },
+    );
+    let place = instruction::place_range_order(
+        &program_id,
+        &holder,
+        &order,
+        PlaceRangeOrderArgs { liquidity_delta: 1_000, amount_0_max: 500, amount_1_max: 0 },
+    );
+    assert_eq!(place.accounts, add.accounts);
+
+    let settle = instruction::settle_range_order(&program_id, &Pubkey::new_unique(), &order);
+    assert_eq!(settle.accounts.len(), 17);
+    assert!(settle.accounts[0].is_signer);
+    assert_eq!(settle.accounts[2].pubkey, add.accounts[2].pubkey);
+    assert_eq!(settle.accounts[4..6], add.accounts[4..6]);
+    assert_eq!(settle.accounts[12..14], add.accounts[14..16]);
+    assert_eq!(settle.accounts[14..17], add.accounts[16..19]);
+    assert_eq!(
+        CLMMInstruction::try_from_slice(&settle.data).unwrap(),
+        CLMMInstruction::SettleRangeOrder
+    );
+}
+
+#[test]
+fn test_swap_builder_remaining_accounts() {
+    let program_id = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let mut tokens = PoolTokens::new(Pubkey::new_unique(), Pubkey::new_unique());
+    tokens.token_program_b = TOKEN_2022_PROGRAM_ID;
+
+    let accounts = SwapAccounts {
+        pool,
+        tokens,
+        user_token_a: Pubkey::new_unique(),
+        user_token_b: Pubkey::new_unique(),
+        bitmap_words: vec![0, -1],
+        ticks: vec![-60, -120, -180],
+    };
+
+    let ix = instruction::swap(
+        &program_id,
+        &Pubkey::new_unique(),
+        &accounts,
+        SwapArgs {
+            amount_in: 1000,
+            minimum_amount_out: 990,
+            sqrt_price_limit: 0,
+            zero_for_one: true,
+            max_price_impact_bps: 100,
+            native_sol: false,
+        },
+    );
+
+    assert_eq!(ix.accounts.len(), 12 + 2 + 3);
+    assert_eq!(ix.accounts[7].pubkey, spl_token::id());
+    assert_eq!(ix.accounts[9].pubkey, TOKEN_2022_PROGRAM_ID);
+    assert!(!ix.accounts[12].is_writable);
+    assert!(ix.accounts[14].is_writable);
+    assert!(matches!(
+        CLMMInstruction::try_from_slice(&ix.data).unwrap(),
+        CLMMInstruction::Swap { amount_in: 1000, minimum_amount_out: 990, zero_for_one: true, .. }
+    ));
+}
+
+#[test]
+fn test_native_sol_builders_use_wrapped_sol_pda() {
+    let program_id = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let user_token_a = Pubkey::new_unique();
+    let user_token_b = Pubkey::new_unique();
+    let tokens = PoolTokens::new(Pubkey::new_unique(), spl_token::native_mint::id());
+    let (wsol, _) = derive_wsol_address(&program_id, &user);
+
+    // SOL is token B here, so only the B account is replaced
+    let accounts = SwapAccounts {
+        pool,
+        tokens,
+        user_token_a,
+        user_token_b,
+        bitmap_words: vec![0],
+        ticks: vec![-60],
+    };
+    let ix = instruction::swap(
+        &program_id,
+        &user,
+        &accounts,
+        SwapArgs {
+            amount_in: 1000,
+            minimum_amount_out: 990,
+            sqrt_price_limit: 0,
+            zero_for_one: true,
+            max_price_impact_bps: 100,
+            native_sol: true,
+        },
+    );
+    assert_eq!(ix.accounts.len(), 12 + 2 + 1 + 1);
+    assert!(ix.accounts[0].is_signer && ix.accounts[0].is_writable);
+    assert_eq!(ix.accounts[2].pubkey, user_token_a);
+    assert_eq!(ix.accounts[3].pubkey, wsol);
+    assert_eq!(ix.accounts[12].pubkey, system_program::id());
+    assert_eq!(ix.accounts[13].pubkey, sysvar::rent::id());
+    assert!(matches!(
+        CLMMInstruction::try_from_slice(&ix.data).unwrap(),
+        CLMMInstruction::Swap { native_sol: true, .. }
+    ));
+
+    let position = PositionAccounts {
+        pool,
+        tokens,
+        tick_spacing: 60,
+        position_mint: Pubkey::new_unique(),
+        holder_token_account: Pubkey::new_unique(),
+        user_token_a,
+        user_token_b,
+        tick_lower: -120,
+        tick_upper: 120,
+    };
+    let plain = instruction::collect_fees(
+        &program_id,
+        &user,
+        &position,
+        CollectFeesArgs { amount_0_requested: 0, amount_1_requested: 0, native_sol: false },
+    );
+    assert_eq!(plain.accounts.len(), 15);
+    assert!(!plain.accounts[0].is_writable);
+    assert_eq!(plain.accounts[7].pubkey, user_token_b);
+
+    let native = instruction::collect_fees(
+        &program_id,
+        &user,
+        &position,
+        CollectFeesArgs { amount_0_requested: 0, amount_1_requested: 0, native_sol: true },
+    );
+    assert_eq!(native.accounts.len(), 17);
+    assert!(native.accounts[0].is_writable);
+    assert_eq!(native.accounts[7].pubkey, wsol);
+}
+
+#[test]
+fn test_swap_route_builder_chains_hops() {
+    let program_id = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let (mint_x, mint_y, mint_z) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let (user_x, user_y, user_z) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    // X -> Y through a pool where Y is token B, then Y -> Z where Y is token B again
+    let first = SwapRouteHop {
+        pool: Pubkey::new_unique(),
+        tokens: PoolTokens::new(mint_x, mint_y),
+        user_token_a: user_x,
+        user_token_b: user_y,
+        zero_for_one: true,
+        max_price_impact_bps: 100,
+        bitmap_words: vec![0, -1],
+        ticks: vec![-60],
+    };
+    let second = SwapRouteHop {
+        pool: Pubkey::new_unique(),
+        tokens: PoolTokens::new(mint_z, mint_y),
+        user_token_a: user_z,
+        user_token_b: user_y,
+        zero_for_one: false,
+        max_price_impact_bps: 200,
+        bitmap_words: vec![0],
+        ticks: vec![],
+    };
+
+    let ix = instruction::swap_route(&program_id, &user, &[first.clone(), second.clone()], 1000, 950);
+
+    let second_start = 1 + ROUTE_HOP_ACCOUNTS + 2 + 1;
+    assert_eq!(ix.accounts.len(), second_start + ROUTE_HOP_ACCOUNTS + 1);
+    assert!(ix.accounts[0].is_signer && !ix.accounts[0].is_writable);
+    assert_eq!(ix.accounts[1].pubkey, first.pool);
+    assert_eq!(ix.accounts[3].pubkey, user_y);
+    assert_eq!(
+        ix.accounts[1 + ROUTE_HOP_ACCOUNTS].pubkey,
+        derive_tick_bitmap_address(&program_id, &first.pool, 0).0
+    );
+    assert_eq!(ix.accounts[second_start].pubkey, second.pool);
+    assert_eq!(ix.accounts[second_start + 2].pubkey, user_y);
+    assert_eq!(
+        CLMMInstruction::try_from_slice(&ix.data).unwrap(),
+        CLMMInstruction::SwapRoute {
+            amount_in: 1000,
+            minimum_amount_out: 950,
+            hops: vec![
+                RouteHop { zero_for_one: true, max_price_impact_bps: 100, bitmap_count: 2, tick_count: 1 },
+                RouteHop { zero_for_one: false, max_price_impact_bps: 200, bitmap_count: 1, tick_count: 0 },
+            ],
+        }
+    );
+}
+
+#[test]
+fn test_admin_builders_round_trip() {
+    let program_id = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let new_authority = Pubkey::new_unique();
+
+    let ix = instruction::propose_config_authority(&program_id, &authority, &pool, &new_authority);
+    assert_eq!(ix.accounts.len(), 2);
+    assert!(ix.accounts[0].is_signer);
+    assert!(ix.accounts[1].is_writable);
+    assert_eq!(
+        CLMMInstruction::try_from_slice(&ix.data).unwrap(),
+        CLMMInstruction::ProposeConfigAuthority { new_authority }
+    );
+
+    let ix = instruction::accept_config_authority(&program_id, &new_authority, &pool);
+    assert_eq!(CLMMInstruction::try_from_slice(&ix.data).unwrap(), CLMMInstruction::AcceptConfigAuthority);
+
+    let ix = instruction::set_fee_bounds(&program_id, &authority, &pool, 100, 5000, 60);
+    assert_eq!(
+        CLMMInstruction::try_from_slice(&ix.data).unwrap(),
+        CLMMInstruction::SetFeeBounds { min_fee: 100, max_fee: 5000, fee_adjustment_interval: 60 }
+    );
+}
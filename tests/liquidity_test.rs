@@ -1 +1,111 @@
 // Integration tests for liquidity functionality
+
+use clmm_rust::math::{FixedPointMath, LiquidityDistribution, Quoter};
+use clmm_rust::math::tick_math::{TickMath, I256, U256, Q96};
+use clmm_rust::state::{Pool, Tick};
+use solana_program::pubkey::Pubkey;
+use std::collections::BTreeMap;
+
+const WIDE: u128 = 1_000_000_000_000_000_000;
+const NARROW: u128 = 500_000_000_000_000_000;
+
+#[test]
+fn test_distribution_accumulates_liquidity_net() {
+    let (pool, ticks) = create_pool_with_positions();
+    let distribution = LiquidityDistribution::build(&pool, &ticks).unwrap();
+
+    // Only bands between initialized ticks; the open-ended ones hold nothing
+    let ranges: Vec<(i32, i32, U256)> = distribution
+        .bands
+        .iter()
+        .map(|band| (band.tick_lower, band.tick_upper, band.liquidity))
+        .collect();
+    assert_eq!(ranges, vec![
+        (-120, 60, U256::from(WIDE)),
+        (60, 120, U256::from(WIDE + NARROW)),
+        (120, 180, U256::from(NARROW)),
+    ]);
+
+    // Only the band around the price holds token1
+    assert!(distribution.bands[0].amount1 > U256::zero());
+    assert!(distribution.bands[1..].iter().all(|band| band.amount1.is_zero()));
+
+    // The bands hold what the two positions hold, to within rounding
+    let (wide0, wide1) = amounts_at_price(-120, 120, WIDE);
+    let (narrow0, narrow1) = amounts_at_price(60, 180, NARROW);
+    let (total0, total1) = distribution.total_amounts();
+    assert!(wide0 + narrow0 - total0 <= U256::from(3));
+    assert_eq!(total1, wide1 + narrow1);
+}
+
+#[test]
+fn test_distribution_from_price_on_tick() {
+    let (mut pool, ticks) = create_pool_with_positions();
+    let at_start = LiquidityDistribution::build(&pool, &ticks).unwrap();
+
+    // Sitting exactly on tick 60, the band above it is active
+    pool.sqrt_price_x96 = TickMath::get_sqrt_ratio_at_tick(60).unwrap();
+    pool.tick = 60;
+    pool.liquidity = U256::from(WIDE + NARROW);
+    let on_tick = LiquidityDistribution::build(&pool, &ticks).unwrap();
+
+    let liquidity = |d: &LiquidityDistribution| d.bands.iter().map(|band| band.liquidity).collect::<Vec<_>>();
+    assert_eq!(liquidity(&on_tick), liquidity(&at_start));
+
+    // A snapshot that drains more liquidity than is active is rejected
+    pool.liquidity = U256::from(NARROW);
+    assert!(LiquidityDistribution::build(&pool, &ticks).is_err());
+}
+
+#[test]
+fn test_depth_matches_quoted_swaps() {
+    let (pool, ticks) = create_pool_with_positions();
+    let distribution = LiquidityDistribution::build(&pool, &ticks).unwrap();
+    let curve = distribution.depth_curve(&[0, 50, 120, 10_000]).unwrap();
+
+    assert!(curve[0].amount0_above.is_zero() && curve[0].amount1_below.is_zero());
+    assert!(curve.windows(2).all(|w| w[0].amount0_above <= w[1].amount0_above));
+    assert!(curve.windows(2).all(|w| w[0].amount1_below <= w[1].amount1_below));
+
+    // Depth to +1.2% is what a buyer of token0 receives pushing the price there,
+    // across the tick at 60 where the second position joins
+    let level = &curve[2];
+    let price_ratio = FixedPointMath::sqrt_price_x96_to_price(level.sqrt_price_above_x96);
+    assert!((price_ratio - 1.012).abs() < 1e-9);
+    let quote = Quoter::quote_exact_input(&pool, &ticks, U256::from(WIDE), false, level.sqrt_price_above_x96).unwrap();
+    assert_eq!(quote.ticks_crossed, vec![60]);
+    assert!(level.amount0_above - quote.amount_out <= U256::from(2));
+
+    // Down to the minimum price, every token1 in the pool is available
+    let (_, total1) = distribution.total_amounts();
+    assert_eq!(curve[3].amount1_below, total1);
+}
+
+/// Pool at price 1 with a wide position over [-120, 120] in range and a narrow one over
+/// [60, 180] above it
+fn create_pool_with_positions() -> (Pool, BTreeMap<i32, Tick>) {
+    let mut pool = Pool::new(Pubkey::new_unique(), Pubkey::new_unique(), 300, 60, Q96).unwrap();
+    pool.liquidity = U256::from(WIDE);
+    pool.dynamic_fee_enabled = false;
+
+    let entries = [(-120, WIDE, false), (60, NARROW, false), (120, WIDE, true), (180, NARROW, true)];
+    let ticks = entries
+        .iter()
+        .map(|&(index, liquidity, upper)| {
+            let mut tick = Tick::new(index);
+            tick.update_liquidity(I256(U256::from(liquidity).0), upper).unwrap();
+            (index, tick)
+        })
+        .collect();
+    (pool, ticks)
+}
+
+fn amounts_at_price(tick_lower: i32, tick_upper: i32, liquidity: u128) -> (U256, U256) {
+    FixedPointMath::get_amounts_for_liquidity_at_price(
+        Q96,
+        TickMath::get_sqrt_ratio_at_tick(tick_lower).unwrap(),
+        TickMath::get_sqrt_ratio_at_tick(tick_upper).unwrap(),
+        U256::from(liquidity),
+    )
+    .unwrap()
+}
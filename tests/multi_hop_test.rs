@@ -1,6 +1,6 @@
 use clmm_rust::math::MultiHopRouter;
-use clmm_rust::state::Pool;
-use clmm_rust::math::tick_math::U256;
+use clmm_rust::state::{Pool, Tick};
+use clmm_rust::math::tick_math::{I256, U256, Q96};
 use solana_program::pubkey::Pubkey;
 
 #[test]
@@ -15,7 +15,7 @@ fn test_add_pool() {
     let mut router = MultiHopRouter::new();
     let pool = create_test_pool();
 
-    router.add_pool(pool);
+    router.add_pool(pool.clone());
 
     assert_eq!(router.pools.len(), 1);
     assert!(router.routing_graph.contains_key(&pool.token_a));
@@ -43,10 +43,234 @@ fn test_find_best_route() {
     assert!(!available_tokens.is_empty());
 }
 
+#[test]
+fn test_split_across_fee_tiers_beats_single_route() {
+    let mut router = MultiHopRouter::new();
+    let token_x = Pubkey::new_unique();
+    let token_y = Pubkey::new_unique();
+    let price = U256::from(79228162514264337593543950336u128); // 1:1
+
+    let mut cheap = Pool::new(token_x, token_y, 5, 10, price).unwrap();
+    cheap.liquidity = U256::from(1_000_000_000u64);
+    let mut deep = Pool::new(token_x, token_y, 30, 60, price).unwrap();
+    deep.liquidity = U256::from(3_000_000_000u64);
+    router.add_pool(cheap);
+    router.add_pool(deep);
+
+    let amount_in = U256::from(200_000_000u64);
+    let single = router.find_best_split(&token_x, &token_y, amount_in, 1, 1).unwrap();
+    let split = router.find_best_split(&token_x, &token_y, amount_in, 1, 20).unwrap();
+
+    assert_eq!(single.splits.len(), 1);
+    assert_eq!(split.splits.len(), 2);
+    assert!(split.amount_out > single.amount_out);
+
+    let allocated = split.splits.iter().fold(U256::zero(), |sum, s| sum + s.amount_in);
+    assert_eq!(allocated, amount_in);
+    assert_eq!(split.allocation_bps().iter().sum::<u32>(), 10000);
+    assert!(split.splits.iter().all(|s| s.route.fees.len() == 1 && s.amount_out > U256::zero()));
+}
+
+#[test]
+fn test_split_without_route_fails() {
+    let router = MultiHopRouter::new();
+    let result = router.find_best_split(&Pubkey::new_unique(), &Pubkey::new_unique(), U256::from(1000u64), 2, 4);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_quotes_cross_tick_snapshot() {
+    // Liquidity only between ticks -60 and 60
+    let liquidity = U256::from(1_000_000_000u64);
+    let pool = create_ranged_pool(liquidity);
+    let (token_in, token_out) = (pool.token_a, pool.token_b);
+
+    let mut flat = MultiHopRouter::new();
+    flat.add_pool(pool.clone());
+    let mut ranged = MultiHopRouter::new();
+    ranged.add_pool_with_ticks(pool, range_ticks(liquidity));
+
+    let amount_in = U256::from(100_000_000u64);
+    let flat_out = flat.find_best_split(&token_in, &token_out, amount_in, 1, 1).unwrap().amount_out;
+    let ranged_out = ranged.find_best_split(&token_in, &token_out, amount_in, 1, 1).unwrap().amount_out;
+
+    // The range holds about L * (1 - 1.0001^-30) of token B, far less than the flat curve gives
+    assert!(ranged_out < U256::from(3_000_000u64));
+    assert!(flat_out > ranged_out * U256::from(10));
+}
+
+#[test]
+fn test_execute_multi_hop_swap_persists_pool_state() {
+    let liquidity = U256::from(1_000_000_000u64);
+    let pool = create_ranged_pool(liquidity);
+    let key = (pool.token_a, pool.token_b, pool.fee);
+    let mut router = MultiHopRouter::new();
+    router.add_pool_with_ticks(pool, range_ticks(liquidity));
+
+    let amount_in = U256::from(100_000u64);
+    let route = router.find_best_route(&key.0, &key.1, amount_in, 1).unwrap();
+    let recipient = Pubkey::new_unique();
+
+    // A failed route leaves the router untouched
+    assert!(router
+        .execute_multi_hop_swap(&route, amount_in, amount_in * U256::from(2), &recipient, 1_000_000)
+        .is_err());
+    assert_eq!(router.pools[&key].sqrt_price_x96, Q96);
+    assert!(router.pool_states[&key].price_history.is_empty());
+
+    let first = router.execute_multi_hop_swap(&route, amount_in, U256::zero(), &recipient, 1_000_000).unwrap();
+    assert!(first.amount_out > U256::zero());
+    let price_after_first = router.pools[&key].sqrt_price_x96;
+    assert!(price_after_first < Q96);
+    let mut observations = router.pool_states[&key].oracle_observations.len();
+    assert!(observations > 0);
+
+    // Each later swap starts from the previous one's price and history
+    let mut price_before = price_after_first;
+    for timestamp in [1_000_060, 1_000_120, 1_000_180] {
+        router.execute_multi_hop_swap(&route, amount_in, U256::zero(), &recipient, timestamp).unwrap();
+        assert!(router.pools[&key].sqrt_price_x96 < price_before);
+        assert!(router.pool_states[&key].oracle_observations.len() > observations);
+        assert_eq!(router.pool_states[&key].oracle_observations.back().unwrap().timestamp, timestamp);
+        price_before = router.pools[&key].sqrt_price_x96;
+        observations = router.pool_states[&key].oracle_observations.len();
+    }
+}
+
+fn create_ranged_pool(liquidity: U256) -> Pool {
+    let mut pool = Pool::new(Pubkey::new_unique(), Pubkey::new_unique(), 30, 60, Q96).unwrap();
+    pool.liquidity = liquidity;
+    pool.dynamic_fee_enabled = false;
+    pool
+}
+
+fn range_ticks(liquidity: U256) -> Vec<Tick> {
+    [(-60, false), (60, true)]
+        .iter()
+        .map(|&(index, upper)| {
+            let mut tick = Tick::new(index);
+            tick.update_liquidity(I256(liquidity.0), upper).unwrap();
+            tick
+        })
+        .collect()
+}
+
+#[test]
+fn test_top_routes_rank_each_pool_separately() {
+    let mut router = MultiHopRouter::new();
+    let (token_x, token_y, token_z) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let deep = U256::from(1_000_000_000_000u64);
+
+    router.add_pool(create_liquid_pool(token_x, token_y, 5, deep));
+    router.add_pool(create_liquid_pool(token_x, token_y, 30, deep));
+    router.add_pool(create_liquid_pool(token_y, token_z, 5, deep));
+    router.add_pool(create_liquid_pool(token_x, token_z, 30, U256::from(1_000_000u64)));
+
+    let amount_in = U256::from(1_000_000u64);
+    let quotes = router.find_top_routes(&token_x, &token_z, amount_in, 2, 3).unwrap();
+
+    // Both X/Y fee tiers show up as separate routes, best first
+    assert_eq!(quotes.len(), 3);
+    assert!(quotes.windows(2).all(|pair| pair[0].amount_out >= pair[1].amount_out));
+    assert_eq!(quotes[0].route.path, vec![token_x, token_y, token_z]);
+    assert_eq!(quotes[0].route.fees, vec![5, 5]);
+    assert_eq!(quotes[1].route.fees, vec![30, 5]);
+    assert_eq!(quotes[2].route.path, vec![token_x, token_z]);
+
+    for quote in &quotes {
+        assert_eq!(quote.amount_in, amount_in);
+        assert_eq!(quote.hop_amounts_out.len(), quote.route.pools.len());
+        assert_eq!(*quote.hop_amounts_out.last().unwrap(), quote.amount_out);
+    }
+
+    let best = router.find_best_route(&token_x, &token_z, amount_in, 2).unwrap();
+    assert_eq!(best.pools, quotes[0].route.pools);
+
+    // A single hop only reaches the direct pool
+    let direct = router.find_top_routes(&token_x, &token_z, amount_in, 1, 3).unwrap();
+    assert_eq!(direct.len(), 1);
+    assert_eq!(direct[0].route.path, vec![token_x, token_z]);
+}
+
+#[test]
+fn test_paused_pool_drops_out_of_route_search() {
+    let mut router = MultiHopRouter::new();
+    let (token_x, token_y, token_z) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let deep = U256::from(1_000_000_000_000u64);
+
+    let mut paused = create_liquid_pool(token_x, token_y, 5, deep);
+    paused.unlocked = false;
+    router.add_pool(paused);
+    router.add_pool(create_liquid_pool(token_x, token_y, 30, deep));
+    router.add_pool(create_liquid_pool(token_y, token_z, 5, deep));
+
+    let amount_in = U256::from(1_000_000u64);
+    let quotes = router.find_top_routes(&token_x, &token_z, amount_in, 2, 3).unwrap();
+
+    assert_eq!(quotes.len(), 1);
+    assert_eq!(quotes[0].route.fees, vec![30, 5]);
+
+    let split = router.find_best_split(&token_x, &token_z, amount_in, 2, 4).unwrap();
+    assert_eq!(split.splits.len(), 1);
+    assert_eq!(split.amount_out, quotes[0].amount_out);
+}
+
+fn create_liquid_pool(token_a: Pubkey, token_b: Pubkey, fee: u32, liquidity: U256) -> Pool {
+    let mut pool = Pool::new(token_a, token_b, fee, 10, Q96).unwrap();
+    pool.liquidity = liquidity;
+    pool.dynamic_fee_enabled = false;
+    pool
+}
+
+#[test]
+fn test_arbitrage_between_mispriced_fee_tiers() {
+    let mut router = MultiHopRouter::new();
+    let (token_x, token_y) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let liquidity = U256::from(1_000_000_000_000u64);
+
+    // Same pair priced 1.0 in one tier and about 1.1 in the other
+    let cheap = create_liquid_pool(token_x, token_y, 5, liquidity);
+    let mut rich = create_liquid_pool(token_x, token_y, 30, liquidity);
+    rich.sqrt_price_x96 = Q96 * U256::from(1_048_809u64) / U256::from(1_000_000u64);
+    rich.tick = 953;
+    let base = cheap.token_a;
+    router.add_pool(cheap);
+    router.add_pool(rich);
+
+    let max_amount_in = U256::from(1_000_000_000_000u64);
+    let cycles = router.find_arbitrage_cycles(&base, 3, max_amount_in).unwrap();
+
+    // Only one direction around the two pools pays
+    assert_eq!(cycles.len(), 1);
+    let cycle = &cycles[0];
+    assert_eq!(cycle.quote.route.path, vec![base, cycle.quote.route.path[1], base]);
+    assert_eq!(cycle.quote.route.fees, vec![30, 5]);
+    assert_eq!(cycle.quote.amount_out, cycle.quote.amount_in + cycle.profit);
+    assert!(cycle.profit > U256::zero());
+
+    // The optimum is interior: neither a tiny nor the maximum input
+    assert!(cycle.quote.amount_in > max_amount_in / U256::from(1000));
+    assert!(cycle.quote.amount_in < max_amount_in);
+}
+
+#[test]
+fn test_no_arbitrage_between_aligned_pools() {
+    let mut router = MultiHopRouter::new();
+    let (token_x, token_y, token_z) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let liquidity = U256::from(1_000_000_000_000u64);
+
+    router.add_pool(create_liquid_pool(token_x, token_y, 5, liquidity));
+    router.add_pool(create_liquid_pool(token_y, token_z, 5, liquidity));
+    router.add_pool(create_liquid_pool(token_x, token_z, 30, liquidity));
+
+    let cycles = router.find_arbitrage_cycles(&token_x, 3, U256::from(1_000_000u64)).unwrap();
+    assert!(cycles.is_empty());
+}
+
 fn create_test_pool() -> Pool {
     let token_a = Pubkey::new_unique();
     let token_b = Pubkey::new_unique();
-    let initial_price = U256([1000000000000000000000000, 0, 0, 0]);
+    let initial_price = U256::from(1000000000000000000000000u128);
 
     Pool::new(token_a, token_b, 300, 60, initial_price).unwrap()
 }
@@ -54,7 +278,7 @@ fn create_test_pool() -> Pool {
 fn create_pool_ab() -> Pool {
     let token_a = Pubkey::new_unique();
     let token_b = Pubkey::new_unique();
-    let initial_price = U256([1000000000000000000000000, 0, 0, 0]);
+    let initial_price = U256::from(1000000000000000000000000u128);
 
     Pool::new(token_a, token_b, 300, 60, initial_price).unwrap()
 }
@@ -62,7 +286,7 @@ fn create_pool_ab() -> Pool {
 fn create_pool_bc() -> Pool {
     let token_b = Pubkey::new_unique();
     let token_c = Pubkey::new_unique();
-    let initial_price = U256([1000000000000000000000000, 0, 0, 0]);
+    let initial_price = U256::from(1000000000000000000000000u128);
 
     Pool::new(token_b, token_c, 300, 60, initial_price).unwrap()
 }
@@ -0,0 +1,108 @@
+use borsh::BorshDeserialize;
+use clmm_rust::math::mev_protection::{MevProtectionEngine, OracleObservation};
+use clmm_rust::math::tick_math::U256;
+use clmm_rust::state::Oracle;
+use solana_program::pubkey::Pubkey;
+use std::collections::VecDeque;
+
+fn observation(timestamp: u32) -> OracleObservation {
+    OracleObservation {
+        timestamp,
+        price: U256::from(timestamp as u64 * 1000),
+        tick: timestamp as i32,
+        liquidity: U256::from(1_000_000u64),
+    }
+}
+
+fn timestamps(oracle: &Oracle) -> Vec<u32> {
+    oracle.chronological().iter().map(|obs| obs.timestamp).collect()
+}
+
+#[test]
+fn test_oracle_single_slot_overwrites() {
+    let mut oracle = Oracle::new(Pubkey::new_unique());
+    assert!(oracle.latest().is_none());
+
+    oracle.write(observation(10));
+    oracle.write(observation(20));
+
+    assert_eq!(oracle.observation_count, 1);
+    assert_eq!(timestamps(&oracle), vec![20]);
+}
+
+#[test]
+fn test_oracle_ring_buffer_wraps() {
+    let mut oracle = Oracle::new(Pubkey::new_unique());
+    oracle.grow(3);
+
+    for timestamp in [10, 20, 30, 40, 50] {
+        oracle.write(observation(timestamp));
+    }
+
+    assert_eq!(oracle.cardinality, 3);
+    assert_eq!(oracle.observation_count, 3);
+    assert_eq!(timestamps(&oracle), vec![30, 40, 50]);
+    assert_eq!(oracle.latest().unwrap().timestamp, 50);
+}
+
+#[test]
+fn test_oracle_growth_applies_on_wrap() {
+    let mut oracle = Oracle::new(Pubkey::new_unique());
+    oracle.grow(2);
+    oracle.write(observation(10));
+    oracle.write(observation(20));
+    oracle.write(observation(30));
+    assert_eq!(timestamps(&oracle), vec![20, 30]);
+
+    // The ring is mid-cycle, so the new slots are only used after it wraps
+    oracle.grow(4);
+    assert_eq!(oracle.cardinality, 2);
+    oracle.write(observation(40));
+    assert_eq!(timestamps(&oracle), vec![30, 40]);
+
+    oracle.write(observation(50));
+    assert_eq!(oracle.cardinality, 4);
+    assert_eq!(timestamps(&oracle), vec![30, 40, 50]);
+
+    oracle.write(observation(60));
+    oracle.write(observation(70));
+    assert_eq!(timestamps(&oracle), vec![40, 50, 60, 70]);
+}
+
+#[test]
+fn test_oracle_same_timestamp_replaces_latest() {
+    let mut oracle = Oracle::new(Pubkey::new_unique());
+    oracle.grow(4);
+    oracle.write(observation(10));
+
+    let mut updated = observation(10);
+    updated.tick = -5;
+    oracle.write(updated);
+
+    assert_eq!(oracle.observation_count, 1);
+    assert_eq!(oracle.latest().unwrap().tick, -5);
+}
+
+#[test]
+fn test_oracle_serialization_matches_space() {
+    let mut oracle = Oracle::new(Pubkey::new_unique());
+    oracle.grow(5);
+    oracle.write(observation(10));
+    oracle.write(observation(20));
+
+    let bytes = borsh::to_vec(&oracle).unwrap();
+    assert_eq!(bytes.len(), Oracle::space(5));
+    assert_eq!(Oracle::try_from_slice(&bytes).unwrap(), oracle);
+}
+
+#[test]
+fn test_twap_of_single_observation_is_its_price() {
+    let observations: VecDeque<OracleObservation> = [observation(10)].into_iter().collect();
+    assert_eq!(MevProtectionEngine::calculate_twap(&observations, 300).unwrap(), U256::from(10_000u64));
+
+    // Only the latest observation falls inside the window
+    let observations: VecDeque<OracleObservation> = [observation(10), observation(500)].into_iter().collect();
+    assert_eq!(MevProtectionEngine::calculate_twap(&observations, 300).unwrap(), U256::from(500_000u64));
+
+    assert!(MevProtectionEngine::calculate_twap(&VecDeque::new(), 300).is_err());
+}
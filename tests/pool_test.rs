@@ -1,3 +1,4 @@
+use borsh::BorshDeserialize;
 use clmm_rust::state::Pool;
 use clmm_rust::math::tick_math::U256;
 use solana_program::pubkey::Pubkey;
@@ -6,7 +7,7 @@ use solana_program::pubkey::Pubkey;
 fn test_pool_creation() {
     let token_a = Pubkey::new_unique();
     let token_b = Pubkey::new_unique();
-    let initial_price = U256([1000000000000000000000000, 0, 0, 0]);
+    let initial_price = U256::from(1000000000000000000000000u128);
 
     let pool = Pool::new(token_a, token_b, 300, 60, initial_price).unwrap();
     assert!(pool.is_valid());
@@ -18,7 +19,7 @@ fn test_pool_creation() {
 fn test_token_sorting() {
     let token_a = Pubkey::new_unique();
     let token_b = Pubkey::new_unique();
-    let initial_price = U256([1000000000000000000000000, 0, 0, 0]);
+    let initial_price = U256::from(1000000000000000000000000u128);
 
     let pool = Pool::new(token_b, token_a, 300, 60, initial_price).unwrap();
     assert!(pool.token_a < pool.token_b);
@@ -28,7 +29,7 @@ fn test_token_sorting() {
 fn test_tick_validation() {
     let token_a = Pubkey::new_unique();
     let token_b = Pubkey::new_unique();
-    let initial_price = U256([1000000000000000000000000, 0, 0, 0]);
+    let initial_price = U256::from(1000000000000000000000000u128);
 
     let pool = Pool::new(token_a, token_b, 300, 60, initial_price).unwrap();
 
@@ -38,3 +39,106 @@ fn test_tick_validation() {
 
     assert!(pool.validate_tick_range(-50, 60).is_err());
 }
+
+#[test]
+fn test_protocol_fee_split() {
+    let token_a = Pubkey::new_unique();
+    let token_b = Pubkey::new_unique();
+    let initial_price = U256::from(1000000000000000000000000u128);
+
+    let mut pool = Pool::new(token_a, token_b, 300, 60, initial_price).unwrap();
+    assert_eq!(pool.protocol_fee_percent, 0);
+    assert_eq!(pool.split_protocol_fee(U256::from(1000u64)), (U256::from(1000u64), U256::zero()));
+
+    pool.protocol_fee_percent = 25;
+    assert_eq!(pool.split_protocol_fee(U256::from(1000u64)), (U256::from(750u64), U256::from(250u64)));
+
+    // The protocol share rounds down in favour of liquidity providers
+    assert_eq!(pool.split_protocol_fee(U256::from(3u64)), (U256::from(3u64), U256::zero()));
+
+    // The setting survives a serialization round trip
+    let bytes = borsh::to_vec(&pool).unwrap();
+    let decoded = Pool::try_from_slice(&bytes).unwrap();
+    assert_eq!(decoded.protocol_fee_percent, 25);
+    assert_eq!(decoded, pool);
+}
+
+#[test]
+fn test_fee_bounds_and_config_authority() {
+    let token_a = Pubkey::new_unique();
+    let token_b = Pubkey::new_unique();
+    let initial_price = U256::from(1000000000000000000000000u128);
+
+    let mut pool = Pool::new(token_a, token_b, 300, 60, initial_price).unwrap();
+
+    // Nobody administers a pool until an authority is set
+    assert!(!pool.is_config_authority(&Pubkey::default()));
+    let admin = Pubkey::new_unique();
+    pool.config_authority = admin;
+    assert!(pool.is_config_authority(&admin));
+    assert!(!pool.is_config_authority(&Pubkey::new_unique()));
+
+    // Invalid bounds leave the pool untouched
+    assert!(pool.set_fee_bounds(50, 10, 3600).is_err());
+    assert!(pool.set_fee_bounds(10, 20000, 3600).is_err());
+    assert!(pool.set_fee_bounds(10, 50, 0).is_err());
+    assert_eq!((pool.min_fee, pool.max_fee), (1, 100));
+
+    // Narrowing the bounds pulls the fee inside them
+    pool.set_fee_bounds(10, 50, 600).unwrap();
+    assert_eq!((pool.min_fee, pool.max_fee, pool.fee_adjustment_interval), (10, 50, 600));
+    assert_eq!(pool.fee, 50);
+    assert_eq!(pool.base_fee, 50);
+
+    let bytes = borsh::to_vec(&pool).unwrap();
+    assert_eq!(Pool::try_from_slice(&bytes).unwrap(), pool);
+}
+
+#[test]
+fn test_flash_fee_rounds_up() {
+    let token_a = Pubkey::new_unique();
+    let token_b = Pubkey::new_unique();
+    let pool = Pool::new(token_a, token_b, 30, 60, U256::from(1u128 << 96)).unwrap();
+
+    assert_eq!(pool.flash_fee(0), 0);
+    assert_eq!(pool.flash_fee(1), 1);
+    assert_eq!(pool.flash_fee(10_000), 30);
+    assert_eq!(pool.flash_fee(10_001), 31);
+    assert_eq!(pool.flash_fee(u64::MAX), (u64::MAX as u128 * 30).div_ceil(10000) as u64);
+}
+
+#[test]
+fn test_token_accounts_must_match_pool() {
+    let token_a = Pubkey::new_unique();
+    let token_b = Pubkey::new_unique();
+    let mut pool = Pool::new(token_a, token_b, 30, 60, U256::from(1u128 << 96)).unwrap();
+    let (mint_a, mint_b) = (pool.token_a, pool.token_b);
+    let token_2022 = clmm_rust::utils::TOKEN_2022_PROGRAM_ID;
+
+    assert!(pool.validate_token_accounts(&spl_token::id(), &spl_token::id(), &mint_a, &mint_b).is_ok());
+    assert!(pool.validate_token_accounts(&spl_token::id(), &spl_token::id(), &mint_b, &mint_a).is_err());
+
+    pool.token_program_b = token_2022;
+    assert!(pool.validate_token_accounts(&spl_token::id(), &spl_token::id(), &mint_a, &mint_b).is_err());
+    assert!(pool.validate_token_accounts(&spl_token::id(), &token_2022, &mint_a, &mint_b).is_ok());
+
+    // The token programs survive a round trip through the account data
+    let data = borsh::to_vec(&pool).unwrap();
+    let decoded = Pool::deserialize(&mut &data[..]).unwrap();
+    assert_eq!(decoded.token_program_a, spl_token::id());
+    assert_eq!(decoded.token_program_b, token_2022);
+}
+
+#[test]
+fn test_flash_guard_is_stored_apart_from_pause_state() {
+    let mut pool = Pool::new(Pubkey::new_unique(), Pubkey::new_unique(), 30, 60, U256::from(1u128 << 96)).unwrap();
+    let size = borsh::to_vec(&pool).unwrap().len();
+    pool.flash_in_progress = true;
+
+    // Carved out of the reserved bytes, so the account size is unchanged
+    let data = borsh::to_vec(&pool).unwrap();
+    assert_eq!(data.len(), size);
+    let decoded = Pool::deserialize(&mut &data[..]).unwrap();
+    assert!(decoded.flash_in_progress);
+    assert!(decoded.unlocked);
+}
@@ -0,0 +1,124 @@
+use clmm_rust::math::{FixedPointMath, PositionAnalyzer, PositionEntry, TickMath};
+use clmm_rust::math::price_impact::PriceImpactCalculator;
+use clmm_rust::math::tick_math::{U256, Q96};
+use clmm_rust::state::{Pool, Position, Tick};
+use solana_program::pubkey::Pubkey;
+
+const LIQUIDITY: u64 = 1_000_000_000_000;
+const DAY: u32 = 86_400;
+
+#[test]
+fn test_report_after_price_move_and_fees() {
+    let (mut pool, position, ticks) = open_in_range_position();
+    let entry = PositionEntry::from(&position);
+
+    // The price rises about 3% and 1000 of token0 in fees accrue to the range
+    move_price(&mut pool, 300);
+    pool.fee_growth_global0_x128 = (U256::one() << 128) * U256::from(1000) / U256::from(LIQUIDITY);
+
+    let report = PositionAnalyzer::analyze(&position, &pool, &entry, &ticks.0, &ticks.1, entry.timestamp + DAY).unwrap();
+
+    // Token0 was sold into the rise
+    assert!(report.amount0 < U256::from(entry.amount0));
+    assert!(report.amount1 > U256::from(entry.amount1));
+    assert!(report.fees_owed0 >= U256::from(999) && report.fees_owed0 <= U256::from(1000));
+
+    // Holding would have done slightly better, before fees
+    assert!(report.divergence_loss < 0.0);
+    assert!(report.divergence_loss > -0.01);
+    assert!(report.value.in_token1 < report.hold_value.in_token1 + 1100.0);
+
+    // Nothing withdrawn yet: all PnL is unrealized, measured against the entry price
+    assert_eq!(report.realized_pnl.in_token1, 0.0);
+    let cost = (entry.amount0 + entry.amount1) as f64;
+    assert!((report.unrealized_pnl.in_token1 - (report.value.in_token1 - cost)).abs() < 1e-3);
+
+    // About 1000 of fees on the hold value in one day, annualized
+    let price = FixedPointMath::sqrt_price_x96_to_price(pool.sqrt_price_x96);
+    let expected_apr = 1000.0 * price / report.hold_value.in_token1 * 365.0;
+    assert!((report.fee_apr - expected_apr).abs() / expected_apr < 0.01);
+}
+
+#[test]
+fn test_withdrawal_moves_pnl_to_realized() {
+    let (pool, mut position, ticks) = open_in_range_position();
+    let entry = PositionEntry::from(&position);
+
+    // Take half the liquidity out at the entry price
+    let half = U256::from(LIQUIDITY / 2);
+    position.liquidity = half;
+    position.record_withdrawal(entry.amount0 / 2, entry.amount1 / 2);
+
+    let report = PositionAnalyzer::analyze(&position, &pool, &entry, &ticks.0, &ticks.1, entry.timestamp).unwrap();
+
+    // At an unchanged price nothing is gained or lost on either half
+    let cost = (entry.amount0 + entry.amount1) as f64;
+    assert!(report.realized_pnl.in_token1.abs() < cost * 1e-9 + 2.0);
+    assert!(report.unrealized_pnl.in_token0.abs() < cost * 1e-9 + 2.0);
+    assert!(report.divergence_loss.abs() < 1e-9);
+    assert_eq!(report.fee_apr, 0.0);
+}
+
+#[test]
+fn test_cost_basis_prices_each_deposit() {
+    let (mut pool, mut position, ticks) = open_in_range_position();
+    let first_cost = (position.amount0_deposited + position.amount1_deposited) as f64;
+
+    // Double the liquidity after the price has risen about 3%
+    move_price(&mut pool, 300);
+    let (amount0, amount1) = FixedPointMath::get_amounts_for_liquidity_at_price(
+        pool.sqrt_price_x96,
+        TickMath::get_sqrt_ratio_at_tick(-600).unwrap(),
+        TickMath::get_sqrt_ratio_at_tick(600).unwrap(),
+        U256::from(LIQUIDITY),
+    )
+    .unwrap();
+    position.liquidity = U256::from(2 * LIQUIDITY);
+    position.record_deposit(U256::from(LIQUIDITY), amount0.low_u64(), amount1.low_u64(), pool.sqrt_price_x96).unwrap();
+    let entry = PositionEntry::from(&position);
+
+    let report = PositionAnalyzer::analyze(&position, &pool, &entry, &ticks.0, &ticks.1, entry.timestamp).unwrap();
+
+    // The second deposit cost what it was worth when made, not at the entry price
+    let price = FixedPointMath::sqrt_price_x96_to_price(pool.sqrt_price_x96);
+    let cost = first_cost + amount0.low_u64() as f64 * price + amount1.low_u64() as f64;
+    assert!((report.unrealized_pnl.in_token1 - (report.value.in_token1 - cost)).abs() < cost * 1e-9 + 2.0);
+}
+
+#[test]
+fn test_impermanent_loss_uses_entry_price() {
+    let lower = TickMath::get_sqrt_ratio_at_tick(-600).unwrap();
+    let upper = TickMath::get_sqrt_ratio_at_tick(600).unwrap();
+    let moved = TickMath::get_sqrt_ratio_at_tick(300).unwrap();
+    let liquidity = U256::from(LIQUIDITY);
+
+    let unchanged = PriceImpactCalculator::calculate_impermanent_loss(lower, upper, Q96, Q96, liquidity).unwrap();
+    let loss = PriceImpactCalculator::calculate_impermanent_loss(lower, upper, Q96, moved, liquidity).unwrap();
+
+    assert!(unchanged.abs() < 1e-9);
+    assert!(loss < 0.0);
+}
+
+/// Position over [-600, 600] holding `LIQUIDITY`, opened at price 1
+fn open_in_range_position() -> (Pool, Position, (Tick, Tick)) {
+    let mut pool = Pool::new(Pubkey::new_unique(), Pubkey::new_unique(), 30, 60, Q96).unwrap();
+    pool.liquidity = U256::from(LIQUIDITY);
+
+    let mut position = Position::new(Pubkey::new_unique(), Pubkey::new_unique(), -600, 600, 1, 1_000).unwrap();
+    let (amount0, amount1) = FixedPointMath::get_amounts_for_liquidity_at_price(
+        pool.sqrt_price_x96,
+        TickMath::get_sqrt_ratio_at_tick(-600).unwrap(),
+        TickMath::get_sqrt_ratio_at_tick(600).unwrap(),
+        U256::from(LIQUIDITY),
+    )
+    .unwrap();
+    position.liquidity = U256::from(LIQUIDITY);
+    position.record_deposit(position.liquidity, amount0.low_u64(), amount1.low_u64(), pool.sqrt_price_x96).unwrap();
+
+    (pool, position, (Tick::new_initialized(-600), Tick::new_initialized(600)))
+}
+
+fn move_price(pool: &mut Pool, tick: i32) {
+    pool.sqrt_price_x96 = TickMath::get_sqrt_ratio_at_tick(tick).unwrap();
+    pool.tick = tick;
+}
@@ -1,6 +1,6 @@
-use clmm_rust::state::{Position, PositionInfo};
-use clmm_rust::math::tick_math::U256;
-use clmm_rust::math::fixed_point::U256_ZERO;
+use borsh::BorshDeserialize;
+use clmm_rust::state::{Position, PositionInfo, RangeOrder};
+use clmm_rust::math::tick_math::{U256, U256_ZERO, Q96};
 use solana_program::pubkey::Pubkey;
 
 #[test]
@@ -47,7 +47,19 @@ fn test_position_validation() {
         created_at: 1000,
         updated_at: 1000,
         is_active: true,
-        reserved: [0; 256],
+        position_mint: Pubkey::default(),
+        range_order: RangeOrder::None,
+        range_order_epoch: 0,
+        entry_sqrt_price_x96: U256_ZERO,
+        liquidity_added: U256_ZERO,
+        amount0_deposited: 0,
+        amount1_deposited: 0,
+        amount0_withdrawn: 0,
+        amount1_withdrawn: 0,
+        fees0_collected: 0,
+        fees1_collected: 0,
+        deposit_value1: U256_ZERO,
+        reserved: [0; 75],
     };
 
     assert!(!position.is_valid());
@@ -85,6 +97,30 @@ fn test_position_operations() {
     assert_eq!(position.tokens_owed1, U256([50, 0, 0, 0]));
 }
 
+#[test]
+fn test_position_fees_earned() {
+    let mut position = Position::new(Pubkey::new_unique(), Pubkey::new_unique(), -100, 100, 1, 1000).unwrap();
+    position.update_liquidity(U256::from(1_000_000u64), 1000);
+
+    let q128 = U256::one() << 128;
+    position.update_fee_growth(q128, U256_ZERO, 1000);
+
+    // Fee growth of 3 (Q128) per unit of liquidity since the checkpoint on token0
+    let (fees_0, fees_1) = position.fees_earned(q128 * U256::from(4u64), U256_ZERO).unwrap();
+    assert_eq!(fees_0, U256::from(3_000_000u64));
+    assert_eq!(fees_1, U256_ZERO);
+
+    // Growth measured across an accumulator wrap is still the difference
+    position.update_fee_growth(U256::MAX - q128 + U256::one(), U256_ZERO, 2000);
+    let (fees_0, _) = position.fees_earned(q128, U256_ZERO).unwrap();
+    assert_eq!(fees_0, U256::from(2_000_000u64));
+
+    // Growth too large to pay out is an error, not an unlimited credit
+    position.update_liquidity(U256::one() << 200, 3000);
+    position.update_fee_growth(U256_ZERO, U256_ZERO, 3000);
+    assert!(position.fees_earned(U256::one() << 200, U256_ZERO).is_err());
+}
+
 #[test]
 fn test_position_info() {
     let pool_id = Pubkey::new_unique();
@@ -106,3 +142,66 @@ fn test_position_info() {
     assert_eq!(info.tick_upper, 100);
     assert!(info.is_active);
 }
+
+#[test]
+fn test_position_mint_round_trip() {
+    let mut position = Position::new(Pubkey::new_unique(), Pubkey::new_unique(), -100, 100, 1, 1000).unwrap();
+    assert_eq!(position.position_mint, Pubkey::default());
+
+    position.position_mint = Pubkey::new_unique();
+    let data = borsh::to_vec(&position).unwrap();
+
+    let decoded = Position::try_from_slice(&data).unwrap();
+    assert_eq!(decoded, position);
+    assert_eq!(PositionInfo::from(&decoded).position_mint, position.position_mint);
+}
+
+#[test]
+fn test_range_order_completion_tick() {
+    let mut position = Position::new(Pubkey::new_unique(), Pubkey::new_unique(), 60, 120, 1, 1000).unwrap();
+    assert!(!position.is_range_order());
+    assert_eq!(position.range_order_completion_tick(), None);
+
+    position.range_order = RangeOrder::SellToken0;
+    assert_eq!(position.range_order_completion_tick(), Some(120));
+    position.range_order = RangeOrder::SellToken1;
+    assert_eq!(position.range_order_completion_tick(), Some(60));
+
+    position.range_order_epoch = 3;
+    let decoded = Position::try_from_slice(&borsh::to_vec(&position).unwrap()).unwrap();
+    assert_eq!(decoded, position);
+}
+
+#[test]
+fn test_position_entry_round_trip() {
+    let mut position = Position::new(Pubkey::new_unique(), Pubkey::new_unique(), -100, 100, 1, 1000).unwrap();
+
+    // Only the first deposit sets the entry price
+    position.record_deposit(U256::from(1_000u64), 50, 60, U256::from(7u64)).unwrap();
+    position.record_deposit(U256::from(500u64), 25, 30, U256::from(9u64)).unwrap();
+    position.record_withdrawal(10, 20);
+    position.record_fees_collected(3, 4);
+
+    assert_eq!(position.entry_sqrt_price_x96, U256::from(7u64));
+    assert_eq!(position.liquidity_added, U256::from(1_500u64));
+    assert_eq!((position.amount0_deposited, position.amount1_deposited), (75, 90));
+
+    let data = borsh::to_vec(&position).unwrap();
+    assert_eq!(data.len(), borsh::to_vec(&Position::new(Pubkey::new_unique(), Pubkey::new_unique(), -1, 1, 0, 0).unwrap()).unwrap().len());
+    let decoded = Position::try_from_slice(&data).unwrap();
+    assert_eq!(decoded, position);
+    assert_eq!((decoded.amount0_withdrawn, decoded.amount1_withdrawn), (10, 20));
+    assert_eq!((decoded.fees0_collected, decoded.fees1_collected), (3, 4));
+}
+
+#[test]
+fn test_deposit_value_prices_each_deposit() {
+    let mut position = Position::new(Pubkey::new_unique(), Pubkey::new_unique(), -100, 100, 1, 1000).unwrap();
+
+    // 1000 + 1000 at price 1, then 1000 of token0 at price 4
+    position.record_deposit(U256::from(1_000u64), 1_000, 1_000, Q96).unwrap();
+    position.record_deposit(U256::from(1_000u64), 1_000, 0, Q96 * U256::from(2u64)).unwrap();
+
+    assert_eq!(position.entry_sqrt_price_x96, Q96);
+    assert_eq!(position.deposit_value1, U256::from(6_000u64));
+}
@@ -1,7 +1,9 @@
-use clmm_rust::math::price_impact::{PriceImpactCalculator, ImpactSeverity, U256_ZERO};
-use clmm_rust::state::Pool;
-use clmm_rust::math::tick_math::U256;
+use clmm_rust::math::price_impact::{PriceImpactCalculator, ImpactSeverity};
+use clmm_rust::math::{FixedPointMath, Quoter};
+use clmm_rust::state::{Pool, Tick};
+use clmm_rust::math::tick_math::{I256, U256, Q96, U256_ZERO, MAX_SQRT_RATIO, MIN_SQRT_RATIO};
 use solana_program::pubkey::Pubkey;
+use std::collections::BTreeMap;
 
 #[test]
 fn test_price_impact_calculation() {
@@ -23,6 +25,59 @@ fn test_optimal_swap_amount() {
     assert!(optimal_amount > U256_ZERO);
 }
 
+#[test]
+fn test_max_amount_for_impact_crosses_ticks_exactly() {
+    let (pool, ticks) = create_ranged_pool();
+
+    // Pushing the price up 1.2% crosses tick 60, where the second range joins
+    let quote = PriceImpactCalculator::max_amount_for_impact(&pool, &ticks, 120, false).unwrap();
+    assert_eq!(quote.ticks_crossed, vec![60]);
+    let price = FixedPointMath::sqrt_price_x96_to_price(quote.sqrt_price_after_x96);
+    assert!((price - 1.012).abs() < 1e-9);
+
+    // Swapping that input with the target as the limit spends all of it landing there
+    let target = quote.sqrt_price_after_x96;
+    let swapped = Quoter::quote_exact_input(&pool, &ticks, quote.amount_in, false, target).unwrap();
+    assert_eq!(swapped.amount_in, quote.amount_in);
+    assert_eq!(swapped.amount_out, quote.amount_out);
+    assert_eq!(swapped.fee_amount, quote.fee_amount);
+
+    // Without the limit, fee rounding leaves at most a sliver past the target
+    let unlimited = Quoter::quote_exact_input(&pool, &ticks, quote.amount_in, false, MAX_SQRT_RATIO - U256::one()).unwrap();
+    assert!(unlimited.sqrt_price_after_x96 >= target);
+    assert!(unlimited.amount_out - quote.amount_out <= U256::from(10));
+
+    // The same in the other direction, through the bottom of the wide range
+    let quote = PriceImpactCalculator::max_amount_for_impact(&pool, &ticks, 200, true).unwrap();
+    assert_eq!(quote.ticks_crossed, vec![-120]);
+    assert_eq!(quote.liquidity_after, U256_ZERO);
+    let price = FixedPointMath::sqrt_price_x96_to_price(quote.sqrt_price_after_x96);
+    assert!((price - 0.98).abs() < 1e-9);
+
+    let swapped = Quoter::quote_exact_input(&pool, &ticks, quote.amount_in, true, quote.sqrt_price_after_x96).unwrap();
+    assert_eq!(swapped.amount_in, quote.amount_in);
+    assert_eq!(swapped.amount_out, quote.amount_out);
+
+    // With nothing left below -120, the whole price range is open
+    let floor = PriceImpactCalculator::max_amount_for_impact(&pool, &ticks, 10000, true).unwrap();
+    assert_eq!(floor.sqrt_price_after_x96, MIN_SQRT_RATIO + U256::one());
+    assert_eq!(floor.amount_in, quote.amount_in);
+}
+
+#[test]
+fn test_slippage_curve() {
+    let (pool, ticks) = create_ranged_pool();
+    let curve = PriceImpactCalculator::calculate_slippage_curve(&pool, &ticks, &[0, 10, 50, 100, 120], false).unwrap();
+
+    assert_eq!(curve[0].amount_in, U256_ZERO);
+    assert_eq!(curve[0].sqrt_price_x96, pool.sqrt_price_x96);
+    assert!(curve.windows(2).all(|w| w[0].amount_in < w[1].amount_in && w[0].amount_out < w[1].amount_out));
+
+    // Past tick 60 the deeper liquidity makes each basis point cost more
+    let per_bps = |i: usize, j: usize| (curve[j].amount_in - curve[i].amount_in) / U256::from(curve[j].impact_bps - curve[i].impact_bps);
+    assert!(per_bps(3, 4) > per_bps(2, 3));
+}
+
 #[test]
 fn test_impact_severity_classification() {
     assert_eq!(
@@ -50,7 +105,28 @@ fn test_impact_severity_classification() {
 fn create_test_pool() -> Pool {
     let token_a = Pubkey::new_unique();
     let token_b = Pubkey::new_unique();
-    let initial_price = U256([1000000000000000000000000, 0, 0, 0]); // 1e21
+    let initial_price = Q96; // price 1.0
+
+    let mut pool = Pool::new(token_a, token_b, 300, 60, initial_price).unwrap();
+    pool.liquidity = U256::from(1_000_000_000_000_000_000u128);
+    pool
+}
+
+/// Pool at price 1 with liquidity over [-120, 120] and more over [60, 180]
+fn create_ranged_pool() -> (Pool, BTreeMap<i32, Tick>) {
+    let wide = U256::from(1_000_000_000_000_000_000u128);
+    let narrow = U256::from(500_000_000_000_000_000u128);
+    let mut pool = Pool::new(Pubkey::new_unique(), Pubkey::new_unique(), 30, 60, Q96).unwrap();
+    pool.liquidity = wide;
 
-    Pool::new(token_a, token_b, 300, 60, initial_price).unwrap()
+    let entries = [(-120, wide, false), (60, narrow, false), (120, wide, true), (180, narrow, true)];
+    let ticks = entries
+        .iter()
+        .map(|&(index, liquidity, upper)| {
+            let mut tick = Tick::new(index);
+            tick.update_liquidity(I256(liquidity.0), upper).unwrap();
+            (index, tick)
+        })
+        .collect();
+    (pool, ticks)
 }
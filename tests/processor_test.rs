@@ -0,0 +1,286 @@
+use borsh::BorshDeserialize;
+use clmm_rust::instruction::{self, PoolTokens, SwapAccounts, SwapArgs};
+use clmm_rust::math::mev_protection::OracleObservation;
+use clmm_rust::math::tick_math::{U256, Q96};
+use clmm_rust::process_instruction;
+use clmm_rust::state::{Oracle, Pool};
+use clmm_rust::utils::{
+    derive_oracle_address, derive_pool_authority_address, derive_pool_vault_a_address,
+    derive_pool_vault_b_address,
+};
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::Clock;
+use solana_program::entrypoint::{self, ProgramResult, MAX_PERMITTED_DATA_INCREASE};
+use solana_program::instruction::Instruction;
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::Pack;
+use solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use solana_program::program_utils::limited_deserialize;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_instruction::SystemInstruction;
+use solana_program::{bpf_loader, system_program};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Once;
+
+const LIQUIDITY: u128 = 1_000_000_000_000;
+const BALANCE: u64 = 1_000_000_000_000;
+
+static NOW: AtomicI64 = AtomicI64::new(0);
+static STUBS: Once = Once::new();
+
+/// Syscalls for running the program natively: a settable clock, default rent, and
+/// CPIs into the SPL Token and System programs
+struct TestSyscalls;
+
+impl SyscallStubs for TestSyscalls {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { unix_timestamp: NOW.load(Ordering::SeqCst), ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let accounts: Vec<AccountInfo> = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let mut account = account_infos
+                    .iter()
+                    .find(|account| account.key == &meta.pubkey)
+                    .expect("CPI account not passed")
+                    .clone();
+                account.is_signer |= meta.is_signer;
+                account
+            })
+            .collect();
+
+        if instruction.program_id == spl_token::id() {
+            spl_token::processor::Processor::process(&instruction.program_id, &accounts, &instruction.data)
+        } else if instruction.program_id == system_program::id() {
+            match limited_deserialize(&instruction.data, 1232).map_err(|_| ProgramError::InvalidInstructionData)? {
+                SystemInstruction::Transfer { lamports } => {
+                    **accounts[0].try_borrow_mut_lamports()? -= lamports;
+                    **accounts[1].try_borrow_mut_lamports()? += lamports;
+                    Ok(())
+                }
+                _ => Err(ProgramError::InvalidInstructionData),
+            }
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+}
+
+struct TestAccount {
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    executable: bool,
+}
+
+/// Account store that runs instructions through the program entrypoint
+struct TestLedger {
+    program_id: Pubkey,
+    accounts: HashMap<Pubkey, TestAccount>,
+}
+
+impl TestLedger {
+    fn new(program_id: Pubkey) -> Self {
+        STUBS.call_once(|| {
+            set_syscall_stubs(Box::new(TestSyscalls));
+        });
+        TestLedger { program_id, accounts: HashMap::new() }
+    }
+
+    fn set(&mut self, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        let lamports = Rent::default().minimum_balance(data.len()).max(1_000_000_000);
+        self.accounts.insert(key, TestAccount { owner, lamports, data, executable: false });
+    }
+
+    fn data(&self, key: &Pubkey) -> &[u8] {
+        &self.accounts[key].data
+    }
+
+    /// Serialize the accounts the way the runtime does, run the instruction, and keep
+    /// its account changes if it succeeds
+    fn process(&mut self, ix: &Instruction) -> ProgramResult {
+        assert_eq!(ix.program_id, self.program_id);
+        let empty = TestAccount { owner: system_program::id(), lamports: 0, data: Vec::new(), executable: false };
+
+        let mut input = Vec::new();
+        input.extend_from_slice(&(ix.accounts.len() as u64).to_le_bytes());
+        for (i, meta) in ix.accounts.iter().enumerate() {
+            if let Some(first) = ix.accounts[..i].iter().position(|other| other.pubkey == meta.pubkey) {
+                input.push(first as u8);
+                input.extend_from_slice(&[0; 7]);
+                continue;
+            }
+            let account = self.accounts.get(&meta.pubkey).unwrap_or(&empty);
+            input.extend_from_slice(&[u8::MAX, meta.is_signer as u8, meta.is_writable as u8, account.executable as u8]);
+            input.extend_from_slice(&[0; 4]);
+            input.extend_from_slice(meta.pubkey.as_ref());
+            input.extend_from_slice(account.owner.as_ref());
+            input.extend_from_slice(&account.lamports.to_le_bytes());
+            input.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            input.extend_from_slice(&account.data);
+            input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            input.resize(input.len().next_multiple_of(8), 0);
+            input.extend_from_slice(&0u64.to_le_bytes());
+        }
+        input.extend_from_slice(&(ix.data.len() as u64).to_le_bytes());
+        input.extend_from_slice(&ix.data);
+        input.extend_from_slice(ix.program_id.as_ref());
+
+        // Keep the buffer 8-byte aligned for the loader's u64 reads
+        let mut aligned = vec![0u64; input.len().div_ceil(8)];
+        let buffer = aligned.as_mut_ptr() as *mut u8;
+        unsafe { std::ptr::copy_nonoverlapping(input.as_ptr(), buffer, input.len()) };
+        let (program_id, account_infos, instruction_data) = unsafe { entrypoint::deserialize(buffer) };
+
+        process_instruction(program_id, &account_infos, instruction_data)?;
+
+        for account in &account_infos {
+            self.accounts.insert(*account.key, TestAccount {
+                owner: *account.owner,
+                lamports: account.lamports(),
+                data: account.data.borrow().to_vec(),
+                executable: account.executable,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn pack<T: Pack>(value: T) -> Vec<u8> {
+    let mut data = vec![0; T::LEN];
+    value.pack_into_slice(&mut data);
+    data
+}
+
+fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Vec<u8> {
+    pack(spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    })
+}
+
+fn token_balance(ledger: &TestLedger, key: &Pubkey) -> u64 {
+    spl_token::state::Account::unpack(ledger.data(key)).unwrap().amount
+}
+
+/// A pool at price 1 with in-range liquidity and funded vaults, and a user holding both tokens
+fn setup() -> (TestLedger, Pubkey, Pubkey, SwapAccounts) {
+    let program_id = Pubkey::new_unique();
+    let mut ledger = TestLedger::new(program_id);
+
+    let mut pool = Pool::new(Pubkey::new_unique(), Pubkey::new_unique(), 30, 60, Q96).unwrap();
+    pool.liquidity = U256::from(LIQUIDITY);
+    pool.dynamic_fee_enabled = false;
+    let pool_key = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+
+    let mint = spl_token::state::Mint { decimals: 6, is_initialized: true, ..Default::default() };
+    for mint_key in [pool.token_a, pool.token_b] {
+        ledger.set(mint_key, spl_token::id(), pack(mint));
+    }
+    ledger.accounts.insert(spl_token::id(), TestAccount {
+        owner: bpf_loader::id(),
+        lamports: 1,
+        data: Vec::new(),
+        executable: true,
+    });
+    ledger.set(user, system_program::id(), Vec::new());
+
+    let (authority, _) = derive_pool_authority_address(&program_id, &pool_key);
+    let (vault_a, _) = derive_pool_vault_a_address(&program_id, &pool_key);
+    let (vault_b, _) = derive_pool_vault_b_address(&program_id, &pool_key);
+    ledger.set(vault_a, spl_token::id(), token_account(pool.token_a, authority, BALANCE));
+    ledger.set(vault_b, spl_token::id(), token_account(pool.token_b, authority, BALANCE));
+    let user_token_a = Pubkey::new_unique();
+    let user_token_b = Pubkey::new_unique();
+    ledger.set(user_token_a, spl_token::id(), token_account(pool.token_a, user, BALANCE));
+    ledger.set(user_token_b, spl_token::id(), token_account(pool.token_b, user, BALANCE));
+
+    // Initialized as InitializePool leaves it, with the initial price as the only observation
+    let mut oracle = Oracle::new(pool_key);
+    oracle.write(OracleObservation {
+        timestamp: 1_000,
+        price: pool.sqrt_price_x96,
+        tick: pool.tick,
+        liquidity: pool.liquidity,
+    });
+    let (oracle_key, _) = derive_oracle_address(&program_id, &pool_key);
+    ledger.set(oracle_key, program_id, borsh::to_vec(&oracle).unwrap());
+
+    let swap_accounts = SwapAccounts {
+        pool: pool_key,
+        tokens: PoolTokens::from_pool(&pool),
+        user_token_a,
+        user_token_b,
+        bitmap_words: vec![-1, 0],
+        ticks: vec![],
+    };
+    ledger.set(pool_key, program_id, borsh::to_vec(&pool).unwrap());
+
+    (ledger, user, oracle_key, swap_accounts)
+}
+
+fn swap_args(amount_in: u64, zero_for_one: bool) -> SwapArgs {
+    SwapArgs {
+        amount_in,
+        minimum_amount_out: 0,
+        sqrt_price_limit: 0,
+        zero_for_one,
+        max_price_impact_bps: 10_000,
+        native_sol: false,
+    }
+}
+
+#[test]
+fn test_swaps_after_growing_oracle_cardinality() {
+    let (mut ledger, user, oracle_key, accounts) = setup();
+    let program_id = ledger.program_id;
+
+    let grow = instruction::increase_observation_cardinality(&program_id, &user, &accounts.pool, 8);
+    ledger.process(&grow).unwrap();
+    let oracle = Oracle::deserialize(&mut ledger.data(&oracle_key)).unwrap();
+    assert_eq!(oracle.cardinality_next, 8);
+
+    // Default price limits in both directions, with the oracle filling up
+    for (i, zero_for_one) in [true, false, true, false].into_iter().enumerate() {
+        NOW.store(1_010 + 10 * i as i64, Ordering::SeqCst);
+        let swap = instruction::swap(&program_id, &user, &accounts, swap_args(1_000_000, zero_for_one));
+        ledger.process(&swap).unwrap();
+    }
+
+    let oracle = Oracle::deserialize(&mut ledger.data(&oracle_key)).unwrap();
+    assert_eq!(oracle.cardinality, 8);
+    assert_eq!(oracle.observation_count, 5);
+
+    // A swap far larger than the band allows stops within max_slippage_bps of the TWAP
+    NOW.store(1_100, Ordering::SeqCst);
+    let balance_before = token_balance(&ledger, &accounts.user_token_a);
+    let swap = instruction::swap(&program_id, &user, &accounts, swap_args(BALANCE / 2, true));
+    ledger.process(&swap).unwrap();
+
+    let pool = Pool::deserialize(&mut ledger.data(&accounts.pool)).unwrap();
+    let spent = balance_before - token_balance(&ledger, &accounts.user_token_a);
+    assert!(spent < BALANCE / 2);
+    assert!(pool.sqrt_price_x96 >= Q96 * U256::from(8_999u64) / U256::from(10_000u64));
+    assert!(pool.sqrt_price_x96 < Q96 * U256::from(9_100u64) / U256::from(10_000u64));
+}
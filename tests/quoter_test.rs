@@ -0,0 +1,132 @@
+use clmm_rust::math::{Quoter, SwapContext, SwapEngine};
+use clmm_rust::state::{Pool, Tick, TickBitmap};
+use clmm_rust::math::tick_math::{TickMath, I256, U256, Q96};
+use solana_program::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+#[test]
+fn test_quote_matches_executed_swap() {
+    let wide = U256::from(1_000_000_000_000_000_000u128);
+    let narrow = U256::from(500_000_000_000_000_000u128);
+    let mut pool = create_test_pool(wide + narrow);
+    let entries = [(0, wide + narrow, false), (60, narrow, true), (120, wide, true)];
+    let snapshot = build_snapshot(&entries);
+
+    let amount_in = U256::from(1_000_000_000_000_000_000u128);
+    let sqrt_price_limit = TickMath::get_sqrt_ratio_at_tick(90).unwrap();
+    let quote = Quoter::quote_exact_input(&pool, &snapshot, amount_in, false, sqrt_price_limit).unwrap();
+
+    // Quoting leaves the pool untouched
+    assert_eq!(pool.sqrt_price_x96, Q96);
+
+    let (bitmaps, mut ticks) = build_engine_state(&entries);
+    let sequence_number = pool.last_sequence_number + 1;
+    let mut ctx = SwapContext {
+        tick_bitmaps: &bitmaps,
+        ticks: &mut ticks,
+        price_history: &mut VecDeque::new(),
+        volume_history: &mut VecDeque::new(),
+        impact_history: &mut VecDeque::new(),
+        oracle_observations: &mut VecDeque::new(),
+        current_timestamp: 1,
+        sequence_number,
+    };
+    let result = SwapEngine::execute_swap(
+        &mut pool,
+        &mut ctx,
+        amount_in,
+        false,
+        sqrt_price_limit,
+        &Pubkey::new_unique(),
+    ).unwrap();
+
+    assert_eq!(quote.amount_in, result.amount_in);
+    assert_eq!(quote.amount_out, result.amount_out);
+    assert_eq!(quote.sqrt_price_after_x96, result.final_sqrt_price);
+    assert_eq!(quote.tick_after, result.final_tick);
+    assert_eq!(quote.liquidity_after, pool.liquidity);
+    assert_eq!(quote.ticks_crossed, vec![60]);
+
+    // The trace splits at the crossed tick and adds up to the totals
+    assert_eq!(quote.steps.len(), 2);
+    assert_eq!(quote.steps[0].tick_crossed, Some(60));
+    assert_eq!(quote.steps[0].liquidity, wide + narrow);
+    assert_eq!(quote.steps[1].liquidity, wide);
+    let fees: U256 = quote.steps.iter().fold(U256::zero(), |acc, step| acc + step.fee_amount);
+    assert_eq!(fees, quote.fee_amount);
+    assert!(quote.fee_amount > U256::zero());
+}
+
+#[test]
+fn test_quote_exact_output() {
+    let liquidity = U256::from(1_000_000_000_000_000_000u128);
+    let pool = create_test_pool(liquidity);
+    let snapshot = build_snapshot(&[(-60, liquidity, false), (60, liquidity, true)]);
+
+    let amount_out = U256::from(1_000_000u64);
+    let sqrt_price_limit = TickMath::get_sqrt_ratio_at_tick(-600).unwrap();
+    let quote = Quoter::quote_exact_output(&pool, &snapshot, amount_out, true, sqrt_price_limit).unwrap();
+
+    assert_eq!(quote.amount_out, amount_out);
+    assert!(quote.amount_in > amount_out);
+    assert!(quote.ticks_crossed.is_empty());
+
+    // Quoting the input back as exact input yields at least the requested output
+    let round_trip = Quoter::quote_exact_input(&pool, &snapshot, quote.amount_in, true, sqrt_price_limit).unwrap();
+    assert!(round_trip.amount_out >= amount_out);
+}
+
+#[test]
+fn test_quote_runs_out_of_liquidity() {
+    let liquidity = U256::from(1_000_000_000_000_000_000u128);
+    let pool = create_test_pool(liquidity);
+    let snapshot = build_snapshot(&[(-60, liquidity, false), (60, liquidity, true)]);
+
+    let sqrt_price_limit = TickMath::get_sqrt_ratio_at_tick(-600).unwrap();
+    let quote = Quoter::quote_exact_input(&pool, &snapshot, liquidity, true, sqrt_price_limit).unwrap();
+
+    // Crossing -60 drains the range; the price then runs to the limit for free
+    assert_eq!(quote.ticks_crossed, vec![-60]);
+    assert_eq!(quote.liquidity_after, U256::zero());
+    assert_eq!(quote.sqrt_price_after_x96, sqrt_price_limit);
+    assert!(quote.amount_in < liquidity);
+
+    // An invalid price limit is rejected
+    assert!(Quoter::quote_exact_input(&pool, &snapshot, liquidity, true, pool.sqrt_price_x96).is_err());
+}
+
+fn create_test_pool(liquidity: U256) -> Pool {
+    let mut pool = Pool::new(Pubkey::new_unique(), Pubkey::new_unique(), 300, 60, Q96).unwrap();
+    pool.liquidity = liquidity;
+    pool.dynamic_fee_enabled = false;
+    pool
+}
+
+fn build_tick(index: i32, liquidity: U256, upper: bool) -> Tick {
+    let mut tick = Tick::new(index);
+    tick.update_liquidity(I256(liquidity.0), upper).unwrap();
+    tick
+}
+
+/// Tick snapshot as an off-chain service would hold it
+fn build_snapshot(entries: &[(i32, U256, bool)]) -> BTreeMap<i32, Tick> {
+    entries
+        .iter()
+        .map(|&(index, liquidity, upper)| (index, build_tick(index, liquidity, upper)))
+        .collect()
+}
+
+/// The same ticks in the bitmap-plus-map form the swap engine reads
+fn build_engine_state(entries: &[(i32, U256, bool)]) -> (BTreeMap<i16, TickBitmap>, HashMap<i32, Tick>) {
+    let mut bitmaps = BTreeMap::new();
+    let mut ticks = HashMap::new();
+    for &(index, liquidity, upper) in entries {
+        let (word_position, _) = TickBitmap::position(index, 60);
+        bitmaps
+            .entry(word_position)
+            .or_insert_with(|| TickBitmap::new(word_position))
+            .flip_tick(index, 60);
+        ticks.insert(index, build_tick(index, liquidity, upper));
+    }
+    (bitmaps, ticks)
+}
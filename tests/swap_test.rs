@@ -1,28 +1,314 @@
-use clmm_rust::math::{SwapEngine, PriceImpactCalculator, MultiHopRouter};
-use clmm_rust::state::{Pool, Position};
-use clmm_rust::math::tick_math::U256;
+use clmm_rust::math::{SwapEngine, SwapContext, PriceImpactCalculator, MultiHopRouter, SwapResult};
+use clmm_rust::state::{Pool, Tick, TickBitmap};
+use clmm_rust::math::tick_math::{TickMath, I256, U256, Q96, MAX_SQRT_RATIO};
+use clmm_rust::error::CLMMError;
+use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 #[test]
 fn test_basic_swap_functionality() {
     let mut pool = create_test_pool();
+    let initial_sqrt_price = pool.sqrt_price_x96;
     let amount_in = U256([1000, 0, 0, 0]);
-    let sqrt_price_limit = U256::MAX;
-    let user = Pubkey::new_unique();
+    let sqrt_price_limit = TickMath::get_sqrt_ratio_at_tick(-600).unwrap();
 
     // Test swap execution
-    let swap_result = SwapEngine::execute_swap(
+    let swap_result = run_swap(
         &mut pool,
+        &BTreeMap::new(),
+        &mut HashMap::new(),
         amount_in,
         true, // zero_for_one
         sqrt_price_limit,
-        &user,
     ).unwrap();
 
     assert!(swap_result.amount_in > U256::zero());
     assert!(swap_result.amount_out > U256::zero());
-    assert!(swap_result.price_impact >= 0 && swap_result.price_impact <= 10000);
-    assert!(swap_result.final_sqrt_price != pool.sqrt_price_x96); // Price should change
+    assert!(swap_result.price_impact <= 10000);
+    assert!(swap_result.final_sqrt_price < initial_sqrt_price); // Price should move down
+    assert_eq!(swap_result.final_sqrt_price, pool.sqrt_price_x96);
+}
+
+#[test]
+fn test_swap_crosses_initialized_tick() {
+    let mut pool = create_test_pool();
+    let wide_liquidity = U256::from(1_000_000_000_000_000_000u128);
+    let narrow_liquidity = U256::from(500_000_000_000_000_000u128);
+
+    // Two ranges starting at the current tick: [0, 120] and [0, 60]
+    let (bitmap, mut ticks) = build_ticks(&[
+        (0, wide_liquidity + narrow_liquidity, false),
+        (60, narrow_liquidity, true),
+        (120, wide_liquidity, true),
+    ]);
+    pool.liquidity = wide_liquidity + narrow_liquidity;
+
+    // Push the price past tick 60 but stop short of tick 120
+    let sqrt_price_limit = TickMath::get_sqrt_ratio_at_tick(90).unwrap();
+    let swap_result = run_swap(
+        &mut pool,
+        &bitmap,
+        &mut ticks,
+        U256::from(1_000_000_000_000_000_000u128),
+        false,
+        sqrt_price_limit,
+    ).unwrap();
+
+    // The narrow range is left behind, only the wide one stays active
+    assert_eq!(swap_result.final_sqrt_price, sqrt_price_limit);
+    assert_eq!(pool.liquidity, wide_liquidity);
+    assert!(pool.tick >= 60 && pool.tick < 120);
+
+    // Fee growth outside tick 60 flipped to the global value at the time of crossing
+    let crossed = &ticks[&60];
+    assert!(crossed.fee_growth_outside1_x128 > U256::zero());
+    assert!(crossed.fee_growth_outside1_x128 < pool.fee_growth_global1_x128);
+    assert_eq!(crossed.fee_growth_outside0_x128, U256::zero());
+
+    // Ticks that were not crossed are untouched
+    assert_eq!(ticks[&120].fee_growth_outside1_x128, U256::zero());
+}
+
+#[test]
+fn test_swap_fills_range_order_it_crosses() {
+    let mut pool = create_test_pool();
+    let wide_liquidity = U256::from(1_000_000_000_000_000_000u128);
+    let order_liquidity = U256::from(500_000_000_000_000_000u128);
+
+    // A wide range [0, 600] and a token0 range order on [60, 120] completing at tick 120
+    let (bitmap, mut ticks) = build_ticks(&[
+        (0, wide_liquidity, false),
+        (60, order_liquidity, false),
+        (120, order_liquidity, true),
+        (600, wide_liquidity, true),
+    ]);
+    ticks.get_mut(&120).unwrap().add_range_order(order_liquidity, true).unwrap();
+    pool.liquidity = wide_liquidity;
+
+    // Push the price through the whole order
+    run_swap(
+        &mut pool,
+        &bitmap,
+        &mut ticks,
+        U256::from(1_000_000_000_000_000_000u128),
+        false,
+        TickMath::get_sqrt_ratio_at_tick(180).unwrap(),
+    ).unwrap();
+
+    let completion = &ticks[&120];
+    assert_eq!(completion.range_order_epoch, 1);
+    assert!(completion.range_order_liquidity.is_zero());
+    assert_eq!(completion.range_order_filled_liquidity, order_liquidity);
+    assert!(completion.range_order_fee_growth_inside1_x128 > U256::zero());
+
+    // The order's net liquidity is gone from both ticks, the gross stays until settlement
+    assert!(ticks[&60].liquidity_net.is_zero());
+    assert!(completion.liquidity_net.is_zero());
+    assert_eq!(ticks[&60].liquidity_gross, order_liquidity);
+    assert_eq!(pool.liquidity, wide_liquidity);
+
+    // Moving back down through the range does not re-activate the filled order
+    run_swap(
+        &mut pool,
+        &bitmap,
+        &mut ticks,
+        U256::from(1_000_000_000_000_000_000u128),
+        true,
+        TickMath::get_sqrt_ratio_at_tick(30).unwrap(),
+    ).unwrap();
+
+    assert!(pool.tick < 60);
+    assert_eq!(pool.liquidity, wide_liquidity);
+    assert_eq!(ticks[&120].range_order_epoch, 1);
+}
+
+#[test]
+fn test_swap_stops_at_word_boundary_without_liquidity() {
+    let mut pool = create_test_pool();
+    let liquidity = U256::from(1_000_000_000_000_000_000u128);
+
+    // A single range [0, 60]: crossing 60 leaves the pool without liquidity
+    let (bitmap, mut ticks) = build_ticks(&[(0, liquidity, false), (60, liquidity, true)]);
+    pool.liquidity = liquidity;
+
+    let sqrt_price_limit = TickMath::get_sqrt_ratio_at_tick(600).unwrap();
+    let swap_result = run_swap(
+        &mut pool,
+        &bitmap,
+        &mut ticks,
+        U256::from(1_000_000_000_000_000_000u128),
+        false,
+        sqrt_price_limit,
+    ).unwrap();
+
+    // Only the input needed to reach tick 60 is consumed; the price then runs freely
+    assert_eq!(pool.liquidity, U256::zero());
+    assert_eq!(swap_result.final_sqrt_price, sqrt_price_limit);
+    assert!(swap_result.amount_in < U256::from(1_000_000_000_000_000_000u128));
+}
+
+#[test]
+fn test_swap_crosses_tick_in_lower_word() {
+    let mut pool = create_test_pool();
+    let liquidity = U256::from(1_000_000_000_000_000_000u128);
+
+    // Range [-15420, 60]: the lower tick sits two words below the current one
+    let (bitmap, mut ticks) = build_ticks(&[(-15420, liquidity, false), (60, liquidity, true)]);
+    assert!(bitmap.contains_key(&-2) && bitmap.contains_key(&0));
+    pool.liquidity = liquidity;
+
+    let sqrt_price_limit = TickMath::get_sqrt_ratio_at_tick(-16000).unwrap();
+    let swap_result = run_swap(
+        &mut pool,
+        &bitmap,
+        &mut ticks,
+        U256::from(10_000_000_000_000_000_000u128),
+        true,
+        sqrt_price_limit,
+    ).unwrap();
+
+    // Crossing -15420 downwards drops the range out of the active liquidity
+    assert_eq!(swap_result.final_sqrt_price, sqrt_price_limit);
+    assert_eq!(pool.liquidity, U256::zero());
+    assert!(ticks[&-15420].fee_growth_outside0_x128 > U256::zero());
+    assert_eq!(ticks[&60].fee_growth_outside0_x128, U256::zero());
+}
+
+#[test]
+fn test_swap_accrues_protocol_fee() {
+    let amount_in = U256::from(1_000_000_000_000u64);
+    let sqrt_price_limit = TickMath::get_sqrt_ratio_at_tick(-600).unwrap();
+
+    let mut lp_only = create_test_pool();
+    run_swap(&mut lp_only, &BTreeMap::new(), &mut HashMap::new(), amount_in, true, sqrt_price_limit).unwrap();
+    assert_eq!(lp_only.protocol_fees_token0, U256::zero());
+
+    let mut with_protocol = create_test_pool();
+    with_protocol.protocol_fee_percent = 20;
+    run_swap(&mut with_protocol, &BTreeMap::new(), &mut HashMap::new(), amount_in, true, sqrt_price_limit).unwrap();
+
+    // The protocol keeps a fifth of the input-token fee, LPs get the rest
+    let total_fee = amount_in * U256::from(300u32) / U256::from(10000u32);
+    assert!(with_protocol.protocol_fees_token0 > U256::zero());
+    assert!(with_protocol.protocol_fees_token0 <= total_fee / U256::from(5u32));
+    assert_eq!(with_protocol.protocol_fees_token1, U256::zero());
+    assert!(with_protocol.fee_growth_global0_x128 < lp_only.fee_growth_global0_x128);
+    assert_eq!(with_protocol.sqrt_price_x96, lp_only.sqrt_price_x96);
+}
+
+#[test]
+fn test_accrue_fee_credits_lps_and_protocol() {
+    let mut pool = create_test_pool();
+    pool.protocol_fee_percent = 10;
+    let liquidity = pool.liquidity;
+
+    SwapEngine::accrue_fee(&mut pool, U256::from(1_000u64), false).unwrap();
+
+    // A tenth goes to the protocol, the rest grows token1 fees per unit of liquidity
+    assert_eq!(pool.protocol_fees_token1, U256::from(100u64));
+    assert_eq!(pool.protocol_fees_token0, U256::zero());
+    assert_eq!(pool.fee_growth_global0_x128, U256::zero());
+    assert_eq!(pool.fee_growth_global1_x128, (U256::from(900u64) << 128) / liquidity);
+}
+
+#[test]
+fn test_exact_output_swap() {
+    let mut pool = create_test_pool();
+    let amount_out = U256::from(1_000_000u64);
+    let sqrt_price_limit = TickMath::get_sqrt_ratio_at_tick(-600).unwrap();
+
+    let swap_result = run_swap_exact_out(
+        &mut pool.clone(),
+        &BTreeMap::new(),
+        &mut HashMap::new(),
+        amount_out,
+        true,
+        sqrt_price_limit,
+    ).unwrap();
+
+    // Exactly the requested output, paid for with input plus the 0.3% fee
+    assert_eq!(swap_result.amount_out, amount_out);
+    assert!(swap_result.amount_in > amount_out);
+
+    // Spending that input as an exact-input swap yields at least the same output
+    let exact_in_result = run_swap(
+        &mut pool,
+        &BTreeMap::new(),
+        &mut HashMap::new(),
+        swap_result.amount_in,
+        true,
+        sqrt_price_limit,
+    ).unwrap();
+    assert!(exact_in_result.amount_out >= amount_out);
+}
+
+#[test]
+fn test_exact_output_swap_crosses_initialized_tick() {
+    let mut pool = create_test_pool();
+    let wide_liquidity = U256::from(1_000_000_000_000_000_000u128);
+    let narrow_liquidity = U256::from(500_000_000_000_000_000u128);
+
+    let (bitmap, mut ticks) = build_ticks(&[
+        (0, wide_liquidity + narrow_liquidity, false),
+        (60, narrow_liquidity, true),
+        (120, wide_liquidity, true),
+    ]);
+    pool.liquidity = wide_liquidity + narrow_liquidity;
+
+    // More token0 than the [0, 60] segment holds, so tick 60 must be crossed
+    let amount_out = U256::from(5_000_000_000_000_000u128);
+    let swap_result = run_swap_exact_out(
+        &mut pool,
+        &bitmap,
+        &mut ticks,
+        amount_out,
+        false,
+        TickMath::get_sqrt_ratio_at_tick(600).unwrap(),
+    ).unwrap();
+
+    assert_eq!(swap_result.amount_out, amount_out);
+    assert_eq!(pool.liquidity, wide_liquidity);
+    assert!(pool.tick >= 60 && pool.tick < 120);
+    assert!(ticks[&60].fee_growth_outside1_x128 > U256::zero());
+
+    // The reported impact is the price actually moved, past tick 60 (about 0.6%)
+    let expected_impact = SwapEngine::realized_price_impact(Q96, swap_result.final_sqrt_price);
+    assert_eq!(swap_result.price_impact, expected_impact);
+    assert!(swap_result.price_impact > 60 && swap_result.price_impact < 130);
+}
+
+#[test]
+fn test_estimate_swap_input_prices_the_output_token() {
+    let mut pool = create_test_pool();
+    pool.sqrt_price_x96 = Q96 * U256::from(2u64); // price 4
+    let amount_out = U256::from(1_000_000u64);
+
+    // Token0 out is paid for in token1 at the price, and the reverse, plus the 3% fee
+    let token1_in = SwapEngine::estimate_swap_input(&pool, amount_out, false).unwrap();
+    let token0_in = SwapEngine::estimate_swap_input(&pool, amount_out, true).unwrap();
+    assert_eq!(token1_in, U256::from(4_123_712u64));
+    assert_eq!(token0_in, U256::from(257_732u64));
+}
+
+#[test]
+fn test_exact_output_swap_stops_at_price_limit() {
+    let mut pool = create_test_pool();
+    let sqrt_price_limit = TickMath::get_sqrt_ratio_at_tick(-10).unwrap();
+
+    // Far more output than the pool can give before the limit
+    let amount_out = U256::from(1_000_000_000_000_000_000u128);
+    let swap_result = run_swap_exact_out(
+        &mut pool,
+        &BTreeMap::new(),
+        &mut HashMap::new(),
+        amount_out,
+        true,
+        sqrt_price_limit,
+    ).unwrap();
+
+    assert_eq!(swap_result.final_sqrt_price, sqrt_price_limit);
+    assert!(swap_result.amount_out < amount_out);
 }
 
 #[test]
@@ -84,29 +370,159 @@ fn test_swap_output_estimation() {
 fn test_price_limit_validation() {
     let pool = create_test_pool();
     let amount_in = U256([1000, 0, 0, 0]);
-    let sqrt_price_limit = pool.sqrt_price_x96; // Same as current price
+    let bitmap = BTreeMap::new();
+
+    // A limit equal to the current price leaves no room to move
+    let result = run_swap(
+        &mut pool.clone(),
+        &bitmap,
+        &mut HashMap::new(),
+        amount_in,
+        true,
+        pool.sqrt_price_x96,
+    );
+    assert!(result.is_err());
 
-    let user = Pubkey::new_unique();
+    // A limit on the wrong side of the current price is rejected
+    let result = run_swap(
+        &mut pool.clone(),
+        &bitmap,
+        &mut HashMap::new(),
+        amount_in,
+        true,
+        TickMath::get_sqrt_ratio_at_tick(600).unwrap(),
+    );
+    assert!(result.is_err());
 
-    // This should work since limit equals current price
-    let result = SwapEngine::execute_swap(
+    // The absolute maximum price is out of range
+    let result = run_swap(
         &mut pool.clone(),
+        &bitmap,
+        &mut HashMap::new(),
         amount_in,
+        false,
+        MAX_SQRT_RATIO,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_paused_pool_rejects_swaps() {
+    let mut pool = create_test_pool();
+    pool.unlocked = false;
+
+    let result = run_swap(
+        &mut pool,
+        &BTreeMap::new(),
+        &mut HashMap::new(),
+        U256::from(1000u64),
         true,
-        sqrt_price_limit,
-        &user,
+        TickMath::get_sqrt_ratio_at_tick(-600).unwrap(),
     );
+    assert_eq!(result.unwrap_err(), CLMMError::PoolLocked.into());
+}
+
+#[test]
+fn test_swaps_rejected_during_flash_loan() {
+    let mut pool = create_test_pool();
+    pool.flash_in_progress = true;
+    assert!(pool.unlocked);
 
-    // Should either succeed or fail with a specific error, not panic
-    assert!(result.is_ok() || result.is_err());
+    let result = run_swap(
+        &mut pool,
+        &BTreeMap::new(),
+        &mut HashMap::new(),
+        U256::from(1000u64),
+        true,
+        TickMath::get_sqrt_ratio_at_tick(-600).unwrap(),
+    );
+    assert_eq!(result.unwrap_err(), CLMMError::PoolLocked.into());
 }
 
 fn create_test_pool() -> Pool {
     let token_a = Pubkey::new_unique();
     let token_b = Pubkey::new_unique();
-    let initial_price = U256([1000000000000000000000000, 0, 0, 0]); // 1e21
+    let initial_price = Q96; // price 1.0, tick 0
 
-    Pool::new(token_a, token_b, 300, 60, initial_price).unwrap()
+    let mut pool = Pool::new(token_a, token_b, 300, 60, initial_price).unwrap();
+    pool.liquidity = U256::from(1_000_000_000_000_000_000u128);
+    pool.dynamic_fee_enabled = false;
+    pool
+}
+
+/// Build bitmap words and a tick map from `(tick, liquidity, upper)` entries
+fn build_ticks(entries: &[(i32, U256, bool)]) -> (BTreeMap<i16, TickBitmap>, HashMap<i32, Tick>) {
+    let mut bitmap = BTreeMap::new();
+    let mut ticks = HashMap::new();
+    for &(index, liquidity, upper) in entries {
+        let mut tick = Tick::new(index);
+        tick.update_liquidity(I256(liquidity.0), upper).unwrap();
+        let (word_position, _) = TickBitmap::position(index, 60);
+        bitmap
+            .entry(word_position)
+            .or_insert_with(|| TickBitmap::new(word_position))
+            .flip_tick(index, 60);
+        ticks.insert(index, tick);
+    }
+    (bitmap, ticks)
+}
+
+fn run_swap_exact_out(
+    pool: &mut Pool,
+    bitmap: &BTreeMap<i16, TickBitmap>,
+    ticks: &mut HashMap<i32, Tick>,
+    amount_out: U256,
+    zero_for_one: bool,
+    sqrt_price_limit: U256,
+) -> Result<SwapResult, ProgramError> {
+    let sequence_number = pool.last_sequence_number + 1;
+    let mut ctx = SwapContext {
+        tick_bitmaps: bitmap,
+        ticks,
+        price_history: &mut VecDeque::new(),
+        volume_history: &mut VecDeque::new(),
+        impact_history: &mut VecDeque::new(),
+        oracle_observations: &mut VecDeque::new(),
+        current_timestamp: 1,
+        sequence_number,
+    };
+    SwapEngine::execute_swap_exact_out(
+        pool,
+        &mut ctx,
+        amount_out,
+        zero_for_one,
+        sqrt_price_limit,
+        &Pubkey::new_unique(),
+    )
+}
+
+fn run_swap(
+    pool: &mut Pool,
+    bitmap: &BTreeMap<i16, TickBitmap>,
+    ticks: &mut HashMap<i32, Tick>,
+    amount_in: U256,
+    zero_for_one: bool,
+    sqrt_price_limit: U256,
+) -> Result<SwapResult, ProgramError> {
+    let sequence_number = pool.last_sequence_number + 1;
+    let mut ctx = SwapContext {
+        tick_bitmaps: bitmap,
+        ticks,
+        price_history: &mut VecDeque::new(),
+        volume_history: &mut VecDeque::new(),
+        impact_history: &mut VecDeque::new(),
+        oracle_observations: &mut VecDeque::new(),
+        current_timestamp: 1,
+        sequence_number,
+    };
+    SwapEngine::execute_swap(
+        pool,
+        &mut ctx,
+        amount_in,
+        zero_for_one,
+        sqrt_price_limit,
+        &Pubkey::new_unique(),
+    )
 }
 
 // Tests from src/math/swap.rs
@@ -119,7 +535,7 @@ fn test_swap_engine_creation() {
 
 #[test]
 fn test_price_impact_calculation_swap_math() {
-    let mut pool = create_test_pool();
+    let pool = create_test_pool();
     let amount_in = U256([1000, 0, 0, 0]);
     let price_impact = SwapEngine::calculate_price_impact(&pool, amount_in, true).unwrap();
 
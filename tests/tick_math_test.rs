@@ -1,4 +1,5 @@
-use clmm_rust::math::tick_math::{TickMath, MIN_TICK, MAX_TICK, U256_ZERO};
+use borsh::BorshDeserialize;
+use clmm_rust::math::tick_math::{TickMath, I256, U256, MIN_TICK, MAX_TICK, MIN_SQRT_RATIO, MAX_SQRT_RATIO, U256_ZERO, U256_ONE};
 
 #[test]
 fn test_get_sqrt_ratio_at_tick() {
@@ -15,3 +16,63 @@ fn test_tick_math_bounds() {
     assert!(TickMath::get_sqrt_ratio_at_tick(MIN_TICK - 1).is_err());
     assert!(TickMath::get_sqrt_ratio_at_tick(MAX_TICK + 1).is_err());
 }
+
+#[test]
+fn test_get_tick_at_sqrt_ratio_inverts_each_tick() {
+    let ticks = (MIN_TICK..=MAX_TICK).step_by(7919).chain([MIN_TICK + 1, -1, 0, 1, MAX_TICK - 1]);
+    for tick in ticks {
+        let sqrt_price = TickMath::get_sqrt_ratio_at_tick(tick).unwrap();
+        assert_eq!(TickMath::get_tick_at_sqrt_ratio(sqrt_price).unwrap(), tick);
+        if tick > MIN_TICK {
+            // Just below a tick's price belongs to the tick beneath it
+            assert_eq!(TickMath::get_tick_at_sqrt_ratio(sqrt_price - U256_ONE).unwrap(), tick - 1);
+        }
+    }
+
+    assert_eq!(TickMath::get_tick_at_sqrt_ratio(MAX_SQRT_RATIO - U256_ONE).unwrap(), MAX_TICK - 1);
+    assert!(TickMath::get_tick_at_sqrt_ratio(MIN_SQRT_RATIO - U256_ONE).is_err());
+    assert!(TickMath::get_tick_at_sqrt_ratio(MAX_SQRT_RATIO).is_err());
+}
+
+#[test]
+fn test_i256_signed_arithmetic() {
+    let a = I256::from(-1000i128);
+    let b = I256::from(400i128);
+
+    assert!(a.is_negative());
+    assert!(a < b);
+    assert_eq!(a.checked_add(b), Some(I256::from(-600i128)));
+    assert_eq!(b.checked_sub(a), Some(I256::from(1400i128)));
+    assert_eq!(a.checked_neg(), Some(I256::from(1000i128)));
+    assert_eq!(a.unsigned_abs(), U256::from(1000u64));
+    assert_eq!(a.to_string(), "-1000");
+}
+
+#[test]
+fn test_i256_overflow_is_checked() {
+    assert!(I256::MAX.checked_add(I256::one()).is_none());
+    assert!(I256::MIN.checked_sub(I256::one()).is_none());
+    assert!(I256::MIN.checked_neg().is_none());
+    assert_eq!(I256::MIN.unsigned_abs(), U256_ONE << 255);
+    assert_eq!(I256::MAX.wrapping_add(I256::one()), I256::MIN);
+}
+
+#[test]
+fn test_i256_conversions() {
+    assert_eq!(i128::try_from(I256::from(i128::MIN)).unwrap(), i128::MIN);
+    assert_eq!(i128::try_from(I256::from(-7i128)).unwrap(), -7);
+    assert!(i128::try_from(I256::MAX).is_err());
+
+    let unsigned = U256::from(123456789u64);
+    assert_eq!(U256::try_from(I256::try_from(unsigned).unwrap()).unwrap(), unsigned);
+    assert!(I256::try_from(U256::MAX).is_err());
+    assert!(U256::try_from(I256::from(-1i128)).is_err());
+}
+
+#[test]
+fn test_i256_borsh_roundtrip() {
+    let value = I256::from(-42i128);
+    let bytes = borsh::to_vec(&value).unwrap();
+    assert_eq!(bytes.len(), 32);
+    assert_eq!(I256::try_from_slice(&bytes).unwrap(), value);
+}
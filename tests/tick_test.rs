@@ -1,5 +1,8 @@
-use clmm_rust::state::{Tick, TickBitmap, TickInfo};
+use clmm_rust::error::CLMMError;
+use clmm_rust::state::{Tick, TickBitmap, TickInfo, TICK_BITMAP_ACCOUNT_SIZE};
 use clmm_rust::math::tick_math::{U256, I256};
+use borsh::BorshDeserialize;
+use std::collections::BTreeMap;
 
 #[test]
 fn test_tick_creation() {
@@ -18,16 +21,46 @@ fn test_tick_creation() {
 fn test_tick_liquidity_update() {
     let mut tick = Tick::new(100);
 
-    tick.update_liquidity(I256([1000, 0, 0, 0]), true);
+    tick.update_liquidity(I256([1000, 0, 0, 0]), true).unwrap();
     assert!(tick.initialized);
     assert_eq!(tick.liquidity_net, I256([1000, 0, 0, 0]));
     assert_eq!(tick.liquidity_gross, U256([1000, 0, 0, 0]));
 
-    tick.update_liquidity(I256([500, 0, 0, 0]), false);
+    tick.update_liquidity(I256([500, 0, 0, 0]), false).unwrap();
     assert_eq!(tick.liquidity_net, I256([500, 0, 0, 0]));
     assert_eq!(tick.liquidity_gross, U256([1500, 0, 0, 0]));
 }
 
+#[test]
+fn test_tick_liquidity_net_goes_negative() {
+    let mut tick = Tick::new(-60);
+
+    // A lower tick carries the negated liquidity of its position
+    tick.update_liquidity(I256::from(1000i128), false).unwrap();
+    assert_eq!(tick.liquidity_net, I256::from(-1000i128));
+    assert_eq!(tick.liquidity_gross, U256::from(1000u64));
+
+    // Removing the liquidity passes a negative delta and restores both sides
+    tick.update_liquidity(I256::from(-1000i128), false).unwrap();
+    assert!(tick.liquidity_net.is_zero());
+    assert!(tick.liquidity_gross.is_zero());
+}
+
+#[test]
+fn test_tick_liquidity_overflow_is_an_error() {
+    let mut tick = Tick::new(60);
+    tick.update_liquidity(I256::MAX, true).unwrap();
+
+    // Neither total can absorb another unit; the tick is left as it was
+    assert!(matches!(tick.update_liquidity(I256::one(), true), Err(CLMMError::MathOverflow)));
+    assert_eq!(tick.liquidity_net, I256::MAX);
+    assert_eq!(tick.liquidity_gross, I256::MAX.unsigned_abs());
+
+    // Removing more than the tick holds fails the same way
+    let mut empty = Tick::new(60);
+    assert!(matches!(empty.update_liquidity(I256::from(-1i128), false), Err(CLMMError::MathOverflow)));
+}
+
 #[test]
 fn test_tick_bitmap() {
     let mut bitmap = TickBitmap::new(0);
@@ -38,6 +71,91 @@ fn test_tick_bitmap() {
 
     bitmap.clear_bit(5);
     assert!(!bitmap.is_bit_set(5));
+
+    // One bit per compressed tick in the word, packed into the account
+    bitmap.set_bit(255);
+    assert!(bitmap.is_bit_set(255));
+    let data = borsh::to_vec(&bitmap).unwrap();
+    assert_eq!(data.len(), TICK_BITMAP_ACCOUNT_SIZE);
+    assert!(TickBitmap::deserialize(&mut &data[..]).unwrap().is_bit_set(255));
+}
+
+#[test]
+fn test_tick_bitmap_search_across_words() {
+    let spacing = 60;
+    let mut words = BTreeMap::new();
+    for tick in [-15420, 120] {
+        let (word_position, _) = TickBitmap::position(tick, spacing);
+        words
+            .entry(word_position)
+            .or_insert_with(|| TickBitmap::new(word_position))
+            .flip_tick(tick, spacing);
+    }
+    assert_eq!(TickBitmap::position(-15420, spacing), (-2, 255));
+    assert_eq!(TickBitmap::position(120, spacing), (0, 2));
+
+    // Searching down skips the empty word -1
+    assert_eq!(TickBitmap::next_initialized_tick_in_words(&words, 0, spacing, true), Some(-15420));
+    assert_eq!(TickBitmap::next_initialized_tick_in_words(&words, -15420, spacing, false), Some(120));
+    assert_eq!(TickBitmap::next_initialized_tick_in_words(&words, -16000, spacing, true), None);
+
+    // Flipping the same tick again clears it
+    words.get_mut(&0).unwrap().flip_tick(120, spacing);
+    assert!(words[&0].is_empty());
+    assert_eq!(TickBitmap::next_initialized_tick_in_words(&words, 0, spacing, false), None);
+}
+
+#[test]
+fn test_fee_growth_inside() {
+    let global = U256::from(1000u64);
+    let mut lower = Tick::new_initialized(-60);
+    let mut upper = Tick::new_initialized(60);
+    lower.update_fee_growth_outside(U256::from(100u64), U256::from(10u64));
+    upper.update_fee_growth_outside(U256::from(300u64), U256::from(30u64));
+
+    // Price inside the range: everything not below or above
+    let (inside_0, inside_1) = Tick::fee_growth_inside(&lower, &upper, 0, global, global);
+    assert_eq!(inside_0, U256::from(600u64));
+    assert_eq!(inside_1, U256::from(960u64));
+
+    // Price below the range: each outside value is the growth above its tick
+    lower.update_fee_growth_outside(U256::from(300u64), U256::zero());
+    upper.update_fee_growth_outside(U256::from(100u64), U256::zero());
+    let (inside_0, _) = Tick::fee_growth_inside(&lower, &upper, -120, global, global);
+    assert_eq!(inside_0, U256::from(200u64));
+
+    // Price above the range: each outside value is the growth below its tick
+    lower.update_fee_growth_outside(U256::from(100u64), U256::zero());
+    upper.update_fee_growth_outside(U256::from(300u64), U256::zero());
+    let (inside_0, _) = Tick::fee_growth_inside(&lower, &upper, 120, global, global);
+    assert_eq!(inside_0, U256::from(200u64));
+}
+
+#[test]
+fn test_range_order_lifecycle() {
+    let mut tick = Tick::new(120);
+    tick.update_liquidity(I256::from(1000i128), true).unwrap();
+
+    assert_eq!(tick.add_range_order(U256::from(1000u64), true), Ok(0));
+    assert!(!tick.is_range_order_filled(0));
+
+    // Filling moves the open liquidity aside and starts a new epoch
+    let filled = tick.fill_range_orders(U256::from(5u64), U256::from(7u64));
+    assert_eq!(filled, U256::from(1000u64));
+    assert!(tick.is_range_order_filled(0));
+    assert_eq!(tick.range_order_fee_growth_inside1_x128, U256::from(7u64));
+
+    // New orders wait until the filled ones are settled
+    assert!(tick.add_range_order(U256::from(10u64), true).is_err());
+
+    tick.settle_range_order(filled);
+    assert!(tick.remove_gross_liquidity(filled));
+    assert!(!tick.initialized);
+    assert_eq!(tick.add_range_order(U256::from(10u64), false), Ok(1));
+
+    // The round trip keeps every range order field
+    let decoded = Tick::try_from_slice(&borsh::to_vec(&tick).unwrap()).unwrap();
+    assert_eq!(decoded, tick);
 }
 
 #[test]
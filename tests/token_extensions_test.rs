@@ -0,0 +1,80 @@
+use clmm_rust::utils::{
+    assert_supported_mint, parse_transfer_fee, vault_account_size, TransferFee,
+    TOKEN_ACCOUNT_SIZE,
+};
+
+/// Token-2022 mint data: base mint padded to the account size, then the TLV extensions
+fn mint_with_extensions(extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
+    let mut data = vec![0u8; TOKEN_ACCOUNT_SIZE];
+    data.push(1); // AccountType::Mint
+    for (extension_type, value) in extensions {
+        data.extend_from_slice(&extension_type.to_le_bytes());
+        data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        data.extend_from_slice(value);
+    }
+    data
+}
+
+fn transfer_fee_config(older: TransferFee, newer: TransferFee) -> Vec<u8> {
+    let mut config = vec![0u8; 72];
+    for fee in [older, newer] {
+        config.extend_from_slice(&fee.epoch.to_le_bytes());
+        config.extend_from_slice(&fee.maximum_fee.to_le_bytes());
+        config.extend_from_slice(&fee.basis_points.to_le_bytes());
+    }
+    config
+}
+
+#[test]
+fn test_transfer_fee_round_trip() {
+    let fee = TransferFee { epoch: 0, maximum_fee: u64::MAX, basis_points: 150 };
+
+    assert_eq!(fee.calculate_fee(0), 0);
+    assert_eq!(fee.calculate_fee(1), 1);
+    assert_eq!(fee.calculate_fee(10_000), 150);
+    assert_eq!(fee.calculate_fee(10_001), 151);
+
+    // Sending the pre-fee amount always delivers at least the requested amount
+    for amount in [1u64, 7, 999, 10_000, 123_456_789] {
+        let gross = fee.calculate_pre_fee_amount(amount).unwrap();
+        assert!(gross - fee.calculate_fee(gross) >= amount);
+        assert!(gross - 1 - fee.calculate_fee(gross - 1) < amount);
+    }
+
+    // The maximum fee caps large transfers
+    let capped = TransferFee { epoch: 0, maximum_fee: 50, basis_points: 150 };
+    assert_eq!(capped.calculate_fee(1_000_000), 50);
+    assert_eq!(capped.calculate_pre_fee_amount(1_000_000), Some(1_000_050));
+    assert_eq!(TransferFee::default().calculate_pre_fee_amount(42), Some(42));
+}
+
+#[test]
+fn test_parse_transfer_fee_by_epoch() {
+    let older = TransferFee { epoch: 0, maximum_fee: 1_000, basis_points: 100 };
+    let newer = TransferFee { epoch: 10, maximum_fee: 2_000, basis_points: 250 };
+    let mint = mint_with_extensions(&[(1, transfer_fee_config(older, newer))]);
+
+    assert_eq!(parse_transfer_fee(&mint, 9).unwrap(), Some(older));
+    assert_eq!(parse_transfer_fee(&mint, 10).unwrap(), Some(newer));
+
+    // Classic SPL Token mints carry no extensions
+    assert_eq!(parse_transfer_fee(&[0u8; 82], 10).unwrap(), None);
+    assert_eq!(vault_account_size(&[0u8; 82]).unwrap(), TOKEN_ACCOUNT_SIZE);
+    assert!(vault_account_size(&mint).unwrap() > TOKEN_ACCOUNT_SIZE);
+}
+
+#[test]
+fn test_unsupported_mint_extensions_rejected() {
+    let fee = TransferFee::default();
+    let with_fee = mint_with_extensions(&[(1, transfer_fee_config(fee, fee)), (3, vec![0u8; 32])]);
+    assert!(assert_supported_mint(&with_fee).is_ok());
+
+    // Transfer hooks (14) and permanent delegates (12) could block or drain the vaults
+    assert!(assert_supported_mint(&mint_with_extensions(&[(14, vec![0u8; 64])])).is_err());
+    assert!(assert_supported_mint(&mint_with_extensions(&[(12, vec![0u8; 32])])).is_err());
+
+    // Truncated extension data is invalid
+    let mut truncated = mint_with_extensions(&[(3, vec![0u8; 32])]);
+    truncated.truncate(truncated.len() - 1);
+    assert!(assert_supported_mint(&truncated).is_err());
+}